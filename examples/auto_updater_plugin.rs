@@ -17,6 +17,7 @@
 //! - Download progress tracking
 //! - SHA256 verification
 //! - Installation flow
+//! - The update prompt rendered by the plugin itself
 //!
 //! To run this example:
 //! ```sh
@@ -28,7 +29,9 @@ use iced::{Element, Fill, Length, Subscription, Task};
 use iced_auto_updater_plugin::{
     AutoUpdaterInput, AutoUpdaterOutput, AutoUpdaterPlugin, ReleaseInfo, UpdaterConfig,
 };
-use iced_plugins::{PluginHandle, PluginManager, PluginManagerBuilder, PluginMessage};
+use iced_plugins::{
+    Capability, OutputBuffer, PluginHandle, PluginManager, PluginManagerBuilder, PluginMessage,
+};
 
 const APP_NAME: &str = "auto_updater_example";
 const CURRENT_VERSION: &str = "0.1.0";
@@ -93,7 +96,17 @@ impl App {
         // Use the builder pattern to set up plugins
         let (plugins, init_task) = PluginManagerBuilder::new()
             .with_plugin(AutoUpdaterPlugin::new(APP_NAME.to_string(), config))
-            .build();
+            // The updater downloads and runs installers
+            .with_capabilities::<AutoUpdaterPlugin>([
+                Capability::Network,
+                Capability::FsRead,
+                Capability::FsWrite,
+                Capability::SpawnProcess,
+            ])
+            // Keep the result of the startup check until the app subscribes to outputs
+            .with_output_buffer::<AutoUpdaterPlugin>(OutputBuffer::Latest)
+            .build()
+            .expect("failed to build plugins");
 
         // Retrieve handle after building
         let updater_handle = plugins.get_handle::<AutoUpdaterPlugin>().unwrap();
@@ -301,9 +314,14 @@ impl App {
             .padding(10);
 
         // Main layout
-        let content = Column::new()
-            .push(title)
-            .push(version_row)
+        let mut content = Column::new().push(title).push(version_row);
+
+        // Update prompt rendered by the plugin, routed back to it on interaction
+        if let Some(prompt) = self.plugins.view_of::<AutoUpdaterPlugin>() {
+            content = content.push(prompt.map(Message::Plugin));
+        }
+
+        let content = content
             .push(text("━".repeat(80)).size(10))
             .push(status_section)
             .push(progress_section)
@@ -1,6 +1,9 @@
-use iced::widget::{button, column, scrollable, text};
+use iced::widget::{button, column, scrollable, stack, text};
 use iced::{Element, Subscription, Task};
-use iced_plugins::{Plugin, PluginHandle, PluginManager, PluginManagerBuilder, PluginMessage};
+use iced_plugins::{
+    Dependency, Plugin, PluginContext, PluginHandle, PluginManager, PluginManagerBuilder,
+    PluginMessage,
+};
 use std::time::Duration;
 
 fn main() -> iced::Result {
@@ -49,7 +52,7 @@ impl Plugin for CounterPlugin {
         "counter"
     }
 
-    fn init(&self) -> (Self::State, iced::Task<Self::Message>) {
+    fn init(&self, _ctx: &mut PluginContext<'_>) -> (Self::State, iced::Task<Self::Message>) {
         (CounterState { value: 0 }, iced::Task::none())
     }
 
@@ -57,6 +60,7 @@ impl Plugin for CounterPlugin {
         &self,
         state: &mut Self::State,
         message: Self::Message,
+        _ctx: &mut PluginContext<'_>,
     ) -> (iced::Task<Self::Message>, Option<Self::Output>) {
         match message {
             CounterMessage::DoIncrement => {
@@ -109,7 +113,11 @@ impl Plugin for TimerPlugin {
         "timer"
     }
 
-    fn init(&self) -> (Self::State, iced::Task<Self::Message>) {
+    fn dependencies(&self) -> Vec<Dependency> {
+        vec![Dependency::on::<CounterPlugin>()]
+    }
+
+    fn init(&self, _ctx: &mut PluginContext<'_>) -> (Self::State, iced::Task<Self::Message>) {
         (TimerState { ticks: 0 }, iced::Task::none())
     }
 
@@ -117,10 +125,15 @@ impl Plugin for TimerPlugin {
         &self,
         state: &mut Self::State,
         message: Self::Message,
+        ctx: &mut PluginContext<'_>,
     ) -> (iced::Task<Self::Message>, Option<Self::Output>) {
         match message {
             TimerMessage::Tick => {
                 state.ticks += 1;
+                // Increment the counter plugin every 5 ticks
+                if state.ticks.is_multiple_of(5) {
+                    ctx.dispatch::<CounterPlugin>(CounterInput::Increment);
+                }
                 (iced::Task::none(), None)
             }
         }
@@ -150,13 +163,16 @@ struct App {
 impl App {
     fn new() -> (Self, Task<Message>) {
         // Use the builder pattern to set up plugins
-        let mut builder = PluginManagerBuilder::new()
-            .with_plugin(CounterPlugin)
-            .with_plugin(TimerPlugin);
+        let mut builder = PluginManagerBuilder::new().with_plugin(TimerPlugin);
+
+        // Press F12 to inspect the plugins in debug builds
+        if cfg!(debug_assertions) {
+            builder = builder.with_inspector();
+        }
 
-        // Retrieve handles after building
+        // Install the counter and keep its handle
         let counter_handle = builder.install(CounterPlugin);
-        let (plugins, init_task) = builder.build();
+        let (plugins, init_task) = builder.build().expect("failed to build plugins");
 
         (
             App {
@@ -208,9 +224,13 @@ impl App {
         .spacing(20)
         .padding(20);
 
-        scrollable(content)
+        let content = scrollable(content)
             .width(iced::Length::Fill)
-            .height(iced::Length::Fill)
-            .into()
+            .height(iced::Length::Fill);
+
+        match self.plugins.inspector() {
+            Some(inspector) => stack![content, inspector.map(From::from)].into(),
+            None => content.into(),
+        }
     }
 }
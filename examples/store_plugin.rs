@@ -5,7 +5,7 @@
 
 use iced::widget::{button, column, row, scrollable, text, text_input};
 use iced::{Element, Length, Task};
-use iced_plugins::{PluginHandle, PluginManager, PluginMessage};
+use iced_plugins::{CallError, Capability, PluginHandle, PluginManager, PluginMessage};
 use iced_store_plugin::{AppName, StoreInput, StoreOutput, StorePlugin};
 use serde::{Deserialize, Serialize};
 
@@ -50,6 +50,7 @@ struct App {
 enum Message {
     Plugin(PluginMessage),
     StoreOutput(StoreOutput),
+    DataLoaded(Result<StoreOutput, CallError>),
 
     // Data actions
     ThemeInputChanged(String),
@@ -62,9 +63,10 @@ enum Message {
 impl App {
     fn new() -> (App, Task<Message>) {
         let app_name = AppName::new("com", "nrjais", "store_plugin");
-        let mut builder = iced_plugins::PluginManagerBuilder::new();
+        let mut builder = iced_plugins::PluginManagerBuilder::new()
+            .with_capabilities::<StorePlugin>([Capability::FsRead, Capability::FsWrite]);
         let store_handle = builder.install(StorePlugin::new(app_name));
-        let (plugins, init_task) = builder.build();
+        let (plugins, init_task) = builder.build().expect("failed to build plugins");
 
         let app = App {
             plugins,
@@ -77,8 +79,8 @@ impl App {
 
         // Auto-load data on startup
         let load_task = store_handle
-            .dispatch(StoreInput::get("ui", "user"))
-            .map(Message::Plugin);
+            .call(StoreInput::get("ui", "user"))
+            .map(Message::DataLoaded);
 
         (
             app,
@@ -92,8 +94,9 @@ impl App {
                 return self.plugins.update(plugin_msg).map(Message::Plugin);
             }
 
-            Message::StoreOutput(output) => match output {
-                StoreOutput::Get { ref key, .. } if key == "user" => {
+            // The response to our own `call`, never someone else's `Get`
+            Message::DataLoaded(result) => match result {
+                Ok(output @ StoreOutput::Get { .. }) => {
                     if let Some(data) = output.as_value::<UserData>() {
                         self.user_data = data.clone();
                         self.theme_input = data.theme;
@@ -102,6 +105,18 @@ impl App {
                     }
                 }
 
+                Ok(StoreOutput::NotFound { key, .. }) => {
+                    self.status_message = format!("'{}' not found, using defaults", key);
+                }
+
+                Ok(_) => {}
+
+                Err(error) => {
+                    self.status_message = format!("Failed to load: {}", error);
+                }
+            },
+
+            Message::StoreOutput(output) => match output {
                 StoreOutput::Set { group, key } => {
                     self.status_message = format!("Saved {}/{}", group, key);
                 }
@@ -114,10 +129,6 @@ impl App {
                     self.font_size_input = self.user_data.font_size.to_string();
                 }
 
-                StoreOutput::NotFound { key, .. } => {
-                    self.status_message = format!("'{}' not found, using defaults", key);
-                }
-
                 StoreOutput::Error { message } => {
                     self.status_message = format!("Error: {}", message);
                 }
@@ -153,8 +164,8 @@ impl App {
             Message::LoadData => {
                 return self
                     .store_handle
-                    .dispatch(StoreInput::get("ui", "user"))
-                    .map(Message::Plugin);
+                    .call(StoreInput::get("ui", "user"))
+                    .map(Message::DataLoaded);
             }
 
             Message::DeleteData => {
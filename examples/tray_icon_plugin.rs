@@ -1,6 +1,6 @@
 use iced::widget::{button, checkbox, column, row, scrollable, text};
 use iced::{Element, Subscription, Task, window};
-use iced_plugins::{PluginHandle, PluginManager, PluginManagerBuilder, PluginMessage};
+use iced_plugins::{Capability, PluginHandle, PluginManager, PluginManagerBuilder, PluginMessage};
 use iced_tray_icon_plugin::{Menu, MenuItem, TrayIconInput, TrayIconOutput, TrayIconPlugin};
 
 fn main() -> iced::Result {
@@ -77,14 +77,15 @@ impl App {
         // Setup plugins with initial menu
         let initial_menu = Self::build_menu(false, true, Status::Online);
 
-        let mut builder = PluginManagerBuilder::new();
+        let mut builder =
+            PluginManagerBuilder::new().with_capabilities::<TrayIconPlugin>([Capability::Tray]);
         let tray_handle = builder.install(
             TrayIconPlugin::new("Tray Icon Demo")
                 .with_icon(icon_data)
                 .with_menu(initial_menu),
         );
 
-        let (plugins, init_task) = builder.build();
+        let (plugins, init_task) = builder.build().expect("failed to build plugins");
 
         (
             App {
@@ -1,8 +1,8 @@
 use iced::widget::{column, container, scrollable, text};
 use iced::window::Position;
 use iced::{Element, Subscription, Task, window};
-use iced_plugins::{PluginHandle, PluginManager, PluginManagerBuilder, PluginMessage};
-use iced_store_plugin::AppName;
+use iced_plugins::{Capability, PluginHandle, PluginManager, PluginManagerBuilder, PluginMessage};
+use iced_store_plugin::{AppName, StorePlugin};
 use iced_window_state_plugin::{WindowState, WindowStateOutput, WindowStatePlugin};
 
 fn load_window_state() -> WindowState {
@@ -21,6 +21,8 @@ fn main() -> iced::Result {
     println!("Loading window state: {:?}", window_state);
     iced::application(App::new, App::update, App::view)
         .subscription(App::subscription)
+        // Shut the plugins down before closing, so the last changes are saved
+        .exit_on_close_request(false)
         .window(window::Settings {
             size: window_state.size,
             position: Position::Specific(window_state.position),
@@ -33,6 +35,7 @@ fn main() -> iced::Result {
 enum Message {
     Plugin(PluginMessage),
     WindowSaved,
+    CloseRequested(window::Id),
 }
 
 impl From<PluginMessage> for Message {
@@ -51,9 +54,14 @@ impl App {
         let app_name = AppName::new("com", "example", "window_state_plugin");
 
         // Use the builder pattern to set up plugins
+        // The window state plugin persists through the store plugin
         let (plugins, init_task) = PluginManagerBuilder::new()
+            .with_plugin(StorePlugin::new(app_name.clone()))
+            .with_capabilities::<StorePlugin>([Capability::FsRead, Capability::FsWrite])
             .with_plugin(WindowStatePlugin::new(app_name))
-            .build();
+            .with_capabilities::<WindowStatePlugin>([Capability::FsRead, Capability::FsWrite])
+            .build()
+            .expect("failed to build plugins");
 
         // Retrieve handle after building
         let window_handle = plugins.get_handle::<WindowStatePlugin>().unwrap();
@@ -74,6 +82,11 @@ impl App {
                 println!("Window state saved");
                 Task::none()
             }
+            Message::CloseRequested(id) => self
+                .plugins
+                .shutdown()
+                .map(From::from)
+                .chain(window::close(id)),
         }
     }
 
@@ -81,7 +94,11 @@ impl App {
         let window_sub = self.window_handle.listen_with(|output| {
             matches!(output, WindowStateOutput::StateSaved(_)).then(|| Message::WindowSaved)
         });
-        Subscription::batch([self.plugins.subscriptions().map(From::from), window_sub])
+        Subscription::batch([
+            self.plugins.subscriptions().map(From::from),
+            window_sub,
+            window::close_requests().map(Message::CloseRequested),
+        ])
     }
 
     fn view(&self) -> Element<'_, Message> {
@@ -0,0 +1,448 @@
+//! Derive macros for iced_plugins
+//!
+//! Use them through the `derive` feature of `iced_plugins` rather than depending on
+//! this crate directly.
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{format_ident, quote};
+use syn::parse::ParseStream;
+use syn::punctuated::Punctuated;
+use syn::{
+    Attribute, Data, DeriveInput, Expr, ExprLit, Fields, Ident, Lit, LitStr, Path, Token, Variant,
+    parenthesized, parse_macro_input,
+};
+
+/// Generate the public `Input` enum of a plugin from its `Message` enum
+///
+/// Every variant of the message enum becomes a variant of the input enum, with the same
+/// fields and doc comments, except the ones marked `#[internal]`. The derive also
+/// implements `From<Input> for Message`, as required by [`Plugin::Input`].
+///
+/// The input enum is named after the message enum with `Message` replaced by `Input`,
+/// and always derives `Clone` and `Debug`. Both can be changed with the `input` attribute:
+///
+/// - `#[input(name = StoreInput)]` sets the name of the input enum
+/// - `#[input(derive(PartialEq, Eq))]` adds derives to the input enum
+/// - doc comments inside `#[input(...)]` document it, as does `#[input(doc = "...")]`
+///
+/// # Example
+///
+/// ```ignore
+/// use iced_plugins::PluginInput;
+///
+/// #[derive(Clone, Debug, PluginInput)]
+/// #[input(
+///     /// Commands of the counter plugin
+/// )]
+/// pub enum CounterMessage {
+///     /// Add one to the counter
+///     Increment,
+///     /// Add a number to the counter
+///     Add(i32),
+///     /// Internal tick of the counter's subscription
+///     #[internal]
+///     Tick,
+/// }
+///
+/// // Generated:
+/// //
+/// // /// Commands of the counter plugin
+/// // #[derive(Clone, Debug)]
+/// // pub enum CounterInput {
+/// //     /// Add one to the counter
+/// //     Increment,
+/// //     /// Add a number to the counter
+/// //     Add(i32),
+/// // }
+/// //
+/// // impl From<CounterInput> for CounterMessage { ... }
+/// ```
+///
+/// [`Plugin::Input`]: https://docs.rs/iced_plugins/latest/iced_plugins/trait.Plugin.html#associatedtype.Input
+#[proc_macro_derive(PluginInput, attributes(input, internal))]
+pub fn derive_plugin_input(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    plugin_input(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Options of the `#[input(...)]` attribute
+struct InputOptions {
+    name: Option<Ident>,
+    derives: Vec<Path>,
+    docs: Vec<LitStr>,
+}
+
+impl InputOptions {
+    fn parse(attrs: &[Attribute]) -> syn::Result<Self> {
+        let mut options = Self {
+            name: None,
+            derives: Vec::new(),
+            docs: Vec::new(),
+        };
+
+        for attr in attrs.iter().filter(|attr| attr.path().is_ident("input")) {
+            attr.parse_args_with(|stream: ParseStream| options.parse_args(stream))?;
+        }
+
+        Ok(options)
+    }
+
+    /// Parse the arguments of one `#[input(...)]` attribute
+    ///
+    /// Doc comments may appear between the options, and reach the derive as `#[doc]`
+    /// attributes.
+    fn parse_args(&mut self, stream: ParseStream) -> syn::Result<()> {
+        while !stream.is_empty() {
+            for attr in Attribute::parse_outer(stream)? {
+                self.docs.push(doc_comment(&attr)?);
+            }
+            if stream.is_empty() {
+                break;
+            }
+
+            let option: Path = stream.parse()?;
+            if option.is_ident("name") {
+                stream.parse::<Token![=]>()?;
+                self.name = Some(stream.parse()?);
+            } else if option.is_ident("derive") {
+                let derives;
+                parenthesized!(derives in stream);
+                self.derives
+                    .extend(Punctuated::<Path, Token![,]>::parse_terminated(&derives)?);
+            } else if option.is_ident("doc") {
+                stream.parse::<Token![=]>()?;
+                self.docs.push(stream.parse()?);
+            } else {
+                return Err(syn::Error::new_spanned(
+                    option,
+                    "expected `name`, `derive` or `doc`",
+                ));
+            }
+
+            if !stream.is_empty() {
+                stream.parse::<Token![,]>()?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// The text of a doc comment
+fn doc_comment(attr: &Attribute) -> syn::Result<LitStr> {
+    if is_doc(attr)
+        && let Expr::Lit(ExprLit {
+            lit: Lit::Str(doc), ..
+        }) = &attr.meta.require_name_value()?.value
+    {
+        return Ok(doc.clone());
+    }
+
+    Err(syn::Error::new_spanned(attr, "expected a doc comment"))
+}
+
+fn plugin_input(input: DeriveInput) -> syn::Result<TokenStream2> {
+    let Data::Enum(data) = &input.data else {
+        return Err(syn::Error::new_spanned(
+            &input.ident,
+            "PluginInput can only be derived for enums",
+        ));
+    };
+    if !input.generics.params.is_empty() {
+        return Err(syn::Error::new_spanned(
+            &input.generics,
+            "PluginInput does not support generic messages",
+        ));
+    }
+
+    let options = InputOptions::parse(&input.attrs)?;
+    let message = &input.ident;
+    let vis = &input.vis;
+    let name = match options.name {
+        Some(name) => name,
+        None => default_name(message)?,
+    };
+
+    let docs = if options.docs.is_empty() {
+        let doc = format!(
+            "Public input API of the plugin, converted into [`{}`]",
+            message
+        );
+        vec![LitStr::new(&doc, message.span())]
+    } else {
+        options.docs
+    };
+    let derives = options.derives;
+
+    let mut variants = Vec::new();
+    let mut arms = Vec::new();
+    for variant in &data.variants {
+        if let Some(attr) = variant
+            .attrs
+            .iter()
+            .find(|attr| attr.path().is_ident("internal"))
+        {
+            attr.meta.require_path_only()?;
+            continue;
+        }
+
+        variants.push(public_variant(variant));
+        arms.push(conversion_arm(&name, message, variant));
+    }
+
+    Ok(quote! {
+        #(#[doc = #docs])*
+        #[derive(Clone, Debug, #(#derives),*)]
+        #vis enum #name {
+            #(#variants),*
+        }
+
+        impl ::core::convert::From<#name> for #message {
+            fn from(input: #name) -> Self {
+                match input {
+                    #(#arms)*
+                }
+            }
+        }
+    })
+}
+
+/// Name `FooMessage` input `FooInput`
+fn default_name(message: &Ident) -> syn::Result<Ident> {
+    message
+        .to_string()
+        .strip_suffix("Message")
+        .filter(|prefix| !prefix.is_empty())
+        .map(|prefix| format_ident!("{}Input", prefix))
+        .ok_or_else(|| {
+            syn::Error::new_spanned(
+                message,
+                "the message name doesn't end in `Message`, set one with `#[input(name = ...)]`",
+            )
+        })
+}
+
+/// Copy a variant, keeping only the doc comments of it and its fields
+fn public_variant(variant: &Variant) -> Variant {
+    let mut variant = variant.clone();
+    variant.attrs.retain(is_doc);
+    variant.discriminant = None;
+    for field in variant.fields.iter_mut() {
+        field.attrs.retain(is_doc);
+    }
+    variant
+}
+
+fn conversion_arm(input: &Ident, message: &Ident, variant: &Variant) -> TokenStream2 {
+    let ident = &variant.ident;
+
+    match &variant.fields {
+        Fields::Named(fields) => {
+            let names: Vec<_> = fields.named.iter().map(|field| &field.ident).collect();
+            quote! { #input::#ident { #(#names),* } => #message::#ident { #(#names),* }, }
+        }
+        Fields::Unnamed(fields) => {
+            let names: Vec<_> = (0..fields.unnamed.len())
+                .map(|index| format_ident!("field_{}", index))
+                .collect();
+            quote! { #input::#ident(#(#names),*) => #message::#ident(#(#names),*), }
+        }
+        Fields::Unit => quote! { #input::#ident => #message::#ident, },
+    }
+}
+
+fn is_doc(attr: &Attribute) -> bool {
+    attr.path().is_ident("doc")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use syn::{File, Item, ItemEnum, parse_quote};
+
+    fn expand(input: DeriveInput) -> File {
+        syn::parse2(plugin_input(input).unwrap()).unwrap()
+    }
+
+    fn error(input: DeriveInput) -> String {
+        plugin_input(input).unwrap_err().to_string()
+    }
+
+    fn input_enum(file: &File) -> &ItemEnum {
+        file.items
+            .iter()
+            .find_map(|item| match item {
+                Item::Enum(item) => Some(item),
+                _ => None,
+            })
+            .expect("an input enum is generated")
+    }
+
+    fn variant_names(item: &ItemEnum) -> Vec<String> {
+        item.variants
+            .iter()
+            .map(|variant| variant.ident.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn leaves_out_internal_variants() {
+        let file = expand(parse_quote! {
+            pub enum CounterMessage {
+                Increment,
+                Add(i32),
+                Set { value: i32 },
+                #[internal]
+                Tick,
+            }
+        });
+
+        let input = input_enum(&file);
+        assert_eq!(input.ident, "CounterInput");
+        assert_eq!(variant_names(input), ["Increment", "Add", "Set"]);
+        assert!(matches!(input.vis, syn::Visibility::Public(_)));
+
+        let Some(Item::Impl(conversion)) = file.items.get(1) else {
+            panic!("a From impl is generated");
+        };
+        let from: syn::Type = parse_quote!(CounterMessage);
+        assert_eq!(*conversion.self_ty, from);
+    }
+
+    #[test]
+    fn keeps_only_doc_attributes() {
+        let file = expand(parse_quote! {
+            enum CounterMessage {
+                /// Add a number
+                #[serde(rename = "add")]
+                Add {
+                    /// How much to add
+                    #[serde(default)]
+                    amount: i32,
+                },
+            }
+        });
+
+        let variant = &input_enum(&file).variants[0];
+        assert!(variant.attrs.iter().all(is_doc));
+        assert_eq!(variant.attrs.len(), 1);
+        let field = variant.fields.iter().next().unwrap();
+        assert!(field.attrs.iter().all(is_doc));
+        assert_eq!(field.attrs.len(), 1);
+    }
+
+    #[test]
+    fn applies_input_options() {
+        let file = expand(parse_quote! {
+            #[input(name = Commands, derive(PartialEq, Eq))]
+            #[input(doc = "First line")]
+            #[input(doc = "Second line")]
+            enum Events {
+                Quit,
+            }
+        });
+
+        let input = input_enum(&file);
+        assert_eq!(input.ident, "Commands");
+
+        let derive = input
+            .attrs
+            .iter()
+            .find(|attr| attr.path().is_ident("derive"))
+            .unwrap();
+        let derives = derive.meta.require_list().unwrap().tokens.to_string();
+        assert_eq!(derives, "Clone , Debug , PartialEq , Eq");
+
+        let docs = input.attrs.iter().filter(|attr| is_doc(attr)).count();
+        assert_eq!(docs, 2);
+    }
+
+    #[test]
+    fn takes_doc_comments_inside_the_attribute() {
+        let file = expand(parse_quote! {
+            /// Messages of the counter
+            #[input(
+                derive(PartialEq),
+                /// Commands of the counter
+                ///
+                /// # Example
+            )]
+            enum CounterMessage {
+                Increment,
+            }
+        });
+
+        let docs: Vec<_> = input_enum(&file)
+            .attrs
+            .iter()
+            .filter(|attr| is_doc(attr))
+            .map(|attr| doc_comment(attr).unwrap().value())
+            .collect();
+        assert_eq!(docs, [" Commands of the counter", "", " # Example"]);
+    }
+
+    #[test]
+    fn rejects_structs() {
+        let message = error(parse_quote! {
+            struct CounterMessage;
+        });
+        assert_eq!(message, "PluginInput can only be derived for enums");
+    }
+
+    #[test]
+    fn rejects_generic_messages() {
+        let message = error(parse_quote! {
+            enum CounterMessage<T> {
+                Add(T),
+            }
+        });
+        assert_eq!(message, "PluginInput does not support generic messages");
+    }
+
+    #[test]
+    fn needs_a_name_without_message_suffix() {
+        let message = error(parse_quote! {
+            enum Counter {
+                Increment,
+            }
+        });
+        assert!(message.contains("#[input(name = ...)]"), "{}", message);
+
+        let message = error(parse_quote! {
+            enum Message {
+                Increment,
+            }
+        });
+        assert!(message.contains("#[input(name = ...)]"), "{}", message);
+    }
+
+    #[test]
+    fn rejects_unknown_options() {
+        let message = error(parse_quote! {
+            #[input(rename = Commands)]
+            enum CounterMessage {
+                Increment,
+            }
+        });
+        assert_eq!(message, "expected `name`, `derive` or `doc`");
+
+        let message = error(parse_quote! {
+            #[input(#[serde(rename = "commands")])]
+            enum CounterMessage {
+                Increment,
+            }
+        });
+        assert_eq!(message, "expected a doc comment");
+
+        let message = error(parse_quote! {
+            enum CounterMessage {
+                #[internal(reason = "tick")]
+                Tick,
+            }
+        });
+        assert!(message.contains("unexpected token"), "{}", message);
+    }
+}
@@ -13,19 +13,32 @@
 //! - Install Linux packages (.deb for Debian/Ubuntu)
 //! - Progress tracking for downloads
 //! - Automatic or manual update checks
+//! - An update prompt to embed with [`PluginManager::view_of`]
+//! - With the `config` feature, settings can be changed in the config file under
+//!   `[auto_updater]` while the app runs
+//!
+//! The plugin needs the `network`, `fs-read` and `fs-write` capabilities, since it
+//! downloads installers. Without the optional `spawn-process` capability, it stops
+//! after verifying the download instead of running the installer.
 //!
 //! # Example
 //!
 //! ```ignore
 //! use iced_auto_updater_plugin::{AutoUpdaterPlugin, UpdaterConfig};
+//! use iced_plugins::{Capability, PluginManagerBuilder};
 //!
 //! const APP_NAME: &str = "my_app";
 //!
 //! fn main() -> iced::Result {
-//!     let mut plugins = PluginManager::new();
-//!
 //!     let config = UpdaterConfig::new("owner", "repo", env!("CARGO_PKG_VERSION"));
-//!     let updater_handle = plugins.install(AutoUpdaterPlugin::new(APP_NAME.to_string(), config));
+//!     let mut builder = PluginManagerBuilder::new().with_capabilities::<AutoUpdaterPlugin>([
+//!         Capability::Network,
+//!         Capability::FsRead,
+//!         Capability::FsWrite,
+//!         Capability::SpawnProcess,
+//!     ]);
+//!     let updater_handle = builder.install(AutoUpdaterPlugin::new(APP_NAME.to_string(), config));
+//!     let (plugins, init_task) = builder.build().expect("failed to build plugins");
 //!
 //!     // Check for updates manually
 //!     let task = updater_handle.dispatch(AutoUpdaterMessage::CheckForUpdates);
@@ -33,17 +46,32 @@
 //!     iced::application(App::new, App::update, App::view)
 //!         .run()
 //! }
+//!
+//! // Show the update prompt, with a progress bar while updating
+//! fn view(&self) -> Element<'_, Message> {
+//!     let mut content = Column::new();
+//!     if let Some(prompt) = self.plugins.view_of::<AutoUpdaterPlugin>() {
+//!         content = content.push(prompt.map(Message::Plugin));
+//!     }
+//!     content.push(self.main_view()).into()
+//! }
 //! ```
+//!
+//! [`PluginManager::view_of`]: iced_plugins::PluginManager::view_of
 
 #[cfg(target_os = "linux")]
 mod linux;
 #[cfg(target_os = "macos")]
 mod macos;
 
+use iced::alignment::Vertical;
 use iced::task::{Straw, sipper};
 use iced::time::every;
-use iced::{Subscription, Task};
-use iced_plugins::Plugin;
+use iced::widget::{Column, Row, button, progress_bar, text};
+use iced::{Element, Subscription, Task};
+#[cfg(feature = "config")]
+use iced_plugins::Configurable;
+use iced_plugins::{Capability, Plugin, PluginContext, PluginInput};
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
 use std::path::PathBuf;
@@ -52,7 +80,7 @@ use tokio::fs;
 use tokio::io::AsyncWriteExt;
 
 /// Configuration for the auto updater
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct UpdaterConfig {
     /// GitHub repository owner
     pub owner: String,
@@ -130,47 +158,41 @@ impl DownloadProgress {
     }
 }
 
-/// Public input API that applications use
-#[derive(Clone, Debug)]
-pub enum AutoUpdaterInput {
-    /// Check for updates from GitHub
-    CheckForUpdates,
-    /// Download and install update
-    DownloadAndInstall(ReleaseInfo),
-}
-
-impl From<AutoUpdaterInput> for AutoUpdaterMessage {
-    fn from(input: AutoUpdaterInput) -> Self {
-        match input {
-            AutoUpdaterInput::CheckForUpdates => AutoUpdaterMessage::CheckForUpdates,
-            AutoUpdaterInput::DownloadAndInstall(release) => {
-                AutoUpdaterMessage::DownloadAndInstall(release)
-            }
-        }
-    }
-}
-
 /// Messages that the auto updater plugin handles
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PluginInput)]
+#[input(doc = "Public input API that applications use")]
 pub enum AutoUpdaterMessage {
     /// Check for updates from GitHub
     CheckForUpdates,
     /// Update check completed
+    #[internal]
     UpdateCheckResult(Result<Option<ReleaseInfo>, String>),
     /// Download and install update
     DownloadAndInstall(ReleaseInfo),
+    /// Hide the update prompt until the next update check finds a release
+    #[internal]
+    Dismiss,
     /// Download progress update
+    #[internal]
     DownloadProgress(DownloadProgress),
     /// Download completed
+    #[internal]
     DownloadCompleted(Result<PathBuf, String>),
     /// SHA256 verification result
+    #[internal]
     VerificationResult(Result<PathBuf, String>),
     /// Start installation
+    #[internal]
     StartInstallation(PathBuf),
     /// Installation result
+    #[internal]
     InstallationResult(Result<(), String>),
     /// Auto-check timer tick
+    #[internal]
     AutoCheckTick,
+    /// The config was reloaded
+    #[internal]
+    ConfigChanged(UpdaterConfig),
 }
 
 /// Output messages emitted by the auto updater plugin
@@ -214,6 +236,8 @@ pub struct AutoUpdaterState {
     pub downloaded_file: Option<PathBuf>,
     /// Abort handle of the download task
     pub abort_handle: Option<iced::task::Handle>,
+    /// Current configuration
+    pub config: UpdaterConfig,
 }
 
 /// Auto updater plugin
@@ -229,6 +253,18 @@ impl AutoUpdaterPlugin {
         Self { app_name, config }
     }
 
+    /// Get the config to start with, from the config file if the plugin is
+    /// registered for it
+    #[cfg(feature = "config")]
+    fn initial_config(&self, ctx: &PluginContext<'_>) -> UpdaterConfig {
+        ctx.config::<Self>().unwrap_or_else(|| self.config.clone())
+    }
+
+    #[cfg(not(feature = "config"))]
+    fn initial_config(&self, _ctx: &PluginContext<'_>) -> UpdaterConfig {
+        self.config.clone()
+    }
+
     /// Get the download directory
     fn download_dir(&self) -> PathBuf {
         directories::BaseDirs::new()
@@ -238,6 +274,24 @@ impl AutoUpdaterPlugin {
             .join("updates")
     }
 
+    /// Check for updates, if the plugin may use the network
+    fn check(config: &UpdaterConfig, ctx: &PluginContext<'_>) -> Task<AutoUpdaterMessage> {
+        if let Err(error) = ctx.require(Capability::Network) {
+            return Task::done(AutoUpdaterMessage::UpdateCheckResult(
+                Err(error.to_string()),
+            ));
+        }
+
+        Task::perform(
+            Self::check_for_updates(
+                config.owner.clone(),
+                config.repo.clone(),
+                config.current_version.clone(),
+            ),
+            AutoUpdaterMessage::UpdateCheckResult,
+        )
+    }
+
     /// Check for updates from GitHub
     async fn check_for_updates(
         owner: String,
@@ -269,10 +323,7 @@ impl AutoUpdaterPlugin {
             .await
             .map_err(|e| format!("Failed to parse release info: {}", e))?;
 
-        let latest_version = release.tag_name.trim_start_matches('v');
-        let current = current_version.trim_start_matches('v');
-
-        if latest_version != current {
+        if is_newer(&release.tag_name, &current_version) {
             Ok(Some(release))
         } else {
             Ok(None)
@@ -502,24 +553,22 @@ impl Plugin for AutoUpdaterPlugin {
         "auto_updater"
     }
 
-    fn init(&self) -> (Self::State, Task<Self::Message>) {
+    fn capabilities(&self) -> Vec<Capability> {
+        vec![Capability::Network, Capability::FsRead, Capability::FsWrite]
+    }
+
+    fn init(&self, ctx: &mut PluginContext<'_>) -> (Self::State, Task<Self::Message>) {
         let state = AutoUpdaterState {
             download_progress: None,
             latest_release: None,
             is_updating: false,
             downloaded_file: None,
             abort_handle: None,
+            config: self.initial_config(ctx),
         };
 
-        let init_task = if self.config.check_on_start {
-            let owner = self.config.owner.clone();
-            let repo = self.config.repo.clone();
-            let current_version = self.config.current_version.clone();
-
-            Task::perform(
-                Self::check_for_updates(owner, repo, current_version),
-                AutoUpdaterMessage::UpdateCheckResult,
-            )
+        let init_task = if state.config.check_on_start {
+            Self::check(&state.config, ctx)
         } else {
             Task::none()
         };
@@ -531,20 +580,10 @@ impl Plugin for AutoUpdaterPlugin {
         &self,
         state: &mut Self::State,
         message: Self::Message,
+        ctx: &mut PluginContext<'_>,
     ) -> (Task<Self::Message>, Option<Self::Output>) {
         match message {
-            AutoUpdaterMessage::CheckForUpdates => {
-                let owner = self.config.owner.clone();
-                let repo = self.config.repo.clone();
-                let current_version = self.config.current_version.clone();
-
-                let task = Task::perform(
-                    Self::check_for_updates(owner, repo, current_version),
-                    AutoUpdaterMessage::UpdateCheckResult,
-                );
-
-                (task, None)
-            }
+            AutoUpdaterMessage::CheckForUpdates => (Self::check(&state.config, ctx), None),
 
             AutoUpdaterMessage::UpdateCheckResult(result) => match result {
                 Ok(Some(release)) => {
@@ -554,14 +593,28 @@ impl Plugin for AutoUpdaterPlugin {
                         Some(AutoUpdaterOutput::UpdateAvailable(release)),
                     )
                 }
-                Ok(None) => (Task::none(), Some(AutoUpdaterOutput::NoUpdateAvailable)),
+                Ok(None) => {
+                    state.latest_release = None;
+                    (Task::none(), Some(AutoUpdaterOutput::NoUpdateAvailable))
+                }
                 Err(e) => (Task::none(), Some(AutoUpdaterOutput::Error(e))),
             },
 
             AutoUpdaterMessage::DownloadAndInstall(release) => {
+                if let Err(error) = ctx
+                    .require(Capability::Network)
+                    .and_then(|()| ctx.require(Capability::FsWrite))
+                {
+                    return (
+                        Task::none(),
+                        Some(AutoUpdaterOutput::Error(error.to_string())),
+                    );
+                }
+
                 if let Some(asset) = self.find_platform_asset(&release) {
                     state.is_updating = true;
                     state.latest_release = Some(release.clone());
+                    state.download_progress = None;
 
                     let download_dir = self.download_dir();
                     let dest_path = download_dir.join(&asset.name);
@@ -651,6 +704,14 @@ impl Plugin for AutoUpdaterPlugin {
             },
 
             AutoUpdaterMessage::StartInstallation(path) => {
+                if let Err(error) = ctx.require(Capability::SpawnProcess) {
+                    state.is_updating = false;
+                    return (
+                        Task::none(),
+                        Some(AutoUpdaterOutput::Error(error.to_string())),
+                    );
+                }
+
                 let task =
                     Task::perform(Self::install(path), AutoUpdaterMessage::InstallationResult);
 
@@ -662,23 +723,17 @@ impl Plugin for AutoUpdaterPlugin {
                 state.downloaded_file = None;
 
                 match result {
-                    Ok(()) => (Task::none(), Some(AutoUpdaterOutput::InstallationCompleted)),
+                    Ok(()) => {
+                        state.latest_release = None;
+                        (Task::none(), Some(AutoUpdaterOutput::InstallationCompleted))
+                    }
                     Err(e) => (Task::none(), Some(AutoUpdaterOutput::Error(e))),
                 }
             }
 
             AutoUpdaterMessage::AutoCheckTick => {
                 if !state.is_updating {
-                    let owner = self.config.owner.clone();
-                    let repo = self.config.repo.clone();
-                    let current_version = self.config.current_version.clone();
-
-                    let task = Task::perform(
-                        Self::check_for_updates(owner, repo, current_version),
-                        AutoUpdaterMessage::UpdateCheckResult,
-                    );
-
-                    (task, None)
+                    (Self::check(&state.config, ctx), None)
                 } else {
                     (Task::none(), None)
                 }
@@ -691,15 +746,123 @@ impl Plugin for AutoUpdaterPlugin {
                     Some(AutoUpdaterOutput::DownloadProgress(progress)),
                 )
             }
+
+            AutoUpdaterMessage::Dismiss => {
+                if !state.is_updating {
+                    state.latest_release = None;
+                }
+                (Task::none(), None)
+            }
+
+            AutoUpdaterMessage::ConfigChanged(config) => {
+                state.config = config;
+                (Task::none(), None)
+            }
+        }
+    }
+
+    fn view<'a>(&'a self, state: &'a Self::State) -> Option<Element<'a, Self::Message>> {
+        let release = state.latest_release.as_ref()?;
+
+        if state.is_updating {
+            let label = if state.downloaded_file.is_some() {
+                format!("Installing {}", release.tag_name)
+            } else {
+                format!("Downloading {}", release.tag_name)
+            };
+            let percentage = state
+                .download_progress
+                .as_ref()
+                .map_or(0.0, DownloadProgress::percentage);
+
+            return Some(
+                Column::new()
+                    .push(text(label).size(14))
+                    .push(progress_bar(0.0..=100.0, percentage))
+                    .spacing(4)
+                    .padding(10)
+                    .into(),
+            );
         }
+
+        Some(
+            Row::new()
+                .push(text(format!("Version {} is available", release.tag_name)).size(14))
+                .push(
+                    button(text("Update").size(14))
+                        .on_press(AutoUpdaterMessage::DownloadAndInstall(release.clone())),
+                )
+                .push(button(text("Later").size(14)).on_press(AutoUpdaterMessage::Dismiss))
+                .spacing(10)
+                .padding(10)
+                .align_y(Vertical::Center)
+                .into(),
+        )
     }
 
-    fn subscription(&self, _state: &Self::State) -> Subscription<Self::Message> {
-        if self.config.auto_check_interval > 0 {
-            every(Duration::from_secs(self.config.auto_check_interval))
+    fn subscription(&self, state: &Self::State) -> Subscription<Self::Message> {
+        if state.config.auto_check_interval > 0 {
+            every(Duration::from_secs(state.config.auto_check_interval))
                 .map(|_| AutoUpdaterMessage::AutoCheckTick)
         } else {
             Subscription::none()
         }
     }
 }
+
+/// Whether version `latest` is newer than `current`, comparing their dot-separated
+/// numbers, e.g. `v1.10.0` is newer than `1.9.2`
+///
+/// Versions that aren't plain numbers, such as pre-releases, are newer when they differ.
+fn is_newer(latest: &str, current: &str) -> bool {
+    let latest = latest.trim_start_matches('v');
+    let current = current.trim_start_matches('v');
+    let numbers = |version: &str| -> Option<Vec<u64>> {
+        let mut numbers = version
+            .split('.')
+            .map(|part| part.parse().ok())
+            .collect::<Option<Vec<u64>>>()?;
+        // `1.2` and `1.2.0` are the same version
+        while numbers.last() == Some(&0) {
+            numbers.pop();
+        }
+        Some(numbers)
+    };
+
+    match (numbers(latest), numbers(current)) {
+        (Some(latest), Some(current)) => latest > current,
+        _ => latest != current,
+    }
+}
+
+#[cfg(feature = "config")]
+impl Configurable for AutoUpdaterPlugin {
+    type Config = UpdaterConfig;
+
+    fn default_config(&self) -> Self::Config {
+        self.config.clone()
+    }
+
+    fn config_changed(config: Self::Config) -> Self::Message {
+        AutoUpdaterMessage::ConfigChanged(config)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compares_version_numbers() {
+        assert!(is_newer("v1.10.0", "1.9.2"));
+        assert!(is_newer("2.0", "v1.99.99"));
+        assert!(!is_newer("v1.2.0", "1.2"));
+        assert!(!is_newer("1.9.2", "1.10.0"));
+    }
+
+    #[test]
+    fn treats_other_versions_as_newer_when_they_differ() {
+        assert!(is_newer("v1.3.0-beta.1", "1.2.0"));
+        assert!(!is_newer("v1.3.0-beta.1", "1.3.0-beta.1"));
+    }
+}
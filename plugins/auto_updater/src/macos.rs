@@ -195,12 +195,11 @@ async fn unmount_dmg_with_retry(volume_path: &str) {
                 if attempt < 3 {
                     sleep(Duration::from_millis(500)).await;
                 } else {
-                    eprintln!(
-                        "Warning: Failed to detach DMG after {} attempts: {}",
+                    tracing::warn!(
                         attempt,
-                        String::from_utf8_lossy(&output.stderr)
+                        stderr = %String::from_utf8_lossy(&output.stderr),
+                        "failed to detach DMG, attempting force detach"
                     );
-                    eprintln!("Attempting force detach...");
 
                     let force_result = Command::new("hdiutil")
                         .args(["detach", "-force", volume_path])
@@ -211,25 +210,25 @@ async fn unmount_dmg_with_retry(volume_path: &str) {
                         if force_output.status.success() {
                             detach_success = true;
                         } else {
-                            eprintln!(
-                                "Warning: Force detach also failed: {}",
-                                String::from_utf8_lossy(&force_output.stderr)
+                            tracing::warn!(
+                                stderr = %String::from_utf8_lossy(&force_output.stderr),
+                                "force detach of DMG failed"
                             );
                         }
                     }
                 }
             }
             Err(e) => {
-                eprintln!("Warning: Failed to execute hdiutil detach: {}", e);
+                tracing::warn!(error = %e, "failed to execute hdiutil detach");
                 break;
             }
         }
     }
 
     if !detach_success {
-        eprintln!(
-            "Warning: DMG '{}' may still be mounted. You may need to manually unmount it.",
-            volume_path
+        tracing::warn!(
+            volume_path,
+            "DMG may still be mounted and may need to be unmounted manually"
         );
     }
 }
@@ -0,0 +1,163 @@
+use iced_auto_updater_plugin::{
+    AutoUpdaterMessage, AutoUpdaterOutput, AutoUpdaterPlugin, ReleaseInfo, UpdaterConfig,
+};
+use iced_plugins::{Capability, Plugin, PluginManagerBuilder, PluginTester};
+use std::path::PathBuf;
+
+/// The capabilities the auto updater declares, which leave out spawn-process
+const DECLARED: [Capability; 3] = [Capability::Network, Capability::FsRead, Capability::FsWrite];
+
+fn config() -> UpdaterConfig {
+    UpdaterConfig::new("owner", "repo", "1.0.0")
+}
+
+fn plugin(config: UpdaterConfig) -> AutoUpdaterPlugin {
+    AutoUpdaterPlugin::new("iced-plugins-tests".to_string(), config)
+}
+
+fn auto_updater(config: UpdaterConfig) -> PluginTester<AutoUpdaterPlugin> {
+    let grants = PluginManagerBuilder::new().with_capabilities::<AutoUpdaterPlugin>(DECLARED);
+    PluginTester::with_builder(grants, plugin(config))
+}
+
+fn release(tag_name: &str) -> ReleaseInfo {
+    ReleaseInfo {
+        tag_name: tag_name.to_string(),
+        name: format!("Release {}", tag_name),
+        body: None,
+        html_url: format!("https://github.com/owner/repo/releases/tag/{}", tag_name),
+        assets: Vec::new(),
+    }
+}
+
+/// Deliver the result of an update check that found a release
+fn found(tester: &mut PluginTester<AutoUpdaterPlugin>, tag_name: &str) {
+    tester.send(AutoUpdaterMessage::UpdateCheckResult(Ok(Some(release(
+        tag_name,
+    )))));
+}
+
+#[test]
+fn offers_found_releases_until_dismissed() {
+    let mut tester = auto_updater(config());
+
+    found(&mut tester, "v1.1.0");
+    assert!(matches!(
+        &tester.take_outputs()[..],
+        [AutoUpdaterOutput::UpdateAvailable(release)] if release.tag_name == "v1.1.0"
+    ));
+    assert!(tester.manager().view_of::<AutoUpdaterPlugin>().is_some());
+
+    tester.send(AutoUpdaterMessage::Dismiss);
+    assert!(tester.state().latest_release.is_none());
+    assert!(tester.manager().view_of::<AutoUpdaterPlugin>().is_none());
+}
+
+#[test]
+fn keeps_the_release_being_installed_on_dismiss() {
+    let mut tester = auto_updater(config());
+    found(&mut tester, "v1.1.0");
+    tester.state_mut().is_updating = true;
+
+    tester.send(AutoUpdaterMessage::Dismiss);
+
+    assert!(tester.state().latest_release.is_some());
+}
+
+#[test]
+fn clears_the_release_when_up_to_date() {
+    let mut tester = auto_updater(config());
+    found(&mut tester, "v1.1.0");
+    tester.take_outputs();
+
+    tester.send(AutoUpdaterMessage::UpdateCheckResult(Ok(None)));
+
+    assert!(matches!(
+        &tester.take_outputs()[..],
+        [AutoUpdaterOutput::NoUpdateAvailable]
+    ));
+    assert!(tester.state().latest_release.is_none());
+}
+
+#[test]
+fn reports_failed_checks() {
+    let mut tester = auto_updater(config());
+
+    tester.send(AutoUpdaterMessage::UpdateCheckResult(Err(
+        "offline".to_string()
+    )));
+
+    assert!(matches!(
+        &tester.take_outputs()[..],
+        [AutoUpdaterOutput::Error(error)] if error == "offline"
+    ));
+}
+
+#[test]
+fn checks_periodically_only_with_an_interval() {
+    let tester = auto_updater(config());
+    assert_eq!(plugin(config()).subscription(tester.state()).units(), 0);
+
+    let hourly = config().with_auto_check(3600);
+    let tester = auto_updater(hourly.clone());
+    assert_eq!(plugin(hourly).subscription(tester.state()).units(), 1);
+}
+
+#[test]
+fn follows_config_changes() {
+    let mut tester = auto_updater(config());
+
+    tester.send(AutoUpdaterMessage::ConfigChanged(
+        config().with_auto_check(60),
+    ));
+
+    assert_eq!(tester.state().config.auto_check_interval, 60);
+    assert_eq!(plugin(config()).subscription(tester.state()).units(), 1);
+}
+
+#[test]
+fn refuses_to_install_without_spawn_process() {
+    let mut tester = auto_updater(config());
+    tester.state_mut().is_updating = true;
+
+    tester.send(AutoUpdaterMessage::StartInstallation(PathBuf::from(
+        "update.dmg",
+    )));
+
+    assert!(matches!(
+        &tester.take_outputs()[..],
+        [AutoUpdaterOutput::Error(error)] if error.contains("spawn-process")
+    ));
+    assert!(!tester.state().is_updating);
+}
+
+#[cfg(feature = "config")]
+#[test]
+fn layers_the_config_file_and_environment_over_the_defaults() {
+    use iced_plugins::ConfigSource;
+
+    let dir = std::env::temp_dir().join(format!("iced-plugins-updater-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("plugins.toml");
+    std::fs::write(
+        &path,
+        "[auto_updater]\nauto_check_interval = 3600\nrepo = \"fork\"\n",
+    )
+    .unwrap();
+    // SAFETY: no other test reads or writes this variable
+    unsafe { std::env::set_var("UPDATERTEST_AUTO_UPDATER_REPO", "nightly") };
+
+    let builder = PluginManagerBuilder::new()
+        .with_capabilities::<AutoUpdaterPlugin>(DECLARED)
+        .with_config::<AutoUpdaterPlugin>()
+        .with_config_source(ConfigSource::file(&path).with_env_prefix("UPDATERTEST"));
+    let tester = PluginTester::with_builder(builder, plugin(config()));
+
+    // Defaults, then the file, then the environment
+    let config = &tester.state().config;
+    assert_eq!(config.owner, "owner");
+    assert_eq!(config.auto_check_interval, 3600);
+    assert_eq!(config.repo, "nightly");
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
@@ -0,0 +1,616 @@
+//! Script Plugin for Iced
+//!
+//! This plugin runs a [Rhai](https://rhai.rs) script as a plugin, so small automations
+//! can be added without writing Rust.
+//!
+//! # Features
+//!
+//! - Inputs and outputs are JSON values, routed like those of any other plugin through
+//!   `PluginHandle` and `PluginMessage`
+//! - Scripts dispatch inputs to other plugins by name, for the plugins the application
+//!   registers with [`ScriptPlugin::with_plugin`], or as targets with
+//!   [`ScriptPlugin::with_target`]
+//! - Scripts set named timers that call them back periodically
+//! - The script's `this` object is persisted through the store plugin, which must be
+//!   installed as well, so the plugin needs the `fs-read` and `fs-write` capabilities
+//! - Every call into the script is limited in the number of operations it may run, and
+//!   a script that fails is faulted
+//!
+//! # Script
+//!
+//! A script defines any of the functions `init()`, `update(input)` and `on_timer(name)`.
+//! The value returned by `update` and `on_timer` is emitted as the plugin's output,
+//! unless it is `()`. The script can call:
+//!
+//! - `dispatch(target, input)` to send an input to a plugin, by its name or target name
+//! - `set_timer(name, interval_ms)` to call `on_timer(name)` periodically, at most every
+//!   [`MIN_TIMER_INTERVAL`], replacing the timer with the same name
+//! - `cancel_timer(name)` to stop a timer
+//!
+//! ```text
+//! fn init() {
+//!     set_timer("check", 60 * 60 * 1000);
+//! }
+//!
+//! fn on_timer(name) {
+//!     dispatch("updater", "check");
+//! }
+//!
+//! fn update(input) {
+//!     this.checks = (this.checks ?? 0) + 1;
+//!     dispatch("tray", `Update ${input.version} available`);
+//!     dispatch("notes", #{ text: `Found ${input.version}` });
+//! }
+//! ```
+//!
+//! # Example
+//!
+//! ```ignore
+//! use iced_auto_updater_plugin::{AutoUpdaterInput, AutoUpdaterPlugin};
+//! use iced_plugins::{Capability, PluginManagerBuilder};
+//! use iced_script_plugin::ScriptPlugin;
+//! use iced_store_plugin::{AppName, StorePlugin};
+//! use iced_tray_icon_plugin::{TrayIconInput, TrayIconPlugin};
+//!
+//! let app_name = AppName::new("com", "example", "myapp");
+//! let script = ScriptPlugin::load("scripts/updates.rhai")
+//!     .expect("failed to load script")
+//!     // `NotesPlugin::Input` implements `Deserialize`, so it is dispatched to by name
+//!     .with_plugin::<NotesPlugin>()
+//!     .with_target::<AutoUpdaterPlugin, _>("updater", |_| Ok(AutoUpdaterInput::CheckForUpdates))
+//!     .with_target::<TrayIconPlugin, _>("tray", |value| {
+//!         let tooltip = value.as_str().ok_or("expected a tooltip")?;
+//!         Ok(TrayIconInput::SetTooltip(Some(tooltip.to_string())))
+//!     });
+//!
+//! let (plugins, init_task) = PluginManagerBuilder::new()
+//!     .with_plugin(StorePlugin::new(app_name.clone()))
+//!     .with_capabilities::<StorePlugin>([Capability::FsRead, Capability::FsWrite])
+//!     .with_plugin(script)
+//!     // The targets need the network and the tray, so the script needs them too
+//!     .with_capabilities::<ScriptPlugin>([
+//!         Capability::FsRead,
+//!         Capability::FsWrite,
+//!         Capability::Network,
+//!         Capability::Tray,
+//!     ])
+//!     .build()
+//!     .expect("failed to build plugins");
+//! ```
+
+use iced::futures::channel::oneshot;
+use iced::futures::future::BoxFuture;
+use iced::time::every;
+use iced::{Subscription, Task};
+use iced_plugins::{Capability, Dependency, Plugin, PluginContext, intern_name};
+use iced_store_plugin::{StoreInput, StoreOutput, StorePlugin};
+use rhai::{AST, CallFnOptions, Dynamic, Engine, EvalAltResult, Scope};
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
+
+/// Operations a single call into the script may run unless set with
+/// [`ScriptPlugin::with_max_operations`]
+pub const DEFAULT_MAX_OPERATIONS: u64 = 1_000_000;
+
+/// Shortest interval of a script's timers, longer than a frame so timers can't keep
+/// the application busy
+pub const MIN_TIMER_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Store key of the script's `this` object, in the store group of the script
+const STATE_KEY: &str = "state";
+
+/// Messages that the script plugin handles
+#[derive(Clone, Debug)]
+pub enum ScriptMessage {
+    /// An input for the script, as JSON
+    Input(Value),
+    /// A timer set by the script fired
+    Timer(String),
+    /// The script was started, so the inputs it dispatched in `init` can be delivered
+    Started,
+    /// Handing on what the script dispatched failed, so the script faults once the rest
+    /// was delivered
+    Failed(String),
+}
+
+impl From<Value> for ScriptMessage {
+    fn from(input: Value) -> Self {
+        ScriptMessage::Input(input)
+    }
+}
+
+/// Turns a JSON value into an input of a target plugin, and dispatches it
+type Target = Arc<dyn Fn(&mut PluginContext<'_>, Value) -> Result<bool, String> + Send + Sync>;
+
+/// A plugin the script dispatches to by the plugin's name
+#[derive(Clone)]
+struct NamedTarget {
+    /// Get the name of the plugin, if it is installed
+    name: fn(&PluginContext<'_>) -> Option<&'static str>,
+    dispatch: Target,
+}
+
+fn plugin_name<P: Plugin + 'static>(ctx: &PluginContext<'_>) -> Option<&'static str> {
+    ctx.plugin_name::<P>()
+}
+
+/// What the script did through the functions it calls
+#[derive(Debug, Default)]
+struct Host {
+    /// Inputs dispatched since the last flush, by target name
+    dispatches: Vec<(String, Value)>,
+    /// Timer intervals by name
+    timers: BTreeMap<String, Duration>,
+}
+
+/// A running script
+struct Script {
+    engine: Engine,
+    scope: Scope<'static>,
+    /// The object the script's functions see as `this`
+    this: Dynamic,
+    /// `this` as JSON when it was last handed to the store plugin
+    saved: String,
+    host: Arc<Mutex<Host>>,
+}
+
+impl Script {
+    /// Run the top level statements of a script with its stored `this`, then call its `init`
+    fn start(
+        name: &'static str,
+        ast: &AST,
+        targets: BTreeSet<String>,
+        stored: Option<String>,
+        max_operations: u64,
+    ) -> Result<Self, String> {
+        let host = Arc::new(Mutex::new(Host::default()));
+        let mut engine = Engine::new();
+        engine
+            .set_max_operations(max_operations)
+            .on_print(move |text| tracing::info!(script = name, "{}", text))
+            .on_debug(
+                move |text, _, position| tracing::debug!(script = name, %position, "{}", text),
+            );
+
+        let dispatch_host = Arc::clone(&host);
+        engine.register_fn(
+            "dispatch",
+            move |target: &str, input: Dynamic| -> Result<(), Box<EvalAltResult>> {
+                if !targets.contains(target) {
+                    return Err(format!("Unknown dispatch target: {}", target).into());
+                }
+                let input = rhai::serde::from_dynamic::<Value>(&input)?;
+                lock(&dispatch_host)
+                    .dispatches
+                    .push((target.to_string(), input));
+                Ok(())
+            },
+        );
+        let timer_host = Arc::clone(&host);
+        engine.register_fn("set_timer", move |name: &str, interval_ms: i64| {
+            let interval = Duration::from_millis(interval_ms.max(0) as u64).max(MIN_TIMER_INTERVAL);
+            lock(&timer_host).timers.insert(name.to_string(), interval);
+        });
+        let timer_host = Arc::clone(&host);
+        engine.register_fn("cancel_timer", move |name: &str| {
+            lock(&timer_host).timers.remove(name);
+        });
+
+        let saved = stored.unwrap_or_else(|| "{}".to_string());
+        let this = serde_json::from_str::<Value>(&saved)
+            .map_err(|e| format!("Invalid stored state: {}", e))
+            .and_then(|this| {
+                rhai::serde::to_dynamic(this).map_err(|e| format!("Invalid stored state: {}", e))
+            })?;
+
+        let mut scope = Scope::new();
+        engine
+            .run_ast_with_scope(&mut scope, ast)
+            .map_err(|e| e.to_string())?;
+
+        let mut script = Self {
+            engine,
+            scope,
+            this,
+            saved,
+            host,
+        };
+        script.call(ast, "init", ())?;
+        Ok(script)
+    }
+
+    /// Call a function of the script, if it defines it
+    fn call(
+        &mut self,
+        ast: &AST,
+        function: &str,
+        args: impl rhai::FuncArgs,
+    ) -> Result<Option<Value>, String> {
+        if !ast.iter_functions().any(|f| f.name == function) {
+            return Ok(None);
+        }
+
+        let options = CallFnOptions::new()
+            .eval_ast(false)
+            .bind_this_ptr(&mut self.this);
+        let result = self
+            .engine
+            .call_fn_with_options::<Dynamic>(options, &mut self.scope, ast, function, args)
+            .map_err(|e| e.to_string())?;
+
+        if result.is_unit() {
+            return Ok(None);
+        }
+        rhai::serde::from_dynamic(&result)
+            .map(Some)
+            .map_err(|e| format!("Invalid output: {}", e))
+    }
+
+    /// Deliver the inputs dispatched since the last flush, and hand `this` to the store
+    /// plugin if it changed
+    ///
+    /// An unknown target or an invalid input doesn't stop the other inputs or `this`
+    /// from being handed on. The first such error is returned once they are, and the
+    /// caller faults the script with it in a later update, since faulting drops what
+    /// was dispatched in the same one.
+    fn flush(&mut self, plugin: &ScriptPlugin, ctx: &mut PluginContext<'_>) -> Result<(), String> {
+        let name = plugin.name;
+        let mut error = None;
+
+        let dispatches = std::mem::take(&mut lock(&self.host).dispatches);
+        for (target, input) in dispatches {
+            let Some(dispatch) = plugin.target(&target, ctx) else {
+                error.get_or_insert_with(|| format!("Unknown dispatch target: {}", target));
+                continue;
+            };
+            match dispatch(ctx, input) {
+                Ok(true) => {}
+                Ok(false) => tracing::warn!(
+                    script = name,
+                    target,
+                    "target plugin is unavailable, script input dropped"
+                ),
+                Err(e) => {
+                    error.get_or_insert_with(|| format!("Invalid input for {}: {}", target, e));
+                }
+            }
+        }
+
+        match rhai::serde::from_dynamic::<Value>(&self.this) {
+            Ok(this) => {
+                let this = this.to_string();
+                if this != self.saved {
+                    let input = StoreInput::Set {
+                        group: group(name),
+                        key: STATE_KEY.to_string(),
+                        value: this.clone(),
+                    };
+                    if !ctx.dispatch::<StorePlugin>(input) {
+                        tracing::warn!(
+                            script = name,
+                            "store plugin is unavailable, script state not saved"
+                        );
+                    }
+                    self.saved = this;
+                }
+            }
+            Err(e) => {
+                error.get_or_insert_with(|| format!("Invalid state: {}", e));
+            }
+        }
+
+        error.map_or(Ok(()), Err)
+    }
+}
+
+fn lock(host: &Mutex<Host>) -> MutexGuard<'_, Host> {
+    host.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Store group of a script's state
+fn group(name: &str) -> String {
+    format!("script_{}", name)
+}
+
+/// State of a script plugin
+pub struct ScriptState {
+    /// The running script, or why it could not be started
+    script: Result<Script, String>,
+}
+
+impl std::fmt::Debug for ScriptState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.script {
+            Ok(script) => f
+                .debug_struct("ScriptState")
+                .field("this", &script.this)
+                .field("timers", &lock(&script.host).timers)
+                .finish(),
+            Err(error) => f.debug_struct("ScriptState").field("error", error).finish(),
+        }
+    }
+}
+
+/// Plugin that runs a Rhai script
+#[derive(Clone)]
+pub struct ScriptPlugin {
+    name: &'static str,
+    path: Option<PathBuf>,
+    ast: Arc<AST>,
+    targets: BTreeMap<String, Target>,
+    /// Plugins dispatched to by their name, unless a target has the same name
+    plugins: Vec<NamedTarget>,
+    max_operations: u64,
+}
+
+impl std::fmt::Debug for ScriptPlugin {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ScriptPlugin")
+            .field("name", &self.name)
+            .field("path", &self.path)
+            .field("targets", &self.targets.keys().collect::<Vec<_>>())
+            .field("plugins", &self.plugins.len())
+            .field("max_operations", &self.max_operations)
+            .finish()
+    }
+}
+
+impl ScriptPlugin {
+    /// Compile a script given as source
+    ///
+    /// The script's `this` object is kept in the store group `script_<name>` of the store
+    /// plugin.
+    pub fn new(name: &'static str, source: &str) -> Result<Self, String> {
+        let ast = Engine::new()
+            .compile(source)
+            .map_err(|e| format!("Failed to compile script {}: {}", name, e))?;
+
+        Ok(Self {
+            name,
+            path: None,
+            ast: Arc::new(ast),
+            targets: BTreeMap::new(),
+            plugins: Vec::new(),
+            max_operations: DEFAULT_MAX_OPERATIONS,
+        })
+    }
+
+    /// Load and compile a script, named after its file name
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, String> {
+        let path = path.as_ref().to_path_buf();
+        let name = path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .ok_or_else(|| format!("Invalid script file name: {}", path.display()))?;
+        let name = intern_name(name);
+
+        let source = std::fs::read_to_string(&path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        let mut plugin = Self::new(name, &source)?;
+        plugin.path = Some(path);
+        Ok(plugin)
+    }
+
+    /// Load every `.rhai` script in a directory, in file name order
+    ///
+    /// Returns the result of each file, so one script that fails to load doesn't keep
+    /// the others from loading, or an error if the directory can't be read.
+    pub fn load_dir(dir: impl AsRef<Path>) -> Result<Vec<Result<Self, String>>, String> {
+        let dir = dir.as_ref();
+        let read_error = |e: std::io::Error| format!("Failed to read {}: {}", dir.display(), e);
+
+        let mut paths = Vec::new();
+        for entry in std::fs::read_dir(dir).map_err(read_error)? {
+            let path = entry.map_err(read_error)?.path();
+            if path
+                .extension()
+                .is_some_and(|extension| extension == "rhai")
+            {
+                paths.push(path);
+            }
+        }
+        paths.sort();
+
+        Ok(paths.into_iter().map(Self::load).collect())
+    }
+
+    /// Let the script dispatch inputs to plugin `P` as `target`
+    ///
+    /// `convert` turns the value the script dispatches into an input of `P`. An error
+    /// faults the script plugin. The script plugin must be granted the capabilities
+    /// `P` declares, or its inputs are dropped.
+    pub fn with_target<P, F>(mut self, target: impl Into<String>, convert: F) -> Self
+    where
+        P: Plugin + 'static,
+        F: Fn(Value) -> Result<P::Input, String> + Send + Sync + 'static,
+    {
+        let dispatch: Target = Arc::new(move |ctx, value| Ok(ctx.dispatch::<P>(convert(value)?)));
+        self.targets.insert(target.into(), dispatch);
+        self
+    }
+
+    /// Let the script dispatch inputs to plugin `P` by the plugin's name
+    ///
+    /// The values the script dispatches are deserialized into inputs of `P`, and a value
+    /// that doesn't fit faults the script plugin. Targets added with
+    /// [`ScriptPlugin::with_target`] take precedence over plugins of the same name. The
+    /// script plugin must be granted the capabilities `P` declares, or its inputs are
+    /// dropped.
+    pub fn with_plugin<P>(mut self) -> Self
+    where
+        P: Plugin + 'static,
+        P::Input: DeserializeOwned,
+    {
+        let dispatch: Target = Arc::new(|ctx, value| {
+            let input = serde_json::from_value(value).map_err(|e| e.to_string())?;
+            Ok(ctx.dispatch::<P>(input))
+        });
+        self.plugins.push(NamedTarget {
+            name: plugin_name::<P>,
+            dispatch,
+        });
+        self
+    }
+
+    /// Set the number of operations a single call into the script may run
+    pub fn with_max_operations(mut self, max_operations: u64) -> Self {
+        self.max_operations = max_operations;
+        self
+    }
+
+    /// Get the path the script was loaded from, if any
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    /// Get the dispatcher of a target, or of an installed plugin by its name
+    fn target(&self, name: &str, ctx: &PluginContext<'_>) -> Option<Target> {
+        if let Some(target) = self.targets.get(name) {
+            return Some(Arc::clone(target));
+        }
+        self.plugins
+            .iter()
+            .find(|plugin| (plugin.name)(ctx) == Some(name))
+            .map(|plugin| Arc::clone(&plugin.dispatch))
+    }
+}
+
+impl Plugin for ScriptPlugin {
+    type Input = Value;
+    type Message = ScriptMessage;
+    type State = ScriptState;
+    type Output = Value;
+
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn dependencies(&self) -> Vec<Dependency> {
+        vec![Dependency::on::<StorePlugin>()]
+    }
+
+    fn capabilities(&self) -> Vec<Capability> {
+        vec![Capability::FsRead, Capability::FsWrite]
+    }
+
+    fn init(&self, _ctx: &mut PluginContext<'_>) -> (Self::State, Task<Self::Message>) {
+        // Stands in until the script started in `init_async`
+        let state = ScriptState {
+            script: Err("still loading".to_string()),
+        };
+        // Queued until the script started
+        (state, Task::done(ScriptMessage::Started))
+    }
+
+    fn init_async(&self, ctx: &mut PluginContext<'_>) -> Option<BoxFuture<'static, Self::State>> {
+        let name = self.name;
+        let ast = Arc::clone(&self.ast);
+        let mut targets: BTreeSet<String> = self.targets.keys().cloned().collect();
+        targets.extend(
+            self.plugins
+                .iter()
+                .filter_map(|plugin| (plugin.name)(ctx))
+                .map(str::to_string),
+        );
+        let max_operations = self.max_operations;
+
+        // Read through the store plugin, which may hold a state not yet on disk
+        let (sender, receiver) = oneshot::channel();
+        let mut sender = Some(sender);
+        let read = ctx
+            .call::<StorePlugin>(StoreInput::get(group(name), STATE_KEY))
+            .map(move |result| {
+                if let Some(sender) = sender.take() {
+                    let _ = sender.send(result);
+                }
+            });
+        ctx.emit(read.discard());
+
+        Some(Box::pin(async move {
+            let group = group(name);
+            let stored = match receiver.await {
+                Ok(Ok(StoreOutput::Get { value, .. })) => Some(value),
+                Ok(Ok(StoreOutput::Error { message })) => {
+                    tracing::warn!(group, error = message, "failed to load script state");
+                    None
+                }
+                Ok(Err(error)) => {
+                    tracing::warn!(group, %error, "failed to load script state");
+                    None
+                }
+                Ok(Ok(_)) | Err(_) => None,
+            };
+            ScriptState {
+                script: Script::start(name, &ast, targets, stored, max_operations),
+            }
+        }))
+    }
+
+    fn update(
+        &self,
+        state: &mut Self::State,
+        message: Self::Message,
+        ctx: &mut PluginContext<'_>,
+    ) -> (Task<Self::Message>, Option<Self::Output>) {
+        let script = match &mut state.script {
+            Ok(script) => script,
+            Err(error) => {
+                ctx.fault(format!("failed to start: {}", error));
+                return (Task::none(), None);
+            }
+        };
+        let result = match message {
+            ScriptMessage::Input(input) => rhai::serde::to_dynamic(input)
+                .map_err(|e| format!("Invalid input: {}", e))
+                .and_then(|input| script.call(&self.ast, "update", (input,))),
+            ScriptMessage::Timer(name) => script.call(&self.ast, "on_timer", (name,)),
+            ScriptMessage::Started => Ok(None),
+            ScriptMessage::Failed(error) => Err(error),
+        };
+
+        match result {
+            Ok(output) => match script.flush(self, ctx) {
+                Ok(()) => (Task::none(), output),
+                Err(error) => (Task::done(ScriptMessage::Failed(error)), None),
+            },
+            Err(error) => {
+                ctx.fault(error);
+                (Task::none(), None)
+            }
+        }
+    }
+
+    fn subscription(&self, state: &Self::State) -> Subscription<Self::Message> {
+        let Ok(script) = &state.script else {
+            return Subscription::none();
+        };
+
+        let host = lock(&script.host);
+        Subscription::batch(host.timers.iter().map(|(name, &interval)| {
+            every(interval)
+                .with(name.clone())
+                .map(|(name, _)| ScriptMessage::Timer(name))
+        }))
+    }
+
+    fn shutdown(
+        &self,
+        state: &mut Self::State,
+        ctx: &mut PluginContext<'_>,
+    ) -> Task<Self::Message> {
+        if let Ok(script) = &mut state.script
+            && let Err(error) = script.flush(self, ctx)
+        {
+            tracing::warn!(
+                script = self.name,
+                error,
+                "failed to flush script on shutdown"
+            );
+        }
+        Task::none()
+    }
+}
@@ -0,0 +1,145 @@
+use iced::{Subscription, Task};
+use iced_plugins::{
+    Capability, Plugin, PluginContext, PluginManagerBuilder, PluginStatus, PluginTester,
+};
+use iced_script_plugin::ScriptPlugin;
+use iced_store_plugin::{AppName, StorePlugin, storage_dir};
+use serde_json::json;
+
+const SOURCE: &str = r#"
+fn update(input) {
+    dispatch("counter", input);
+    this.total = (this.total ?? 0) + input;
+    this.total
+}
+"#;
+
+/// Dispatches an input the counter can't take before a valid one
+const INVALID_INPUT: &str = r#"
+fn update(input) {
+    dispatch("counter", "three");
+    dispatch("counter", input);
+    this.total = input;
+    input
+}
+"#;
+
+/// Adds up the numbers it receives
+#[derive(Debug)]
+struct Counter;
+
+impl Plugin for Counter {
+    type Input = u32;
+    type Message = u32;
+    type State = u32;
+    type Output = ();
+
+    fn name(&self) -> &'static str {
+        "counter"
+    }
+
+    fn init(&self, _ctx: &mut PluginContext<'_>) -> (Self::State, Task<Self::Message>) {
+        (0, Task::none())
+    }
+
+    fn update(
+        &self,
+        state: &mut Self::State,
+        message: Self::Message,
+        _ctx: &mut PluginContext<'_>,
+    ) -> (Task<Self::Message>, Option<Self::Output>) {
+        *state += message;
+        (Task::none(), None)
+    }
+
+    fn subscription(&self, _state: &Self::State) -> Subscription<Self::Message> {
+        Subscription::none()
+    }
+}
+
+/// An app name of its own for each test, so tests don't share files
+fn app_name(test: &str) -> AppName {
+    AppName::new(
+        "dev",
+        "iced-plugins-tests",
+        format!("script-{}-{}", test, std::process::id()),
+    )
+}
+
+fn script(app_name: &AppName, source: &str) -> PluginTester<ScriptPlugin> {
+    let builder = PluginManagerBuilder::new()
+        .with_plugin(StorePlugin::new(app_name.clone()))
+        .with_capabilities::<StorePlugin>([Capability::FsRead, Capability::FsWrite])
+        .with_capabilities::<ScriptPlugin>([Capability::FsRead, Capability::FsWrite])
+        .with_plugin(Counter);
+    let script = ScriptPlugin::new("sum", source)
+        .unwrap()
+        .with_plugin::<Counter>();
+    PluginTester::with_builder(builder, script)
+}
+
+#[test]
+fn dispatches_to_plugins_by_name() {
+    let app_name = app_name("dispatch");
+    let mut tester = script(&app_name, SOURCE);
+
+    tester.dispatch(json!(3));
+    tester.dispatch(json!(4));
+
+    assert_eq!(tester.take_outputs(), [json!(3), json!(7)]);
+    assert_eq!(tester.manager().get_plugin_state::<Counter>(), Some(&7));
+
+    let _ = std::fs::remove_dir_all(storage_dir(&app_name));
+}
+
+#[test]
+fn restores_state_through_the_store() {
+    let app_name = app_name("restore");
+    let mut tester = script(&app_name, SOURCE);
+    tester.dispatch(json!(3));
+    drop(tester);
+
+    let mut tester = script(&app_name, SOURCE);
+    tester.dispatch(json!(4));
+    assert_eq!(tester.take_outputs(), [json!(7)]);
+
+    let _ = std::fs::remove_dir_all(storage_dir(&app_name));
+}
+
+#[test]
+fn delivers_the_other_dispatches_and_saves_state_before_faulting() {
+    let app_name = app_name("invalid-input");
+    let mut tester = script(&app_name, INVALID_INPUT);
+
+    tester.dispatch(json!(3));
+
+    assert!(matches!(
+        tester.manager().status(tester.handle()),
+        PluginStatus::Faulted { reason } if reason.starts_with("Invalid input for counter")
+    ));
+    assert_eq!(tester.manager().get_plugin_state::<Counter>(), Some(&3));
+    drop(tester);
+
+    let mut tester = script(&app_name, SOURCE);
+    tester.dispatch(json!(4));
+    assert_eq!(tester.take_outputs(), [json!(7)]);
+
+    let _ = std::fs::remove_dir_all(storage_dir(&app_name));
+}
+
+#[test]
+fn loads_each_script_of_a_directory_on_its_own() {
+    let dir = std::env::temp_dir().join(format!("iced-plugins-scripts-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("broken.rhai"), "fn update(").unwrap();
+    std::fs::write(dir.join("sum.rhai"), SOURCE).unwrap();
+    std::fs::write(dir.join("notes.txt"), "not a script").unwrap();
+
+    let scripts = ScriptPlugin::load_dir(&dir).unwrap();
+
+    assert!(matches!(
+        &scripts[..],
+        [Err(_), Ok(script)] if script.name() == "sum"
+    ));
+    let _ = std::fs::remove_dir_all(&dir);
+}
@@ -7,6 +7,7 @@
 use crate::app_name::AppName;
 use crate::storage::{load_group, modify_group};
 use serde::{Serialize, de::DeserializeOwned};
+use std::collections::HashMap;
 
 /// Read a value from the store
 ///
@@ -206,3 +207,41 @@ pub async fn list_keys(app_name: &AppName, group: &str) -> Result<Vec<String>, S
     let data = load_group(app_name, group).await?;
     Ok(data.keys().cloned().collect())
 }
+
+/// Read all values of a group
+///
+/// Reads the file on disk, without the values the store plugin holds but hasn't saved
+/// yet. Plugins running next to the store plugin get the group through it with
+/// [`StoreInput::get_group`](crate::StoreInput::get_group) instead.
+///
+/// # Arguments
+///
+/// * `app_name` - The application name
+/// * `group` - The group name
+///
+/// # Returns
+///
+/// Returns the values of the group as JSON strings by key, or an empty map if the
+/// group doesn't exist.
+///
+/// # Errors
+///
+/// Returns an error if the group cannot be loaded.
+///
+/// # Example
+///
+/// ```ignore
+/// use iced_store_plugin::{AppName, read_group};
+///
+/// async fn count_settings() -> Result<usize, String> {
+///     let app_name = AppName::new("com", "example", "myapp");
+///     let values = read_group(&app_name, "settings").await?;
+///     Ok(values.len())
+/// }
+/// ```
+pub async fn read_group(
+    app_name: &AppName,
+    group: &str,
+) -> Result<HashMap<String, String>, String> {
+    load_group(app_name, group).await
+}
@@ -20,7 +20,7 @@
 //!
 //! ```ignore
 //! use iced_store_plugin::{StorePlugin, StoreInput, StoreOutput, AppName};
-//! use iced_plugins::PluginManagerBuilder;
+//! use iced_plugins::{Capability, PluginManagerBuilder};
 //! use serde::{Deserialize, Serialize};
 //!
 //! #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -30,10 +30,11 @@
 //! }
 //!
 //! fn main() -> iced::Result {
-//!     let mut builder = PluginManagerBuilder::new();
+//!     let mut builder = PluginManagerBuilder::new()
+//!         .with_capabilities::<StorePlugin>([Capability::FsRead, Capability::FsWrite]);
 //!     let app_name = AppName::new("com", "mycompany", "myapp");
 //!     let store_handle = builder.install(StorePlugin::new(app_name));
-//!     let (plugins, init_task) = builder.build();
+//!     let (plugins, init_task) = builder.build().expect("failed to build plugins");
 //!
 //!     // Set a value
 //!     let prefs = UserPrefs {
@@ -98,7 +99,7 @@ mod storage;
 
 // Re-export public API
 pub use app_name::AppName;
-pub use helpers::{delete_value, has_value, list_keys, read_value, write_value};
+pub use helpers::{delete_value, has_value, list_keys, read_group, read_value, write_value};
 pub use messages::{StoreInput, StoreMessage, StoreOutput};
 pub use plugin::{StorePlugin, StoreState};
 pub use storage::{get_group_path, storage_dir};
@@ -1,43 +1,8 @@
 //! Message types for the store plugin
 
+use iced_plugins::PluginInput;
 use serde::{Serialize, de::DeserializeOwned};
-
-/// Public input API that applications use to interact with the store plugin
-///
-/// This is the primary interface for sending commands to the store plugin.
-///
-/// # Example
-///
-/// ```ignore
-/// use iced_store_plugin::{StoreInput, AppName};
-///
-/// let app_name = AppName::new("com", "example", "myapp");
-/// let input = StoreInput::set("settings", "theme", "dark");
-/// // dispatch to plugin using handle.dispatch(input)
-/// ```
-#[derive(Clone, Debug)]
-pub enum StoreInput {
-    /// Set a value in the store
-    Set {
-        group: String,
-        key: String,
-        value: String,
-    },
-    /// Get a value from the store
-    Get { group: String, key: String },
-    /// Delete a value from the store
-    Delete { group: String, key: String },
-}
-
-impl From<StoreInput> for StoreMessage {
-    fn from(input: StoreInput) -> Self {
-        match input {
-            StoreInput::Set { group, key, value } => StoreMessage::Set { group, key, value },
-            StoreInput::Get { group, key } => StoreMessage::Get { group, key },
-            StoreInput::Delete { group, key } => StoreMessage::Delete { group, key },
-        }
-    }
-}
+use std::collections::HashMap;
 
 impl StoreInput {
     /// Create a Set input with automatic serialization
@@ -59,7 +24,7 @@ impl StoreInput {
         T: Serialize,
     {
         let value = serde_json::to_string(&value).unwrap_or_else(|e| {
-            eprintln!("Failed to serialize value: {}", e);
+            tracing::error!(error = %e, "failed to serialize store value");
             String::new()
         });
 
@@ -86,6 +51,21 @@ impl StoreInput {
         }
     }
 
+    /// Create a GetGroup input, for all values of a group
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// use iced_store_plugin::StoreInput;
+    ///
+    /// let input = StoreInput::get_group("settings");
+    /// ```
+    pub fn get_group(group: impl Into<String>) -> Self {
+        Self::GetGroup {
+            group: group.into(),
+        }
+    }
+
     /// Create a Delete input
     ///
     /// # Example
@@ -105,27 +85,60 @@ impl StoreInput {
 
 /// Internal messages that the store plugin handles
 ///
-/// Note: This is for internal use. Applications should use `StoreInput` instead.
-#[derive(Clone, Debug)]
+/// Note: This is for internal use. Applications should use `StoreInput` instead, which
+/// is generated from the variants not marked `#[internal]`.
+#[derive(Clone, Debug, PluginInput)]
+#[input(
+    /// Public input API that applications use to interact with the store plugin
+    ///
+    /// This is the primary interface for sending commands to the store plugin. Create
+    /// inputs with [`StoreInput::set`], [`StoreInput::get`], [`StoreInput::get_group`] and
+    /// [`StoreInput::delete`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use iced_store_plugin::StoreInput;
+    ///
+    /// let input = StoreInput::set("settings", "theme", "dark");
+    /// assert!(matches!(input, StoreInput::Set { value, .. } if value == "\"dark\""));
+    /// // dispatch to plugin using handle.dispatch(input)
+    /// ```
+)]
 pub enum StoreMessage {
-    /// Set a value
+    /// Set a value in the store
     Set {
         group: String,
         key: String,
         value: String,
     },
-    /// Get a value
+    /// Get a value from the store
     Get { group: String, key: String },
-    /// Delete a value
+    /// Get all values of a group from the store
+    GetGroup { group: String },
+    /// Delete a value from the store
     Delete { group: String, key: String },
     /// Save result
-    SaveResult { group: String, success: bool },
+    #[internal]
+    SaveResult {
+        group: String,
+        key: String,
+        deleted: bool,
+        success: bool,
+    },
     /// Get result
+    #[internal]
     GetResult {
         group: String,
         key: String,
         value: Option<String>,
     },
+    /// GetGroup result, with the values on disk
+    #[internal]
+    GroupResult {
+        group: String,
+        values: Result<HashMap<String, String>, String>,
+    },
 }
 
 /// Output messages emitted by the store plugin
@@ -151,7 +164,7 @@ pub enum StoreMessage {
 /// ```
 #[derive(Clone, Debug)]
 pub enum StoreOutput {
-    /// A value was set successfully
+    /// A value was set and its group written to disk
     Set { group: String, key: String },
     /// A value was retrieved
     Get {
@@ -159,9 +172,14 @@ pub enum StoreOutput {
         key: String,
         value: String,
     },
+    /// All values of a group were retrieved, as JSON strings by key
+    Group {
+        group: String,
+        values: HashMap<String, String>,
+    },
     /// A value was not found
     NotFound { group: String, key: String },
-    /// A value was deleted successfully
+    /// A value was deleted and its group written to disk
     Deleted { group: String, key: String },
     /// An error occurred
     Error { message: String },
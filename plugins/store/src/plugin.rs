@@ -4,8 +4,11 @@ use crate::app_name::AppName;
 use crate::messages::{StoreInput, StoreMessage, StoreOutput};
 use crate::storage::{load_group, save_group};
 use iced::{Subscription, Task};
-use iced_plugins::Plugin;
+use iced_plugins::{Capability, Plugin, PluginContext};
 use std::collections::HashMap;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use tokio::sync::Notify;
 
 /// The plugin state held by the PluginManager
 ///
@@ -16,6 +19,74 @@ pub struct StoreState {
     store: HashMap<String, HashMap<String, String>>,
     /// Application name for storage
     app_name: AppName,
+    /// Saves that are still being written to disk
+    pending_saves: Arc<PendingSaves>,
+}
+
+/// Counts the saves in flight so shutdown can wait for them
+#[derive(Debug, Default)]
+struct PendingSaves {
+    count: AtomicUsize,
+    done: Notify,
+}
+
+impl PendingSaves {
+    /// Register a save, which is finished when the returned guard is dropped
+    fn start(self: &Arc<Self>) -> SaveGuard {
+        self.count.fetch_add(1, Ordering::SeqCst);
+        SaveGuard(Arc::clone(self))
+    }
+
+    /// Wait until no save is in flight
+    async fn wait(&self) {
+        loop {
+            let done = self.done.notified();
+            if self.count.load(Ordering::SeqCst) == 0 {
+                return;
+            }
+            done.await;
+        }
+    }
+}
+
+struct SaveGuard(Arc<PendingSaves>);
+
+impl Drop for SaveGuard {
+    fn drop(&mut self) {
+        if self.0.count.fetch_sub(1, Ordering::SeqCst) == 1 {
+            self.0.done.notify_waiters();
+        }
+    }
+}
+
+/// Write a group to disk after `key` was set or deleted, reporting back with a
+/// [`StoreMessage::SaveResult`]
+fn save(
+    state: &StoreState,
+    group: String,
+    key: String,
+    data: HashMap<String, String>,
+    deleted: bool,
+) -> Task<StoreMessage> {
+    let app_name = state.app_name.clone();
+    let save = state.pending_saves.start();
+
+    Task::future(async move {
+        let success = match save_group(&app_name, &group, data).await {
+            Ok(()) => true,
+            Err(error) => {
+                tracing::error!(group = %group, error, "failed to save store group");
+                false
+            }
+        };
+        drop(save);
+        StoreMessage::SaveResult {
+            group,
+            key,
+            deleted,
+            success,
+        }
+    })
 }
 
 /// Store plugin that manages persistent key-value storage
@@ -30,13 +101,14 @@ pub struct StoreState {
 ///
 /// ```ignore
 /// use iced_store_plugin::{StorePlugin, StoreInput, AppName};
-/// use iced_plugins::PluginManagerBuilder;
+/// use iced_plugins::{Capability, PluginManagerBuilder};
 ///
 /// fn setup_plugins() {
-///     let mut builder = PluginManagerBuilder::new();
+///     let mut builder = PluginManagerBuilder::new()
+///         .with_capabilities::<StorePlugin>([Capability::FsRead, Capability::FsWrite]);
 ///     let app_name = AppName::new("com", "example", "myapp");
 ///     let store_handle = builder.install(StorePlugin::new(app_name));
-///     let (plugins, init_task) = builder.build();
+///     let (plugins, init_task) = builder.build().expect("failed to build plugins");
 ///
 ///     // Use the plugin
 ///     store_handle.dispatch(StoreInput::set("settings", "theme", "dark"));
@@ -77,10 +149,15 @@ impl Plugin for StorePlugin {
         "store"
     }
 
-    fn init(&self) -> (Self::State, Task<Self::Message>) {
+    fn capabilities(&self) -> Vec<Capability> {
+        vec![Capability::FsRead, Capability::FsWrite]
+    }
+
+    fn init(&self, _ctx: &mut PluginContext<'_>) -> (Self::State, Task<Self::Message>) {
         let state = StoreState {
             store: HashMap::new(),
             app_name: self.app_name.clone(),
+            pending_saves: Arc::new(PendingSaves::default()),
         };
         (state, Task::none())
     }
@@ -89,6 +166,7 @@ impl Plugin for StorePlugin {
         &self,
         state: &mut Self::State,
         message: Self::Message,
+        _ctx: &mut PluginContext<'_>,
     ) -> (Task<Self::Message>, Option<Self::Output>) {
         match message {
             StoreMessage::Set { group, key, value } => {
@@ -98,22 +176,8 @@ impl Plugin for StorePlugin {
                     .or_insert_with(HashMap::new)
                     .insert(key.clone(), value);
 
-                let app_name = state.app_name.clone();
                 let data = state.store.get(&group).cloned().unwrap_or_default();
-                let group_clone = group.clone();
-
-                let task = Task::perform(
-                    async move {
-                        let success = save_group(&app_name, &group_clone, data).await.is_ok();
-                        StoreMessage::SaveResult {
-                            group: group_clone,
-                            success,
-                        }
-                    },
-                    std::convert::identity,
-                );
-
-                (task, Some(StoreOutput::Set { group, key }))
+                (save(state, group, key, data, false), None)
             }
 
             StoreMessage::Get { group, key } => {
@@ -172,41 +236,60 @@ impl Plugin for StorePlugin {
                 (Task::none(), Some(output))
             }
 
+            StoreMessage::GetGroup { group } => {
+                let app_name = state.app_name.clone();
+                let task = Task::perform(
+                    async move {
+                        let values = load_group(&app_name, &group).await;
+                        StoreMessage::GroupResult { group, values }
+                    },
+                    std::convert::identity,
+                );
+
+                (task, None)
+            }
+
+            StoreMessage::GroupResult { group, values } => {
+                let mut values = match values {
+                    Ok(values) => values,
+                    Err(message) => return (Task::none(), Some(StoreOutput::Error { message })),
+                };
+
+                // Values set since the group was read are newer than the ones on disk
+                let cached = state.store.entry(group.clone()).or_default();
+                values.extend(cached.drain());
+                cached.clone_from(&values);
+
+                (Task::none(), Some(StoreOutput::Group { group, values }))
+            }
+
             StoreMessage::Delete { group, key } => {
                 if let Some(group_data) = state.store.get_mut(&group)
                     && group_data.remove(&key).is_some()
                 {
-                    let app_name = state.app_name.clone();
                     let data = group_data.clone();
-                    let group_clone = group.clone();
-
-                    let task = Task::perform(
-                        async move {
-                            let success = save_group(&app_name, &group_clone, data).await.is_ok();
-                            StoreMessage::SaveResult {
-                                group: group_clone,
-                                success,
-                            }
-                        },
-                        std::convert::identity,
-                    );
-
-                    return (task, Some(StoreOutput::Deleted { group, key }));
+                    return (save(state, group, key, data, true), None);
                 }
 
                 (Task::none(), Some(StoreOutput::NotFound { group, key }))
             }
 
-            StoreMessage::SaveResult { group, success } => {
-                if !success {
-                    return (
-                        Task::none(),
-                        Some(StoreOutput::Error {
-                            message: format!("Failed to save group: {}", group),
-                        }),
-                    );
-                }
-                (Task::none(), None)
+            StoreMessage::SaveResult {
+                group,
+                key,
+                deleted,
+                success,
+            } => {
+                let output = if !success {
+                    StoreOutput::Error {
+                        message: format!("Failed to save group: {}", group),
+                    }
+                } else if deleted {
+                    StoreOutput::Deleted { group, key }
+                } else {
+                    StoreOutput::Set { group, key }
+                };
+                (Task::none(), Some(output))
             }
         }
     }
@@ -214,4 +297,13 @@ impl Plugin for StorePlugin {
     fn subscription(&self, _state: &Self::State) -> Subscription<Self::Message> {
         Subscription::none()
     }
+
+    fn shutdown(
+        &self,
+        state: &mut Self::State,
+        _ctx: &mut PluginContext<'_>,
+    ) -> Task<Self::Message> {
+        let pending_saves = Arc::clone(&state.pending_saves);
+        Task::future(async move { pending_saves.wait().await }).discard()
+    }
 }
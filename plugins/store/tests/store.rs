@@ -0,0 +1,93 @@
+use iced_plugins::{Capability, PluginManagerBuilder, PluginTester};
+use iced_store_plugin::{AppName, StoreInput, StoreOutput, StorePlugin, storage_dir};
+
+/// An app name of its own for each test, so tests don't share files
+fn app_name(test: &str) -> AppName {
+    AppName::new(
+        "dev",
+        "iced-plugins-tests",
+        format!("store-{}-{}", test, std::process::id()),
+    )
+}
+
+fn store(app_name: &AppName) -> PluginTester<StorePlugin> {
+    let grants = PluginManagerBuilder::new()
+        .with_capabilities::<StorePlugin>([Capability::FsRead, Capability::FsWrite]);
+    PluginTester::with_builder(grants, StorePlugin::new(app_name.clone()))
+}
+
+fn cleanup(app_name: &AppName) {
+    let _ = std::fs::remove_dir_all(storage_dir(app_name));
+}
+
+#[test]
+fn gets_what_was_set() {
+    let app_name = app_name("round-trip");
+    let mut tester = store(&app_name);
+
+    tester.dispatch(StoreInput::set("ui", "theme", "dark"));
+    assert!(matches!(
+        &tester.take_outputs()[..],
+        [StoreOutput::Set { group, key }] if group == "ui" && key == "theme"
+    ));
+
+    let output = tester.call(StoreInput::get("ui", "theme")).unwrap();
+    assert_eq!(output.as_value::<String>().as_deref(), Some("dark"));
+
+    cleanup(&app_name);
+}
+
+#[test]
+fn reads_values_written_by_an_earlier_run() {
+    let app_name = app_name("persist");
+    let mut tester = store(&app_name);
+    tester.dispatch(StoreInput::set("ui", "font_size", 14));
+    drop(tester);
+
+    let mut tester = store(&app_name);
+    let output = tester.call(StoreInput::get("ui", "font_size")).unwrap();
+    assert_eq!(output.as_value::<u32>(), Some(14));
+
+    cleanup(&app_name);
+}
+
+#[test]
+fn deletes_values() {
+    let app_name = app_name("delete");
+    let mut tester = store(&app_name);
+    tester.dispatch(StoreInput::set("ui", "theme", "dark"));
+    tester.take_outputs();
+
+    tester.dispatch(StoreInput::delete("ui", "theme"));
+    assert!(matches!(
+        &tester.take_outputs()[..],
+        [StoreOutput::Deleted { key, .. }] if key == "theme"
+    ));
+
+    let output = tester.call(StoreInput::get("ui", "theme")).unwrap();
+    assert!(matches!(output, StoreOutput::NotFound { .. }));
+
+    cleanup(&app_name);
+}
+
+#[test]
+fn gets_a_group_from_memory_and_disk() {
+    let app_name = app_name("group");
+    let mut tester = store(&app_name);
+    tester.dispatch(StoreInput::set("ui", "theme", "dark"));
+    drop(tester);
+
+    let mut tester = store(&app_name);
+    tester.dispatch(StoreInput::set("ui", "theme", "light"));
+    tester.dispatch(StoreInput::set("ui", "font_size", 14));
+    let output = tester.call(StoreInput::get_group("ui")).unwrap();
+    let StoreOutput::Group { group, values } = output else {
+        panic!("expected a group, got {:?}", output);
+    };
+    assert_eq!(group, "ui");
+    assert_eq!(values.len(), 2);
+    assert_eq!(values["theme"], "\"light\"");
+    assert_eq!(values["font_size"], "14");
+
+    cleanup(&app_name);
+}
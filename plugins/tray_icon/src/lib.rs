@@ -8,7 +8,7 @@ mod menu;
 use iced::futures::SinkExt;
 use iced::futures::channel::mpsc::Sender;
 use iced::{Subscription, Task};
-use iced_plugins::Plugin;
+use iced_plugins::{Capability, Plugin, PluginContext, PluginInput};
 use std::collections::HashMap;
 use std::sync::Arc;
 use tokio::time::Duration;
@@ -24,36 +24,10 @@ use menu::{NativeMenuItem, update_menu_items};
 
 use crate::menu::{build_native_menu, create_icon};
 
-/// Public input API that applications use
-#[derive(Clone, Debug)]
-pub enum TrayIconInput {
-    /// Update the tray icon
-    SetIcon(Vec<u8>),
-    /// Update the tooltip
-    SetTooltip(Option<String>),
-    /// Update the menu
-    UpdateMenu(Menu),
-    /// Show the tray icon
-    Show,
-    /// Hide the tray icon
-    Hide,
-}
-
-impl From<TrayIconInput> for TrayIconMessage {
-    fn from(input: TrayIconInput) -> Self {
-        match input {
-            TrayIconInput::SetIcon(data) => TrayIconMessage::SetIcon(data),
-            TrayIconInput::SetTooltip(tooltip) => TrayIconMessage::SetTooltip(tooltip),
-            TrayIconInput::UpdateMenu(menu) => TrayIconMessage::UpdateMenu(menu),
-            TrayIconInput::Show => TrayIconMessage::Show,
-            TrayIconInput::Hide => TrayIconMessage::Hide,
-        }
-    }
-}
-
 /// Internal messages that the tray icon plugin handles
 /// Note: This is for internal use. Applications should use `TrayIconInput` instead.
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PluginInput)]
+#[input(doc = "Public input API that applications use")]
 pub enum TrayIconMessage {
     /// Update the tray icon
     SetIcon(Vec<u8>),
@@ -62,8 +36,10 @@ pub enum TrayIconMessage {
     /// Update the menu
     UpdateMenu(Menu),
     /// Menu event occurred
+    #[internal]
     MenuEvent(String),
     /// Tray icon event occurred
+    #[internal]
     TrayEvent(TrayIconEventKind),
     /// Show the tray icon
     Show,
@@ -201,13 +177,17 @@ impl Plugin for TrayIconPlugin {
         "tray_icon"
     }
 
-    fn init(&self) -> (Self::State, Task<Self::Message>) {
+    fn capabilities(&self) -> Vec<Capability> {
+        vec![Capability::Tray]
+    }
+
+    fn init(&self, _ctx: &mut PluginContext<'_>) -> (Self::State, Task<Self::Message>) {
         // Create icon if data is provided
         let icon = if let Some(ref icon_data) = self.icon_data {
             match create_icon(icon_data) {
                 Ok(icon) => Some(icon),
                 Err(e) => {
-                    eprintln!("Failed to create tray icon: {}", e);
+                    tracing::error!(error = %e, "failed to create tray icon");
                     None
                 }
             }
@@ -242,7 +222,7 @@ impl Plugin for TrayIconPlugin {
             match builder.build() {
                 Ok(tray) => Some(TrayIconWrapper::new(tray)),
                 Err(e) => {
-                    eprintln!("Failed to build tray icon: {}", e);
+                    tracing::error!(error = %e, "failed to build tray icon");
                     None
                 }
             }
@@ -265,6 +245,7 @@ impl Plugin for TrayIconPlugin {
         &self,
         state: &mut Self::State,
         message: Self::Message,
+        _ctx: &mut PluginContext<'_>,
     ) -> (Task<Self::Message>, Option<Self::Output>) {
         match message {
             TrayIconMessage::SetIcon(bytes) => {
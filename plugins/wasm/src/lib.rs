@@ -0,0 +1,502 @@
+//! WebAssembly Plugin for Iced
+//!
+//! This plugin runs plugins compiled to WebAssembly components in a sandbox, so
+//! untrusted community plugins can be installed next to native ones. A component
+//! implements the `plugin` world in `wit/plugin.wit`.
+//!
+//! # Features
+//!
+//! - Inputs and outputs cross the sandbox as JSON, and are routed like those of any
+//!   other plugin through `PluginHandle` and `PluginMessage`
+//! - Components can store JSON values, persisted through the store plugin, which must be
+//!   installed as well, so the plugin needs the `fs-read` and `fs-write` capabilities
+//! - Components can set timers that call them back periodically
+//! - Every call into a component is limited in fuel and time, and its memory is limited
+//!   in size. A component that runs out of any, or returns an error, is faulted
+//!
+//! # Example
+//!
+//! ```ignore
+//! use iced_plugins::{Capability, Plugin, PluginManagerBuilder};
+//! use iced_store_plugin::{AppName, StorePlugin};
+//! use iced_wasm_plugin::WasmPlugin;
+//! use serde_json::json;
+//!
+//! let app_name = AppName::new("com", "example", "myapp");
+//! let mut builder = PluginManagerBuilder::new()
+//!     .with_plugin(StorePlugin::new(app_name.clone()))
+//!     .with_capabilities::<StorePlugin>([Capability::FsRead, Capability::FsWrite])
+//!     .with_capabilities::<WasmPlugin>([Capability::FsRead, Capability::FsWrite]);
+//!
+//! // Each component is installed under its file name, e.g. `greeter` for `greeter.wasm`
+//! for plugin in WasmPlugin::load_dir("plugins").expect("failed to read plugins") {
+//!     match plugin {
+//!         Ok(plugin) => {
+//!             let name = plugin.name();
+//!             builder = builder.with_plugin_keyed(name, plugin.with_fuel(50_000_000));
+//!         }
+//!         Err(error) => eprintln!("skipping plugin: {}", error),
+//!     }
+//! }
+//! let (plugins, init_task) = builder.build().expect("failed to build plugins");
+//!
+//! let greeter = plugins.get_handle_keyed::<WasmPlugin>("greeter").unwrap();
+//! let task = greeter.dispatch(json!({ "name": "iced" }));
+//! ```
+
+use iced::futures::channel::oneshot;
+use iced::futures::future::BoxFuture;
+use iced::time::every;
+use iced::{Subscription, Task};
+use iced_plugins::{Capability, Dependency, Plugin, PluginContext, intern_name};
+use iced_store_plugin::{StoreInput, StoreOutput, StorePlugin};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::time::Duration;
+use wasmtime::component::{Component, Linker};
+use wasmtime::{Config, Engine, Store, StoreLimits, StoreLimitsBuilder};
+
+mod bindings {
+    wasmtime::component::bindgen!({ path: "wit", world: "plugin" });
+}
+
+use bindings::iced_plugins::wasm::host;
+
+/// Fuel a component may use per call unless set with [`WasmPlugin::with_fuel`]
+pub const DEFAULT_FUEL: u64 = 1_000_000_000;
+
+/// How long a call may take unless set with [`WasmPlugin::with_timeout`]
+///
+/// Calls run on the UI thread, so this is kept below a few frames.
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_millis(50);
+
+/// Bytes each linear memory of a component may grow to unless set with
+/// [`WasmPlugin::with_max_memory`]
+pub const DEFAULT_MAX_MEMORY: usize = 64 * 1024 * 1024;
+
+/// Shortest interval of a component's timers, longer than a frame so timers can't
+/// keep the application busy
+pub const MIN_TIMER_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Core instances and tables a component may create, enough for components built by
+/// the usual toolchains
+const MAX_INSTANCES: usize = 16;
+const MAX_TABLES: usize = 16;
+
+/// How often the engine's epoch advances, which is the resolution of call timeouts
+const EPOCH_TICK: Duration = Duration::from_millis(10);
+
+/// The engine shared by all components, with fuel and epoch interruption enabled
+fn engine() -> &'static Engine {
+    static ENGINE: OnceLock<Engine> = OnceLock::new();
+    ENGINE.get_or_init(|| {
+        let mut config = Config::new();
+        config.consume_fuel(true).epoch_interruption(true);
+        let engine = Engine::new(&config).expect("failed to create wasm engine");
+
+        let ticker = engine.clone();
+        std::thread::Builder::new()
+            .name("wasm-epoch".to_string())
+            .spawn(move || {
+                loop {
+                    std::thread::sleep(EPOCH_TICK);
+                    ticker.increment_epoch();
+                }
+            })
+            .expect("failed to spawn wasm epoch thread");
+
+        engine
+    })
+}
+
+/// Messages that the wasm plugin handles
+#[derive(Clone, Debug)]
+pub enum WasmMessage {
+    /// An input for the component, as JSON
+    Input(Value),
+    /// A timer set by the component fired
+    Timer(u32),
+}
+
+impl From<Value> for WasmMessage {
+    fn from(input: Value) -> Self {
+        WasmMessage::Input(input)
+    }
+}
+
+/// What the component sees of the application
+#[derive(Default)]
+struct HostState {
+    /// Stored values of the component, as JSON strings by key
+    values: HashMap<String, String>,
+    /// Values stored since they were last handed to the store plugin
+    writes: Vec<(String, String)>,
+    /// Timer intervals by id
+    timers: BTreeMap<u32, Duration>,
+    /// Limits on the memories, tables and instances of the component
+    limits: StoreLimits,
+}
+
+impl host::Host for HostState {
+    fn store_get(&mut self, key: String) -> Option<String> {
+        self.values.get(&key).cloned()
+    }
+
+    fn store_set(&mut self, key: String, value: String) -> Result<(), String> {
+        serde_json::from_str::<Value>(&value).map_err(|e| format!("Invalid JSON value: {}", e))?;
+        self.values.insert(key.clone(), value.clone());
+        self.writes.push((key, value));
+        Ok(())
+    }
+
+    fn set_timer(&mut self, id: u32, interval_ms: u64) {
+        let interval = Duration::from_millis(interval_ms).max(MIN_TIMER_INTERVAL);
+        self.timers.insert(id, interval);
+    }
+
+    fn cancel_timer(&mut self, id: u32) {
+        self.timers.remove(&id);
+    }
+}
+
+/// A running component
+struct Instance {
+    store: Store<HostState>,
+    bindings: bindings::Plugin,
+}
+
+impl Instance {
+    /// Instantiate a component with its stored values and call its `init`
+    fn start(
+        component: &Component,
+        values: HashMap<String, String>,
+        limits: Limits,
+    ) -> Result<Self, String> {
+        let mut linker = Linker::new(engine());
+        bindings::Plugin::add_to_linker(&mut linker, |state: &mut HostState| state)
+            .map_err(|e| format!("Failed to link host functions: {:#}", e))?;
+
+        let mut store = Store::new(
+            engine(),
+            HostState {
+                values,
+                limits: StoreLimitsBuilder::new()
+                    .memory_size(limits.max_memory)
+                    .instances(MAX_INSTANCES)
+                    .tables(MAX_TABLES)
+                    .trap_on_grow_failure(true)
+                    .build(),
+                ..HostState::default()
+            },
+        );
+        store.limiter(|state| &mut state.limits);
+        limits.apply(&mut store)?;
+        let bindings = bindings::Plugin::instantiate(&mut store, component, &linker)
+            .map_err(|e| format!("Failed to instantiate component: {:#}", e))?;
+
+        let mut instance = Self { store, bindings };
+        instance.call(limits, |bindings, store| bindings.call_init(store))?;
+        Ok(instance)
+    }
+
+    /// Call an export of the component within the limits
+    ///
+    /// Traps, such as running out of fuel, are reported by their cause without the
+    /// wasm backtrace.
+    fn call<R>(
+        &mut self,
+        limits: Limits,
+        export: impl FnOnce(
+            &bindings::Plugin,
+            &mut Store<HostState>,
+        ) -> wasmtime::Result<Result<R, String>>,
+    ) -> Result<R, String> {
+        limits.apply(&mut self.store)?;
+        export(&self.bindings, &mut self.store).map_err(|trap| trap.root_cause().to_string())?
+    }
+
+    /// Hand the values stored since the last flush to the store plugin
+    fn flush(&mut self, group: &str, ctx: &mut PluginContext<'_>) {
+        for (key, value) in self.store.data_mut().writes.drain(..) {
+            let input = StoreInput::Set {
+                group: group.to_string(),
+                key,
+                value,
+            };
+            if !ctx.dispatch::<StorePlugin>(input) {
+                tracing::warn!(
+                    group,
+                    "store plugin is unavailable, wasm plugin value not saved"
+                );
+            }
+        }
+    }
+}
+
+/// Fuel and time a single call may use, and memory the component may use
+#[derive(Clone, Copy, Debug)]
+struct Limits {
+    fuel: u64,
+    timeout: Duration,
+    max_memory: usize,
+}
+
+impl Limits {
+    fn apply(self, store: &mut Store<HostState>) -> Result<(), String> {
+        store
+            .set_fuel(self.fuel)
+            .map_err(|e| format!("Failed to set fuel: {:#}", e))?;
+        let ticks = self.timeout.as_millis().div_ceil(EPOCH_TICK.as_millis());
+        store.set_epoch_deadline(ticks.max(1) as u64);
+        Ok(())
+    }
+}
+
+/// State of a wasm plugin
+pub struct WasmState {
+    /// The running component, or why it could not be started
+    instance: Result<Instance, String>,
+}
+
+impl std::fmt::Debug for WasmState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.instance {
+            Ok(instance) => f
+                .debug_struct("WasmState")
+                .field("values", &instance.store.data().values)
+                .field("timers", &instance.store.data().timers)
+                .finish(),
+            Err(error) => f.debug_struct("WasmState").field("error", error).finish(),
+        }
+    }
+}
+
+/// Plugin that runs a WebAssembly component
+#[derive(Clone)]
+pub struct WasmPlugin {
+    name: &'static str,
+    path: PathBuf,
+    component: Component,
+    limits: Limits,
+}
+
+impl std::fmt::Debug for WasmPlugin {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("WasmPlugin")
+            .field("name", &self.name)
+            .field("path", &self.path)
+            .field("limits", &self.limits)
+            .finish()
+    }
+}
+
+impl WasmPlugin {
+    /// Load and compile a component, named after its file name
+    ///
+    /// Values the component stores are kept in the store group `wasm_<name>` of the
+    /// store plugin.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, String> {
+        let path = path.as_ref().to_path_buf();
+        let name = path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .ok_or_else(|| format!("Invalid plugin file name: {}", path.display()))?;
+        let name = intern_name(name);
+
+        let component = Component::from_file(engine(), &path)
+            .map_err(|e| format!("Failed to load {}: {:#}", path.display(), e))?;
+
+        Ok(Self {
+            name,
+            path,
+            component,
+            limits: Limits {
+                fuel: DEFAULT_FUEL,
+                timeout: DEFAULT_TIMEOUT,
+                max_memory: DEFAULT_MAX_MEMORY,
+            },
+        })
+    }
+
+    /// Load every `.wasm` component in a directory, in file name order
+    ///
+    /// Returns the result of each file, so one component that fails to load doesn't
+    /// keep the others from loading, or an error if the directory can't be read.
+    pub fn load_dir(dir: impl AsRef<Path>) -> Result<Vec<Result<Self, String>>, String> {
+        let dir = dir.as_ref();
+        let read_error = |e: std::io::Error| format!("Failed to read {}: {}", dir.display(), e);
+
+        let mut paths = Vec::new();
+        for entry in std::fs::read_dir(dir).map_err(read_error)? {
+            let path = entry.map_err(read_error)?.path();
+            if path
+                .extension()
+                .is_some_and(|extension| extension == "wasm")
+            {
+                paths.push(path);
+            }
+        }
+        paths.sort();
+
+        Ok(paths.into_iter().map(Self::load).collect())
+    }
+
+    /// Set the fuel a single call into the component may use
+    pub fn with_fuel(mut self, fuel: u64) -> Self {
+        self.limits.fuel = fuel;
+        self
+    }
+
+    /// Set how long a single call into the component may take
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.limits.timeout = timeout;
+        self
+    }
+
+    /// Set how many bytes each linear memory of the component may grow to
+    ///
+    /// Growing past it traps, which faults the plugin.
+    pub fn with_max_memory(mut self, bytes: usize) -> Self {
+        self.limits.max_memory = bytes;
+        self
+    }
+
+    /// Get the path the component was loaded from
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Store group of the component's values
+    fn group(&self) -> String {
+        format!("wasm_{}", self.name)
+    }
+}
+
+impl Plugin for WasmPlugin {
+    type Input = Value;
+    type Message = WasmMessage;
+    type State = WasmState;
+    type Output = Value;
+
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn dependencies(&self) -> Vec<Dependency> {
+        vec![Dependency::on::<StorePlugin>()]
+    }
+
+    fn capabilities(&self) -> Vec<Capability> {
+        vec![Capability::FsRead, Capability::FsWrite]
+    }
+
+    fn init(&self, _ctx: &mut PluginContext<'_>) -> (Self::State, Task<Self::Message>) {
+        // Stands in until the component started in `init_async`
+        let state = WasmState {
+            instance: Err("still loading".to_string()),
+        };
+        (state, Task::none())
+    }
+
+    fn init_async(&self, ctx: &mut PluginContext<'_>) -> Option<BoxFuture<'static, Self::State>> {
+        let group = self.group();
+        let component = self.component.clone();
+        let limits = self.limits;
+
+        // Read through the store plugin, which may hold values not yet on disk
+        let (sender, receiver) = oneshot::channel();
+        let mut sender = Some(sender);
+        let read = ctx
+            .call::<StorePlugin>(StoreInput::get_group(group.clone()))
+            .map(move |result| {
+                if let Some(sender) = sender.take() {
+                    let _ = sender.send(result);
+                }
+            });
+        ctx.emit(read.discard());
+
+        Some(Box::pin(async move {
+            let values = match receiver.await {
+                Ok(Ok(StoreOutput::Group { values, .. })) => values,
+                Ok(Ok(StoreOutput::Error { message })) => {
+                    tracing::warn!(group, error = message, "failed to load wasm plugin values");
+                    HashMap::new()
+                }
+                Ok(Err(error)) => {
+                    tracing::warn!(group, %error, "failed to load wasm plugin values");
+                    HashMap::new()
+                }
+                Ok(Ok(_)) | Err(_) => HashMap::new(),
+            };
+            WasmState {
+                instance: Instance::start(&component, values, limits),
+            }
+        }))
+    }
+
+    fn update(
+        &self,
+        state: &mut Self::State,
+        message: Self::Message,
+        ctx: &mut PluginContext<'_>,
+    ) -> (Task<Self::Message>, Option<Self::Output>) {
+        let limits = self.limits;
+        let instance = match &mut state.instance {
+            Ok(instance) => instance,
+            Err(error) => {
+                ctx.fault(format!("failed to start: {}", error));
+                return (Task::none(), None);
+            }
+        };
+        let result = match message {
+            WasmMessage::Input(input) => instance.call(limits, |bindings, store| {
+                bindings.call_update(store, &input.to_string())
+            }),
+            WasmMessage::Timer(id) => {
+                instance.call(limits, |bindings, store| bindings.call_on_timer(store, id))
+            }
+        };
+        instance.flush(&self.group(), ctx);
+
+        let output = match result {
+            Ok(output) => output,
+            Err(error) => {
+                ctx.fault(error);
+                return (Task::none(), None);
+            }
+        };
+        match output.map(|output| serde_json::from_str(&output)) {
+            Some(Ok(output)) => (Task::none(), Some(output)),
+            Some(Err(error)) => {
+                ctx.fault(format!("emitted invalid JSON: {}", error));
+                (Task::none(), None)
+            }
+            None => (Task::none(), None),
+        }
+    }
+
+    fn subscription(&self, state: &Self::State) -> Subscription<Self::Message> {
+        let Ok(instance) = &state.instance else {
+            return Subscription::none();
+        };
+
+        Subscription::batch(instance.store.data().timers.iter().map(|(&id, &interval)| {
+            every(interval)
+                .with(id)
+                .map(|(id, _)| WasmMessage::Timer(id))
+        }))
+    }
+
+    fn shutdown(
+        &self,
+        state: &mut Self::State,
+        ctx: &mut PluginContext<'_>,
+    ) -> Task<Self::Message> {
+        if let Ok(instance) = &mut state.instance {
+            instance.flush(&self.group(), ctx);
+        }
+        Task::none()
+    }
+}
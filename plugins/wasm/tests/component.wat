@@ -0,0 +1,118 @@
+;; A component implementing the `plugin` world, for the tests
+;;
+;; `update` takes a JSON number and acts on its first digit:
+;;
+;; 1. loop forever, until out of fuel or time
+;; 2. grow its memory by a megabyte
+;; 3. set timer 7 to fire every millisecond
+;; 4. store `42` under `count`
+;; 5. emit the value stored under `count`, if any
+(component
+  (import "iced-plugins:wasm/host@0.1.0" (instance $host
+    (export "store-get" (func (param "key" string) (result (option string))))
+    (export "store-set" (func (param "key" string) (param "value" string) (result (result (error string)))))
+    (export "set-timer" (func (param "id" u32) (param "interval-ms" u64)))
+    (export "cancel-timer" (func (param "id" u32)))
+  ))
+
+  ;; Memory and allocator, instantiated first so host functions can be lowered into them
+  (core module $Libc
+    (memory (export "memory") 1)
+    (global $next (mut i32) (i32.const 1024))
+    (func (export "realloc") (param i32 i32 i32 i32) (result i32)
+      (local $ptr i32)
+      ;; Bump allocation, aligned to 8 bytes
+      (local.set $ptr
+        (i32.and (i32.add (global.get $next) (i32.const 7)) (i32.const -8)))
+      (global.set $next (i32.add (local.get $ptr) (local.get 3)))
+      (local.get $ptr))
+  )
+
+  (core module $Plugin
+    (import "libc" "memory" (memory 1))
+    (import "host" "store-get" (func $store-get (param i32 i32 i32)))
+    (import "host" "store-set" (func $store-set (param i32 i32 i32 i32 i32)))
+    (import "host" "set-timer" (func $set-timer (param i32 i64)))
+
+    ;; Strings, the return area of host calls at 64, and that of exports at 128
+    (data (i32.const 16) "count")
+    (data (i32.const 32) "42")
+
+    ;; `ok(none)`
+    (func $nothing (result i32)
+      (i32.store8 (i32.const 128) (i32.const 0))
+      (i32.store8 (i32.const 132) (i32.const 0))
+      (i32.const 128))
+
+    (func (export "init") (result i32)
+      (i32.store8 (i32.const 128) (i32.const 0))
+      (i32.const 128))
+
+    (func (export "update") (param $input i32) (param $len i32) (result i32)
+      (local $command i32)
+      (local.set $command (i32.load8_u (local.get $input)))
+
+      (if (i32.eq (local.get $command) (i32.const 0x31))
+        (then (loop $forever (br $forever))))
+
+      (if (i32.eq (local.get $command) (i32.const 0x32))
+        (then (drop (memory.grow (i32.const 16)))))
+
+      (if (i32.eq (local.get $command) (i32.const 0x33))
+        (then (call $set-timer (i32.const 7) (i64.const 1))))
+
+      (if (i32.eq (local.get $command) (i32.const 0x34))
+        (then
+          (call $store-set (i32.const 16) (i32.const 5) (i32.const 32) (i32.const 2) (i32.const 64))
+          ;; Pass an error on as `err(message)`
+          (if (i32.load8_u (i32.const 64))
+            (then
+              (i32.store8 (i32.const 128) (i32.const 1))
+              (i32.store (i32.const 132) (i32.load (i32.const 68)))
+              (i32.store (i32.const 136) (i32.load (i32.const 72)))
+              (return (i32.const 128))))))
+
+      (if (i32.eq (local.get $command) (i32.const 0x35))
+        (then
+          (call $store-get (i32.const 16) (i32.const 5) (i32.const 64))
+          ;; `ok(some(value))` if the value is stored
+          (if (i32.load8_u (i32.const 64))
+            (then
+              (i32.store8 (i32.const 128) (i32.const 0))
+              (i32.store8 (i32.const 132) (i32.const 1))
+              (i32.store (i32.const 136) (i32.load (i32.const 68)))
+              (i32.store (i32.const 140) (i32.load (i32.const 72)))
+              (return (i32.const 128))))))
+
+      (call $nothing))
+
+    (func (export "on-timer") (param i32) (result i32)
+      (call $nothing))
+  )
+
+  (core instance $libc (instantiate $Libc))
+  (alias core export $libc "memory" (core memory $memory))
+  (alias core export $libc "realloc" (core func $realloc))
+
+  (core func $store-get
+    (canon lower (func $host "store-get") (memory $memory) (realloc $realloc)))
+  (core func $store-set
+    (canon lower (func $host "store-set") (memory $memory) (realloc $realloc)))
+  (core func $set-timer (canon lower (func $host "set-timer")))
+
+  (core instance $plugin (instantiate $Plugin
+    (with "libc" (instance $libc))
+    (with "host" (instance
+      (export "store-get" (func $store-get))
+      (export "store-set" (func $store-set))
+      (export "set-timer" (func $set-timer))
+    ))
+  ))
+
+  (func (export "init") (result (result (error string)))
+    (canon lift (core func $plugin "init") (memory $memory)))
+  (func (export "update") (param "input" string) (result (result (option string) (error string)))
+    (canon lift (core func $plugin "update") (memory $memory) (realloc $realloc)))
+  (func (export "on-timer") (param "id" u32) (result (result (option string) (error string)))
+    (canon lift (core func $plugin "on-timer") (memory $memory)))
+)
@@ -0,0 +1,136 @@
+use iced_plugins::{Capability, Plugin, PluginManagerBuilder, PluginStatus, PluginTester};
+use iced_store_plugin::{AppName, StorePlugin, storage_dir};
+use iced_wasm_plugin::WasmPlugin;
+use serde_json::json;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// A directory of its own for each test, holding the compiled test component
+fn plugin_dir(test: &str) -> PathBuf {
+    let dir =
+        std::env::temp_dir().join(format!("iced-plugins-wasm-{}-{}", test, std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let component = wat::parse_str(include_str!("component.wat")).unwrap();
+    std::fs::write(dir.join("counter.wasm"), component).unwrap();
+    dir
+}
+
+/// An app name of its own for each test, so tests don't share files
+fn app_name(test: &str) -> AppName {
+    AppName::new(
+        "dev",
+        "iced-plugins-tests",
+        format!("wasm-{}-{}", test, std::process::id()),
+    )
+}
+
+fn counter(dir: &Path) -> WasmPlugin {
+    WasmPlugin::load(dir.join("counter.wasm")).unwrap()
+}
+
+fn wasm(app_name: &AppName, plugin: WasmPlugin) -> PluginTester<WasmPlugin> {
+    let builder = PluginManagerBuilder::new()
+        .with_plugin(StorePlugin::new(app_name.clone()))
+        .with_capabilities::<StorePlugin>([Capability::FsRead, Capability::FsWrite])
+        .with_capabilities::<WasmPlugin>([Capability::FsRead, Capability::FsWrite]);
+    PluginTester::with_builder(builder, plugin)
+}
+
+fn fault(tester: &PluginTester<WasmPlugin>) -> Option<String> {
+    match tester.manager().status(tester.handle()) {
+        PluginStatus::Faulted { reason } => Some(reason),
+        _ => None,
+    }
+}
+
+fn cleanup(dir: &Path, app_name: &AppName) {
+    let _ = std::fs::remove_dir_all(dir);
+    let _ = std::fs::remove_dir_all(storage_dir(app_name));
+}
+
+#[test]
+fn faults_when_out_of_fuel() {
+    let (dir, app_name) = (plugin_dir("fuel"), app_name("fuel"));
+    let mut tester = wasm(&app_name, counter(&dir).with_fuel(10_000));
+
+    tester.dispatch(json!(1));
+
+    let reason = fault(&tester).expect("the plugin faulted");
+    assert!(reason.contains("fuel"), "{}", reason);
+    cleanup(&dir, &app_name);
+}
+
+#[test]
+fn faults_when_out_of_time() {
+    let (dir, app_name) = (plugin_dir("time"), app_name("time"));
+    let plugin = counter(&dir)
+        .with_fuel(u64::MAX)
+        .with_timeout(Duration::from_millis(20));
+    let mut tester = wasm(&app_name, plugin);
+
+    tester.dispatch(json!(1));
+
+    let reason = fault(&tester).expect("the plugin faulted");
+    assert!(reason.contains("interrupt"), "{}", reason);
+    cleanup(&dir, &app_name);
+}
+
+#[test]
+fn faults_when_growing_past_the_memory_limit() {
+    let (dir, app_name) = (plugin_dir("memory"), app_name("memory"));
+
+    let mut tester = wasm(&app_name, counter(&dir));
+    tester.dispatch(json!(2));
+    assert_eq!(fault(&tester), None);
+
+    let mut tester = wasm(&app_name, counter(&dir).with_max_memory(512 * 1024));
+    tester.dispatch(json!(2));
+    assert!(fault(&tester).is_some());
+
+    cleanup(&dir, &app_name);
+}
+
+#[test]
+fn clamps_timer_intervals() {
+    let (dir, app_name) = (plugin_dir("timer"), app_name("timer"));
+    let mut tester = wasm(&app_name, counter(&dir));
+
+    tester.dispatch(json!(3));
+
+    // Asked for every millisecond
+    let state = format!("{:?}", tester.state());
+    assert!(state.contains("timers: {7: 100ms}"), "{}", state);
+    cleanup(&dir, &app_name);
+}
+
+#[test]
+fn stores_values_through_the_store() {
+    let (dir, app_name) = (plugin_dir("store"), app_name("store"));
+    let mut tester = wasm(&app_name, counter(&dir));
+    tester.dispatch(json!(5));
+    assert!(tester.take_outputs().is_empty());
+
+    tester.dispatch(json!(4));
+    drop(tester);
+
+    let mut tester = wasm(&app_name, counter(&dir));
+    tester.dispatch(json!(5));
+    assert_eq!(tester.take_outputs(), [json!(42)]);
+
+    cleanup(&dir, &app_name);
+}
+
+#[test]
+fn loads_each_component_of_a_directory_on_its_own() {
+    let dir = plugin_dir("load-dir");
+    std::fs::write(dir.join("broken.wasm"), b"not a component").unwrap();
+    std::fs::write(dir.join("notes.txt"), b"not a plugin").unwrap();
+
+    let plugins = WasmPlugin::load_dir(&dir).unwrap();
+
+    assert!(matches!(
+        &plugins[..],
+        [Err(error), Ok(plugin)] if error.contains("broken.wasm") && plugin.name() == "counter"
+    ));
+    let _ = std::fs::remove_dir_all(&dir);
+}
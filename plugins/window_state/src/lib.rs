@@ -6,11 +6,14 @@
 //! # Features
 //!
 //! - Automatic window state persistence per-application
-//! - Load state before app creation
+//! - Load state before app creation, or in the background while the app starts
 //! - Subscribe to window resize and move events
-//! - Debounced auto-save every 2 seconds
+//! - Debounced auto-save every 2 seconds, and a final save on shutdown
 //! - Only tracks the first window (main window) in multi-window apps
-//! - Uses the store plugin for persistence
+//! - Persists through the store plugin, which must be installed as well, so it needs the
+//!   `fs-read` and `fs-write` capabilities of the store
+//! - With the `config` feature, the auto-save interval can be set in the config file
+//!   under `[window_state]` and changes while the app runs
 //!
 //! # Example
 //!
@@ -34,18 +37,28 @@
 //!         .run()
 //! }
 //!
-//! // In your app initialization:
-//! let mut plugins = PluginManager::new();
-//! plugins.install(WindowStatePlugin::new(app_name));
+//! // In your app initialization, install the store with the app name the state was
+//! // loaded from:
+//! let (plugins, init_task) = PluginManagerBuilder::new()
+//!     .with_plugin(StorePlugin::new(app_name.clone()))
+//!     .with_capabilities::<StorePlugin>([Capability::FsRead, Capability::FsWrite])
+//!     .with_plugin(WindowStatePlugin::new(app_name))
+//!     .with_capabilities::<WindowStatePlugin>([Capability::FsRead, Capability::FsWrite])
+//!     .build()
+//!     .expect("failed to build plugins");
 //! ```
 
 use iced::Event::Window;
 use iced::event::listen_with;
+use iced::futures::channel::oneshot;
+use iced::futures::future::BoxFuture;
 use iced::time::every;
 use iced::window::{Event, Id};
 use iced::{Subscription, Task};
-use iced_plugins::Plugin;
-use iced_store_plugin::{read_value, write_value};
+#[cfg(feature = "config")]
+use iced_plugins::Configurable;
+use iced_plugins::{Capability, Dependency, Plugin, PluginContext, PluginInput};
+use iced_store_plugin::{StoreInput, StoreOutput, StorePlugin, read_value};
 use serde::{Deserialize, Serialize};
 use std::time::Duration;
 
@@ -141,32 +154,29 @@ pub enum WindowEvent {
     Opened(Id),
 }
 
-#[derive(Clone, Debug)]
-pub enum WindowStateInput {}
-
-impl From<WindowStateInput> for WindowStateMessage {
-    fn from(_: WindowStateInput) -> Self {
-        WindowStateMessage::SaveToDisk
-    }
-}
-
 /// Internal messages that the window state plugin handles
 /// Note: This is for internal use. Applications should use `WindowStateInput` instead.
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PluginInput)]
 pub enum WindowStateMessage {
     /// Window event
+    #[internal]
     WindowEvent(WindowEvent),
     /// Trigger a save to disk
+    #[internal]
     SaveToDisk,
-    /// Save operation completed
-    SaveCompleted(Result<WindowState, String>),
+    /// The store plugin wrote the state, or failed to
+    #[internal]
+    Saved(Result<WindowState, String>),
+    /// The config was reloaded
+    #[internal]
+    ConfigChanged(WindowStateConfig),
 }
 
 /// Output messages emitted by the window state plugin
 #[derive(Clone, Debug)]
 #[non_exhaustive]
 pub enum WindowStateOutput {
-    /// Window state was saved to disk
+    /// Window state was written to disk by the store plugin
     StateSaved(WindowState),
     /// Window state was updated (but not yet saved)
     StateUpdated(WindowState),
@@ -187,6 +197,8 @@ pub struct WindowPluginState {
     app_name: AppName,
     /// The oldest (main) window ID that we track
     oldest_window_id: Option<Id>,
+    /// Current configuration
+    config: WindowStateConfig,
 }
 
 impl WindowPluginState {
@@ -206,12 +218,26 @@ impl WindowPluginState {
     }
 }
 
+/// Configuration for the window state plugin
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WindowStateConfig {
+    /// Auto-save interval in seconds, or 0 to only save on shutdown
+    pub auto_save_interval: u64,
+}
+
+impl Default for WindowStateConfig {
+    fn default() -> Self {
+        Self {
+            auto_save_interval: 2,
+        }
+    }
+}
+
 /// Window state plugin that manages window state persistence
 #[derive(Debug, Clone)]
 pub struct WindowStatePlugin {
     app_name: AppName,
-    /// Auto-save interval in seconds
-    auto_save_interval: u64,
+    config: WindowStateConfig,
 }
 
 const WINDOW_STATE_GROUP: &str = "window_state";
@@ -219,20 +245,39 @@ const WINDOW_STATE_KEY: &str = "main";
 
 impl WindowStatePlugin {
     /// Create a new window state plugin with default settings (tracks main window)
+    ///
+    /// The state is saved and loaded through the store plugin.
     pub fn new(app_name: AppName) -> Self {
         Self {
             app_name,
-            auto_save_interval: 2,
+            config: WindowStateConfig::default(),
         }
     }
 
     /// Set the auto-save interval in seconds
+    ///
+    /// An interval of 0 turns auto-save off, so the state is only saved on shutdown.
     pub fn with_auto_save_interval(mut self, seconds: u64) -> Self {
-        self.auto_save_interval = seconds;
+        self.config.auto_save_interval = seconds;
         self
     }
 
-    /// Load window state from disk
+    /// Get the config to start with, from the config file if the plugin is
+    /// registered for it
+    #[cfg(feature = "config")]
+    fn initial_config(&self, ctx: &PluginContext<'_>) -> WindowStateConfig {
+        ctx.config::<Self>().unwrap_or_else(|| self.config.clone())
+    }
+
+    #[cfg(not(feature = "config"))]
+    fn initial_config(&self, _ctx: &PluginContext<'_>) -> WindowStateConfig {
+        self.config.clone()
+    }
+
+    /// Load window state from disk, before the store plugin runs
+    ///
+    /// This reads the store's files directly, so pass the app name the store plugin is
+    /// created with.
     pub async fn load(app_name: &AppName) -> Option<WindowState> {
         read_value(app_name, WINDOW_STATE_GROUP, WINDOW_STATE_KEY)
             .await
@@ -240,15 +285,25 @@ impl WindowStatePlugin {
     }
 }
 
-fn load_sync(app_name: &AppName) -> Option<WindowState> {
-    tokio::runtime::Runtime::new()
-        .ok()?
-        .block_on(WindowStatePlugin::load(app_name))
-}
-
-async fn save_async(app_name: AppName, state: WindowState) -> Result<WindowState, String> {
-    write_value(&app_name, WINDOW_STATE_GROUP, WINDOW_STATE_KEY, &state).await?;
-    Ok(state)
+/// Hand the window state to the store plugin if it changed since the last save.
+/// The returned task reports back with [`WindowStateMessage::Saved`] once the store
+/// wrote it.
+fn save(state: &mut WindowPluginState, ctx: &mut PluginContext<'_>) -> Task<WindowStateMessage> {
+    if !state.dirty {
+        return Task::none();
+    }
+    state.dirty = false;
+
+    let saved = state.state.clone();
+    let input = StoreInput::set(WINDOW_STATE_GROUP, WINDOW_STATE_KEY, &saved);
+    ctx.call::<StorePlugin>(input).map(move |result| {
+        WindowStateMessage::Saved(match result {
+            Ok(StoreOutput::Set { .. }) => Ok(saved.clone()),
+            Ok(StoreOutput::Error { message }) => Err(message),
+            Ok(output) => Err(format!("Unexpected store output: {:?}", output)),
+            Err(error) => Err(error.to_string()),
+        })
+    })
 }
 
 /// Subscription for listening to all window events
@@ -272,20 +327,69 @@ impl Plugin for WindowStatePlugin {
         "window_state"
     }
 
-    fn init(&self) -> (Self::State, Task<Self::Message>) {
+    fn dependencies(&self) -> Vec<Dependency> {
+        vec![Dependency::on::<StorePlugin>()]
+    }
+
+    fn capabilities(&self) -> Vec<Capability> {
+        vec![Capability::FsRead, Capability::FsWrite]
+    }
+
+    fn init(&self, ctx: &mut PluginContext<'_>) -> (Self::State, Task<Self::Message>) {
         let state = WindowPluginState {
-            state: load_sync(&self.app_name).unwrap_or_default(),
+            state: WindowState::default(),
             dirty: false,
             app_name: self.app_name.clone(),
             oldest_window_id: None,
+            config: self.initial_config(ctx),
         };
         (state, Task::none())
     }
 
+    fn init_async(&self, ctx: &mut PluginContext<'_>) -> Option<BoxFuture<'static, Self::State>> {
+        let app_name = self.app_name.clone();
+        let config = self.initial_config(ctx);
+
+        // Read through the store plugin, which may hold a state not yet on disk
+        let (sender, receiver) = oneshot::channel();
+        let mut sender = Some(sender);
+        let read = ctx
+            .call::<StorePlugin>(StoreInput::get(WINDOW_STATE_GROUP, WINDOW_STATE_KEY))
+            .map(move |result| {
+                if let Some(sender) = sender.take() {
+                    let _ = sender.send(result);
+                }
+            });
+        ctx.emit(read.discard());
+
+        Some(Box::pin(async move {
+            let loaded = match receiver.await {
+                Ok(Ok(output @ StoreOutput::Get { .. })) => output.as_value::<WindowState>(),
+                Ok(Ok(StoreOutput::Error { message })) => {
+                    tracing::warn!(error = message, "failed to load window state");
+                    None
+                }
+                Ok(Err(error)) => {
+                    tracing::warn!(%error, "failed to load window state");
+                    None
+                }
+                Ok(Ok(_)) | Err(_) => None,
+            };
+            WindowPluginState {
+                state: loaded.unwrap_or_default(),
+                dirty: false,
+                app_name,
+                oldest_window_id: None,
+                config,
+            }
+        }))
+    }
+
     fn update(
         &self,
         state: &mut Self::State,
         message: Self::Message,
+        ctx: &mut PluginContext<'_>,
     ) -> (Task<Self::Message>, Option<Self::Output>) {
         match message {
             WindowStateMessage::WindowEvent(WindowEvent::Opened(id)) => {
@@ -326,40 +430,52 @@ impl Plugin for WindowStatePlugin {
                     (Task::none(), None)
                 }
             }
-            WindowStateMessage::SaveToDisk => {
-                if state.dirty {
-                    let app_name = state.app_name.clone();
-                    let window_state = state.state.clone();
-                    let task = Task::perform(
-                        save_async(app_name, window_state),
-                        WindowStateMessage::SaveCompleted,
-                    );
-                    (task, None)
-                } else {
-                    (Task::none(), None)
-                }
+            WindowStateMessage::SaveToDisk => (save(state, ctx), None),
+            WindowStateMessage::Saved(Ok(saved)) => {
+                (Task::none(), Some(WindowStateOutput::StateSaved(saved)))
+            }
+            WindowStateMessage::Saved(Err(error)) => {
+                tracing::warn!(error, "failed to save window state");
+                // Try again on the next auto-save
+                state.dirty = true;
+                (Task::none(), Some(WindowStateOutput::SaveError(error)))
+            }
+            WindowStateMessage::ConfigChanged(config) => {
+                state.config = config;
+                (Task::none(), None)
             }
-            WindowStateMessage::SaveCompleted(result) => match result {
-                Ok(saved_state) => {
-                    state.dirty = false;
-                    (
-                        Task::none(),
-                        Some(WindowStateOutput::StateSaved(saved_state)),
-                    )
-                }
-                Err(e) => {
-                    eprintln!("Failed to save window state: {}", e);
-                    (Task::none(), Some(WindowStateOutput::SaveError(e)))
-                }
-            },
         }
     }
 
-    fn subscription(&self, _state: &Self::State) -> Subscription<Self::Message> {
-        Subscription::batch([
-            window_events(),
-            every(Duration::from_secs(self.auto_save_interval))
-                .map(|_| WindowStateMessage::SaveToDisk),
-        ])
+    fn subscription(&self, state: &Self::State) -> Subscription<Self::Message> {
+        let auto_save = match state.config.auto_save_interval {
+            0 => Subscription::none(),
+            seconds => every(Duration::from_secs(seconds)).map(|_| WindowStateMessage::SaveToDisk),
+        };
+
+        Subscription::batch([window_events(), auto_save])
+    }
+
+    fn shutdown(
+        &self,
+        state: &mut Self::State,
+        ctx: &mut PluginContext<'_>,
+    ) -> Task<Self::Message> {
+        // Flush changes made since the last auto-save. The store writes it before
+        // shutting down, and a failure is logged once it reports back.
+        save(state, ctx)
+    }
+}
+
+#[cfg(feature = "config")]
+impl Configurable for WindowStatePlugin {
+    type Config = WindowStateConfig;
+
+    fn default_config(&self) -> Self::Config {
+        self.config.clone()
+    }
+
+    fn config_changed(config: Self::Config) -> Self::Message {
+        WindowStateMessage::ConfigChanged(config)
     }
 }
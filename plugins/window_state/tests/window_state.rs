@@ -0,0 +1,118 @@
+use iced::window::Id;
+use iced::{Point, Size, Task};
+use iced_plugins::{Capability, PluginManagerBuilder, PluginTester};
+use iced_store_plugin::{StorePlugin, storage_dir};
+use iced_window_state_plugin::{
+    AppName, WindowEvent, WindowState, WindowStateMessage, WindowStateOutput, WindowStatePlugin,
+};
+
+/// An app name of its own for each test, so tests don't share files
+fn app_name(test: &str) -> AppName {
+    AppName::new(
+        "dev",
+        "iced-plugins-tests",
+        format!("window-state-{}-{}", test, std::process::id()),
+    )
+}
+
+fn window_state(app_name: &AppName) -> PluginTester<WindowStatePlugin> {
+    let store = PluginManagerBuilder::new()
+        .with_plugin(StorePlugin::new(app_name.clone()))
+        .with_capabilities::<StorePlugin>([Capability::FsRead, Capability::FsWrite])
+        .with_capabilities::<WindowStatePlugin>([Capability::FsRead, Capability::FsWrite]);
+    PluginTester::with_builder(store, WindowStatePlugin::new(app_name.clone()))
+}
+
+/// Open the main window and resize it
+fn resize(tester: &mut PluginTester<WindowStatePlugin>, size: Size) {
+    let id = Id::unique();
+    tester.send(WindowStateMessage::WindowEvent(WindowEvent::Opened(id)));
+    tester.send(WindowStateMessage::WindowEvent(WindowEvent::Resized(
+        id, size,
+    )));
+}
+
+#[test]
+fn saves_through_the_store() {
+    let app_name = app_name("save");
+    let mut tester = window_state(&app_name);
+
+    resize(&mut tester, Size::new(1024.0, 768.0));
+    tester.send(WindowStateMessage::SaveToDisk);
+
+    let expected = WindowState {
+        size: Size::new(1024.0, 768.0),
+        position: Point::new(100.0, 100.0),
+    };
+    assert!(matches!(
+        &tester.take_outputs()[..],
+        [
+            WindowStateOutput::StateUpdated(_),
+            WindowStateOutput::StateSaved(saved),
+        ] if *saved == expected
+    ));
+
+    let loaded = tester.perform(Task::future({
+        let app_name = app_name.clone();
+        async move { WindowStatePlugin::load(&app_name).await }
+    }));
+    assert_eq!(loaded, [Some(expected)]);
+
+    let _ = std::fs::remove_dir_all(storage_dir(&app_name));
+}
+
+#[test]
+fn saves_on_shutdown() {
+    let app_name = app_name("shutdown");
+    let mut tester = window_state(&app_name);
+
+    resize(&mut tester, Size::new(640.0, 480.0));
+    let shutdown = tester.manager_mut().shutdown();
+    tester.run(shutdown);
+    assert!(matches!(
+        tester.take_outputs().last(),
+        Some(WindowStateOutput::StateSaved(saved)) if saved.size == Size::new(640.0, 480.0)
+    ));
+
+    let restarted = window_state(&app_name);
+    assert_eq!(
+        restarted.state().current_state().size,
+        Size::new(640.0, 480.0)
+    );
+
+    let _ = std::fs::remove_dir_all(storage_dir(&app_name));
+}
+
+#[test]
+fn skips_saves_without_changes() {
+    let app_name = app_name("unchanged");
+    let mut tester = window_state(&app_name);
+
+    tester.send(WindowStateMessage::SaveToDisk);
+    assert!(tester.take_outputs().is_empty());
+
+    let _ = std::fs::remove_dir_all(storage_dir(&app_name));
+}
+
+#[test]
+fn loads_through_the_store() {
+    let app_name = app_name("load");
+    let mut tester = window_state(&app_name);
+    resize(&mut tester, Size::new(1280.0, 720.0));
+    tester.send(WindowStateMessage::SaveToDisk);
+    drop(tester);
+
+    // The store decides where the state lives, not the app name of the plugin
+    let store = PluginManagerBuilder::new()
+        .with_plugin(StorePlugin::new(app_name.clone()))
+        .with_capabilities::<StorePlugin>([Capability::FsRead, Capability::FsWrite])
+        .with_capabilities::<WindowStatePlugin>([Capability::FsRead, Capability::FsWrite]);
+    let other = AppName::new("dev", "iced-plugins-tests", "window-state-other");
+    let restarted = PluginTester::with_builder(store, WindowStatePlugin::new(other));
+    assert_eq!(
+        restarted.state().current_state().size,
+        Size::new(1280.0, 720.0)
+    );
+
+    let _ = std::fs::remove_dir_all(storage_dir(&app_name));
+}
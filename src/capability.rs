@@ -0,0 +1,77 @@
+use crate::BuildError;
+use std::collections::BTreeSet;
+use std::fmt;
+
+/// Access to the system a plugin needs, declared through [`Plugin::capabilities`]
+///
+/// The application grants capabilities per plugin type with
+/// [`PluginManagerBuilder::with_capabilities`], or per instance with
+/// [`PluginManagerBuilder::with_capabilities_keyed`]. Building a manager with a plugin
+/// that declares a capability it wasn't granted fails with
+/// [`BuildError::CapabilityNotGranted`].
+///
+/// Capabilities gate what plugins do through the manager: the operations bundled
+/// plugins check with [`PluginContext::require`], and dispatching to plugins that
+/// declare capabilities. They are not a sandbox. Native plugin code can still call
+/// the system directly, or dispatch through a [`PluginHandle`], so only install
+/// native plugins you trust.
+///
+/// [`Plugin::capabilities`]: crate::Plugin::capabilities
+/// [`PluginContext::require`]: crate::PluginContext::require
+/// [`PluginHandle`]: crate::PluginHandle
+/// [`PluginManagerBuilder::with_capabilities`]: crate::PluginManagerBuilder::with_capabilities
+/// [`PluginManagerBuilder::with_capabilities_keyed`]: crate::PluginManagerBuilder::with_capabilities_keyed
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Capability {
+    /// Read files, including state the plugin loads through the store plugin
+    FsRead,
+    /// Create, write or remove files
+    FsWrite,
+    /// Make network requests
+    Network,
+    /// Run other programs, such as an installer
+    SpawnProcess,
+    /// Show an icon and menu in the system tray
+    Tray,
+}
+
+impl Capability {
+    /// Get the name of the capability, such as `fs-read`
+    pub fn name(self) -> &'static str {
+        match self {
+            Capability::FsRead => "fs-read",
+            Capability::FsWrite => "fs-write",
+            Capability::Network => "network",
+            Capability::SpawnProcess => "spawn-process",
+            Capability::Tray => "tray",
+        }
+    }
+}
+
+impl fmt::Display for Capability {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Check that a plugin was granted every capability it declares
+pub(crate) fn check(
+    plugin: &'static str,
+    declared: &[Capability],
+    granted: &BTreeSet<Capability>,
+) -> Result<(), BuildError> {
+    let missing: Vec<Capability> = declared
+        .iter()
+        .filter(|capability| !granted.contains(capability))
+        .copied()
+        .collect();
+
+    if missing.is_empty() {
+        Ok(())
+    } else {
+        Err(BuildError::CapabilityNotGranted {
+            plugin,
+            capabilities: missing,
+        })
+    }
+}
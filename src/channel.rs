@@ -0,0 +1,284 @@
+use crate::PluginOutput;
+use iced::futures::Stream;
+use std::collections::VecDeque;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
+
+/// What a bounded output channel does with a new output while it is full
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum OverflowPolicy {
+    /// Drop the oldest queued output to make room for the new one
+    DropOldest,
+    /// Drop the new output and keep the queued ones
+    DropNewest,
+    /// Replace the most recently queued output with the new one, so the listener
+    /// always ends up with the latest output, e.g. for progress updates
+    CoalesceLatest,
+}
+
+/// Queue between a plugin and one of its output listeners
+///
+/// Listeners that fall behind, e.g. on frequent download progress outputs, make an
+/// unbounded channel grow without limit. A bounded channel holds at most `capacity`
+/// outputs and applies its [`OverflowPolicy`] when full. Outputs dropped this way are
+/// counted in [`PluginManager::output_metrics`](crate::PluginManager::output_metrics).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum OutputChannel {
+    /// Queue every output until the listener takes it
+    #[default]
+    Unbounded,
+    /// Queue at most `capacity` outputs, at least one
+    Bounded {
+        capacity: usize,
+        policy: OverflowPolicy,
+    },
+}
+
+impl OutputChannel {
+    /// Create a bounded channel
+    pub fn bounded(capacity: usize, policy: OverflowPolicy) -> Self {
+        OutputChannel::Bounded { capacity, policy }
+    }
+}
+
+/// Output delivery statistics of an installed plugin
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct OutputMetrics {
+    /// Name of the plugin
+    pub plugin: &'static str,
+    /// Instance key of the plugin, if installed with one
+    pub key: Option<String>,
+    /// Number of active output listeners
+    pub listeners: usize,
+    /// Outputs dropped by the overflow policies of bounded listeners so far
+    pub dropped: u64,
+}
+
+/// Whether a sent output reached the queue of its listener
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Delivery {
+    Queued,
+    /// The queue was full and its policy dropped an output
+    Dropped,
+}
+
+#[derive(Debug)]
+struct Queue {
+    outputs: VecDeque<PluginOutput>,
+    channel: OutputChannel,
+    waker: Option<Waker>,
+    sender_alive: bool,
+    receiver_alive: bool,
+}
+
+/// Create a channel for the outputs sent to one listener
+pub(crate) fn channel(channel: OutputChannel) -> (OutputSender, OutputReceiver) {
+    let queue = Arc::new(Mutex::new(Queue {
+        outputs: VecDeque::new(),
+        channel,
+        waker: None,
+        sender_alive: true,
+        receiver_alive: true,
+    }));
+
+    (
+        OutputSender {
+            queue: Arc::clone(&queue),
+        },
+        OutputReceiver { queue },
+    )
+}
+
+/// Sending end of an output channel, held by the registry
+#[derive(Debug)]
+pub(crate) struct OutputSender {
+    queue: Arc<Mutex<Queue>>,
+}
+
+impl OutputSender {
+    /// Queue an output, applying the overflow policy if the channel is full.
+    /// Returns None once the receiver is gone.
+    pub(crate) fn send(&self, output: PluginOutput) -> Option<Delivery> {
+        let mut queue = self.queue.lock().ok()?;
+        if !queue.receiver_alive {
+            return None;
+        }
+
+        let delivery = match queue.channel {
+            OutputChannel::Bounded { capacity, policy }
+                if queue.outputs.len() >= capacity.max(1) =>
+            {
+                match policy {
+                    OverflowPolicy::DropOldest => {
+                        queue.outputs.pop_front();
+                        queue.outputs.push_back(output);
+                    }
+                    OverflowPolicy::DropNewest => {}
+                    OverflowPolicy::CoalesceLatest => {
+                        queue.outputs.pop_back();
+                        queue.outputs.push_back(output);
+                    }
+                }
+                Delivery::Dropped
+            }
+            _ => {
+                queue.outputs.push_back(output);
+                Delivery::Queued
+            }
+        };
+
+        if let Some(waker) = queue.waker.take() {
+            waker.wake();
+        }
+        Some(delivery)
+    }
+
+    pub(crate) fn is_closed(&self) -> bool {
+        self.queue
+            .lock()
+            .map(|queue| !queue.receiver_alive)
+            .unwrap_or(true)
+    }
+}
+
+impl Drop for OutputSender {
+    fn drop(&mut self) {
+        if let Ok(mut queue) = self.queue.lock() {
+            queue.sender_alive = false;
+            if let Some(waker) = queue.waker.take() {
+                waker.wake();
+            }
+        }
+    }
+}
+
+/// Receiving end of an output channel, ending once the registry drops the sender
+#[derive(Debug)]
+pub(crate) struct OutputReceiver {
+    queue: Arc<Mutex<Queue>>,
+}
+
+impl Stream for OutputReceiver {
+    type Item = PluginOutput;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let Ok(mut queue) = self.queue.lock() else {
+            return Poll::Ready(None);
+        };
+
+        if let Some(output) = queue.outputs.pop_front() {
+            Poll::Ready(Some(output))
+        } else if !queue.sender_alive {
+            Poll::Ready(None)
+        } else {
+            queue.waker = Some(cx.waker().clone());
+            Poll::Pending
+        }
+    }
+}
+
+impl Drop for OutputReceiver {
+    fn drop(&mut self) {
+        if let Ok(mut queue) = self.queue.lock() {
+            queue.receiver_alive = false;
+            queue.outputs.clear();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use iced::futures::{FutureExt, StreamExt};
+
+    fn send(sender: &OutputSender, values: impl IntoIterator<Item = u32>) -> Vec<Delivery> {
+        values
+            .into_iter()
+            .filter_map(|value| sender.send(PluginOutput::new(0, value)))
+            .collect()
+    }
+
+    /// Take the outputs that are ready
+    fn received(receiver: &mut OutputReceiver) -> Vec<u32> {
+        std::iter::from_fn(|| receiver.next().now_or_never().flatten())
+            .filter_map(|output| output.downcast::<u32>().copied())
+            .collect()
+    }
+
+    #[test]
+    fn unbounded_channels_keep_every_output() {
+        let (sender, mut receiver) = channel(OutputChannel::Unbounded);
+        let deliveries = send(&sender, 1..=100);
+
+        assert!(deliveries.iter().all(|d| *d == Delivery::Queued));
+        assert_eq!(received(&mut receiver), (1..=100).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn drop_oldest_keeps_the_latest_outputs() {
+        let (sender, mut receiver) = channel(OutputChannel::bounded(2, OverflowPolicy::DropOldest));
+        let deliveries = send(&sender, [1, 2, 3, 4]);
+
+        assert_eq!(
+            deliveries,
+            [
+                Delivery::Queued,
+                Delivery::Queued,
+                Delivery::Dropped,
+                Delivery::Dropped
+            ]
+        );
+        assert_eq!(received(&mut receiver), [3, 4]);
+    }
+
+    #[test]
+    fn drop_newest_keeps_the_first_outputs() {
+        let (sender, mut receiver) = channel(OutputChannel::bounded(2, OverflowPolicy::DropNewest));
+        send(&sender, [1, 2, 3, 4]);
+
+        assert_eq!(received(&mut receiver), [1, 2]);
+    }
+
+    #[test]
+    fn coalesce_latest_replaces_the_last_output() {
+        let (sender, mut receiver) =
+            channel(OutputChannel::bounded(2, OverflowPolicy::CoalesceLatest));
+        send(&sender, [1, 2, 3, 4]);
+
+        assert_eq!(received(&mut receiver), [1, 4]);
+    }
+
+    #[test]
+    fn bounded_channels_hold_at_least_one_output() {
+        let (sender, mut receiver) =
+            channel(OutputChannel::bounded(0, OverflowPolicy::CoalesceLatest));
+        send(&sender, [1, 2, 3]);
+
+        assert_eq!(received(&mut receiver), [3]);
+    }
+
+    #[test]
+    fn taking_outputs_makes_room() {
+        let (sender, mut receiver) = channel(OutputChannel::bounded(1, OverflowPolicy::DropNewest));
+        send(&sender, [1]);
+        assert_eq!(received(&mut receiver), [1]);
+
+        assert_eq!(send(&sender, [2]), [Delivery::Queued]);
+        assert_eq!(received(&mut receiver), [2]);
+    }
+
+    #[test]
+    fn closes_with_the_other_end() {
+        let (sender, receiver) = channel(OutputChannel::Unbounded);
+        drop(receiver);
+        assert!(sender.is_closed());
+        assert!(send(&sender, [1]).is_empty());
+
+        let (sender, mut receiver) = channel(OutputChannel::Unbounded);
+        send(&sender, [1]);
+        drop(sender);
+        assert_eq!(received(&mut receiver), [1]);
+        assert!(matches!(receiver.next().now_or_never(), Some(None)));
+    }
+}
@@ -0,0 +1,345 @@
+use crate::{ConfigError, Plugin, PluginMessage};
+use iced::Subscription;
+use iced::futures::stream::{self, BoxStream, StreamExt};
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use std::any::Any;
+use std::fmt::Debug;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+use toml::{Table, Value};
+
+/// A plugin whose behavior can be configured without a rebuild
+///
+/// Its config is layered: the compiled defaults from [`Configurable::default_config`],
+/// then the table named after [`Plugin::name`] in the config file, then environment
+/// variables. Read it in `init` with [`PluginContext::config`](crate::PluginContext::config),
+/// and register the plugin type with
+/// [`PluginManagerBuilder::with_config`](crate::PluginManagerBuilder::with_config).
+///
+/// # Example
+/// ```ignore
+/// impl Configurable for WindowStatePlugin {
+///     type Config = WindowStateConfig;
+///
+///     fn default_config(&self) -> Self::Config {
+///         self.config.clone()
+///     }
+///
+///     fn config_changed(config: Self::Config) -> Self::Message {
+///         WindowStateMessage::ConfigChanged(config)
+///     }
+/// }
+/// ```
+pub trait Configurable: Plugin {
+    /// The configuration of the plugin
+    type Config: Serialize + DeserializeOwned + Clone + Debug + Send + Sync + 'static;
+
+    /// The compiled defaults, e.g. as set through builder methods
+    fn default_config(&self) -> Self::Config;
+
+    /// Message delivering a changed config to the plugin after the config file was reloaded
+    fn config_changed(config: Self::Config) -> Self::Message;
+}
+
+/// Where plugin configs are loaded from
+///
+/// A TOML file with one table per plugin, named after [`Plugin::name`]. A missing file
+/// leaves the compiled defaults in place.
+///
+/// ```toml
+/// [window_state]
+/// auto_save_interval = 5
+///
+/// [auto_updater]
+/// check_on_start = true
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ConfigSource {
+    path: PathBuf,
+    env_prefix: Option<String>,
+    watch_interval: Option<Duration>,
+}
+
+impl ConfigSource {
+    /// Load configs from a TOML file
+    pub fn file(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            env_prefix: None,
+            watch_interval: None,
+        }
+    }
+
+    /// Load configs from `plugins.toml` in the config dir of an app, e.g.
+    /// `~/.config/myapp/plugins.toml` on Linux, with the application name as
+    /// environment variable prefix
+    ///
+    /// Returns None if no home directory could be found.
+    pub fn for_app(qualifier: &str, organization: &str, application: &str) -> Option<Self> {
+        let dirs = directories::ProjectDirs::from(qualifier, organization, application)?;
+        Some(Self::file(dirs.config_dir().join("plugins.toml")).with_env_prefix(application))
+    }
+
+    /// Override config values with environment variables named
+    /// `{PREFIX}_{PLUGIN}_{FIELD}`, e.g. `MYAPP_WINDOW_STATE_AUTO_SAVE_INTERVAL=5`
+    ///
+    /// Names are matched in upper case, with characters other than letters and digits
+    /// replaced by `_`. Nested fields are separated by `__`. Values are parsed as TOML,
+    /// falling back to a string.
+    pub fn with_env_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.env_prefix = Some(prefix.into());
+        self
+    }
+
+    /// Reload the configs when the file changes, checking it every `interval`
+    ///
+    /// Plugins whose config changed receive the message from
+    /// [`Configurable::config_changed`]. The check runs as part of
+    /// [`PluginManager::subscriptions`](crate::PluginManager::subscriptions). A zero
+    /// `interval` turns watching off.
+    pub fn watch(mut self, interval: Duration) -> Self {
+        self.watch_interval = (!interval.is_zero()).then_some(interval);
+        self
+    }
+
+    /// Get the path of the config file
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub(crate) fn is_watched(&self) -> bool {
+        self.watch_interval.is_some()
+    }
+}
+
+/// Layers and converts the configs of one plugin type
+#[derive(Clone, Copy)]
+pub(crate) struct ConfigCodec {
+    defaults: fn(&(dyn Any + Send + Sync)) -> Option<Result<Value, toml::ser::Error>>,
+    decode: fn(Value) -> Result<Arc<dyn Any + Send + Sync>, toml::de::Error>,
+    changed: fn(usize, &(dyn Any + Send + Sync)) -> Option<PluginMessage>,
+}
+
+impl ConfigCodec {
+    pub(crate) fn of<P>() -> Self
+    where
+        P: Configurable + 'static,
+    {
+        Self {
+            defaults: |plugin| {
+                plugin
+                    .downcast_ref::<Arc<P>>()
+                    .map(|plugin| Value::try_from(plugin.default_config()))
+            },
+            decode: |value| {
+                value
+                    .try_into::<P::Config>()
+                    .map(|config| Arc::new(config) as Arc<dyn Any + Send + Sync>)
+            },
+            changed: |plugin_index, config| {
+                config.downcast_ref::<P::Config>().map(|config| {
+                    PluginMessage::new(plugin_index, P::config_changed(config.clone()))
+                })
+            },
+        }
+    }
+
+    /// Layer the config of a plugin instance: defaults, then its table in the file, then
+    /// the environment
+    pub(crate) fn load(
+        &self,
+        plugin: &(dyn Any + Send + Sync),
+        name: &'static str,
+        file: &Table,
+        source: Option<&ConfigSource>,
+    ) -> Result<LoadedConfig, ConfigError> {
+        let invalid = |message: String| ConfigError::Invalid {
+            plugin: name,
+            message,
+        };
+
+        let mut table = match (self.defaults)(plugin) {
+            Some(Ok(Value::Table(table))) => table,
+            Some(Err(error)) => return Err(invalid(error.to_string())),
+            _ => Table::new(),
+        };
+        match file.get(name) {
+            Some(Value::Table(section)) => merge(&mut table, section.clone()),
+            Some(_) => return Err(invalid(format!("`{}` must be a table", name))),
+            None => {}
+        }
+        if let Some(prefix) = source.and_then(|source| source.env_prefix.as_deref()) {
+            merge(&mut table, env_overrides(prefix, name));
+        }
+
+        let value = Value::Table(table);
+        let config = (self.decode)(value.clone()).map_err(|error| invalid(error.to_string()))?;
+        Ok(LoadedConfig { value, config })
+    }
+
+    /// Create the message delivering a config to its plugin
+    pub(crate) fn changed(
+        &self,
+        plugin_index: usize,
+        config: &(dyn Any + Send + Sync),
+    ) -> Option<PluginMessage> {
+        (self.changed)(plugin_index, config)
+    }
+}
+
+/// The current config of a plugin instance
+pub(crate) struct LoadedConfig {
+    /// The layered config, to tell whether a reload changed it
+    pub value: Value,
+    pub config: Arc<dyn Any + Send + Sync>,
+}
+
+/// Read the config file, or an empty table if there is none
+pub(crate) fn read(source: Option<&ConfigSource>) -> Result<Table, ConfigError> {
+    let Some(source) = source else {
+        return Ok(Table::new());
+    };
+
+    let text = match std::fs::read_to_string(&source.path) {
+        Ok(text) => text,
+        Err(error) if error.kind() == ErrorKind::NotFound => return Ok(Table::new()),
+        Err(error) => {
+            return Err(ConfigError::Read {
+                path: source.path.clone(),
+                message: error.to_string(),
+            });
+        }
+    };
+    text.parse::<Table>().map_err(|error| ConfigError::Parse {
+        path: source.path.clone(),
+        message: error.to_string(),
+    })
+}
+
+/// Merge `overlay` into `base`, replacing values and merging nested tables
+fn merge(base: &mut Table, overlay: Table) {
+    for (key, value) in overlay {
+        match (base.get_mut(&key), value) {
+            (Some(Value::Table(base)), Value::Table(overlay)) => merge(base, overlay),
+            (_, value) => {
+                base.insert(key, value);
+            }
+        }
+    }
+}
+
+/// Collect the environment variables overriding the config of a plugin
+fn env_overrides(prefix: &str, plugin: &str) -> Table {
+    let prefix = format!("{}_{}_", env_name(prefix), env_name(plugin));
+    let mut table = Table::new();
+
+    for (name, raw) in std::env::vars_os() {
+        let (Some(name), Some(raw)) = (name.to_str(), raw.to_str()) else {
+            continue;
+        };
+        let Some(path) = name.strip_prefix(&prefix) else {
+            continue;
+        };
+        let keys: Vec<String> = path.split("__").map(str::to_lowercase).collect();
+        insert(&mut table, &keys, parse_env_value(raw));
+    }
+
+    table
+}
+
+fn env_name(name: &str) -> String {
+    name.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_uppercase()
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+/// Parse an environment variable as a TOML value, e.g. `5` or `true`, or keep it as
+/// a string
+fn parse_env_value(raw: &str) -> Value {
+    format!("value = {}", raw)
+        .parse::<Table>()
+        .ok()
+        .and_then(|mut table| table.remove("value"))
+        .unwrap_or_else(|| Value::String(raw.to_string()))
+}
+
+/// Insert a value at a path of keys, creating the tables along the way
+fn insert(table: &mut Table, keys: &[String], value: Value) {
+    match keys {
+        [] => {}
+        [key] => {
+            table.insert(key.clone(), value);
+        }
+        [key, rest @ ..] => {
+            let entry = table
+                .entry(key.clone())
+                .or_insert_with(|| Value::Table(Table::new()));
+            if !entry.is_table() {
+                *entry = Value::Table(Table::new());
+            }
+            if let Value::Table(inner) = entry {
+                insert(inner, rest, value);
+            }
+        }
+    }
+}
+
+/// Message routed to the manager itself when the config file changed
+#[derive(Debug)]
+pub(crate) struct ReloadConfig;
+
+/// Subscription checking the config file for changes
+pub(crate) fn watch(source: &ConfigSource) -> Subscription<PluginMessage> {
+    fn modified(path: &Path) -> Option<SystemTime> {
+        std::fs::metadata(path)
+            .and_then(|metadata| metadata.modified())
+            .ok()
+    }
+
+    fn create_stream(source: &ConfigSource) -> BoxStream<'static, PluginMessage> {
+        let path = source.path.clone();
+        let interval = source.watch_interval.unwrap_or_default();
+
+        stream::unfold(modified(&path), move |last| {
+            let path = path.clone();
+            async move {
+                loop {
+                    tokio::time::sleep(interval).await;
+                    let current = modified(&path);
+                    if current != last {
+                        return Some((PluginMessage::new(usize::MAX, ReloadConfig), current));
+                    }
+                }
+            }
+        })
+        .boxed()
+    }
+
+    Subscription::run_with(source.clone(), create_stream)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn watches_only_with_an_interval() {
+        let source = ConfigSource::file("plugins.toml");
+        assert!(!source.is_watched());
+
+        let source = source.watch(Duration::from_secs(2));
+        assert!(source.is_watched());
+
+        let source = source.watch(Duration::ZERO);
+        assert!(!source.is_watched());
+    }
+}
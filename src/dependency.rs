@@ -0,0 +1,349 @@
+use crate::{BuildError, Plugin};
+use std::any::TypeId;
+use std::collections::BTreeSet;
+
+/// A dependency on another plugin type, declared through [`Plugin::dependencies`]
+///
+/// # Example
+/// ```ignore
+/// fn dependencies(&self) -> Vec<Dependency> {
+///     vec![
+///         Dependency::on::<StorePlugin>(),
+///         Dependency::on_keyed::<TrayIconPlugin>("status"),
+///     ]
+/// }
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Dependency {
+    type_id: TypeId,
+    name: &'static str,
+    key: Option<String>,
+}
+
+impl Dependency {
+    /// Declare a dependency on the instance of plugin type `P` installed without a key
+    pub fn on<P: Plugin + 'static>() -> Self {
+        Self {
+            type_id: TypeId::of::<P>(),
+            name: std::any::type_name::<P>(),
+            key: None,
+        }
+    }
+
+    /// Declare a dependency on the instance of plugin type `P` installed with `key`
+    pub fn on_keyed<P: Plugin + 'static>(key: impl Into<String>) -> Self {
+        Self {
+            key: Some(key.into()),
+            ..Self::on::<P>()
+        }
+    }
+
+    /// Get the type id of the required plugin
+    pub fn type_id(&self) -> TypeId {
+        self.type_id
+    }
+
+    /// Get the type name of the required plugin
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// Get the instance key of the required plugin, if any
+    pub fn key(&self) -> Option<&str> {
+        self.key.as_deref()
+    }
+
+    /// Whether a plugin of the given type and key satisfies this dependency
+    pub(crate) fn matches(&self, plugin_type: TypeId, key: Option<&str>) -> bool {
+        self.type_id == plugin_type && self.key() == key
+    }
+}
+
+/// A plugin as seen by the dependency resolver
+pub(crate) struct Node<'a> {
+    pub name: &'static str,
+    pub plugin_type: TypeId,
+    pub key: Option<&'a str>,
+    pub dependencies: &'a [Dependency],
+}
+
+/// Compute the order in which plugins must be initialized
+///
+/// Every plugin comes after the installed plugins it depends on.
+/// Plugins without a dependency between them keep their installation order.
+pub(crate) fn init_order(nodes: &[Node<'_>]) -> Result<Vec<usize>, BuildError> {
+    for (index, node) in nodes.iter().enumerate() {
+        let duplicate = nodes[..index]
+            .iter()
+            .any(|other| other.plugin_type == node.plugin_type && other.key == node.key);
+        if duplicate {
+            return Err(BuildError::DuplicatePlugin {
+                plugin: node.name,
+                key: node.key.map(str::to_owned),
+            });
+        }
+    }
+
+    let mut pending = vec![0usize; nodes.len()];
+    let mut dependents = vec![Vec::new(); nodes.len()];
+
+    for (index, node) in nodes.iter().enumerate() {
+        for dependency in node.dependencies {
+            let providers: Vec<usize> = nodes
+                .iter()
+                .enumerate()
+                .filter(|(_, other)| dependency.matches(other.plugin_type, other.key))
+                .map(|(provider, _)| provider)
+                .collect();
+
+            if providers.is_empty() {
+                return Err(BuildError::MissingDependency {
+                    plugin: node.name,
+                    dependency: dependency.name,
+                    key: dependency.key.clone(),
+                });
+            }
+
+            for provider in providers {
+                dependents[provider].push(index);
+                pending[index] += 1;
+            }
+        }
+    }
+
+    let mut ready: BTreeSet<usize> = (0..nodes.len()).filter(|&i| pending[i] == 0).collect();
+    let mut order = Vec::with_capacity(nodes.len());
+
+    while let Some(index) = ready.pop_first() {
+        order.push(index);
+        for &dependent in &dependents[index] {
+            pending[dependent] -= 1;
+            if pending[dependent] == 0 {
+                ready.insert(dependent);
+            }
+        }
+    }
+
+    if order.len() < nodes.len() {
+        let plugins = (0..nodes.len())
+            .filter(|&i| pending[i] > 0)
+            .map(|i| nodes[i].name)
+            .collect();
+        return Err(BuildError::DependencyCycle { plugins });
+    }
+
+    Ok(order)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Alpha;
+    struct Beta;
+    struct Gamma;
+    struct Delta;
+
+    fn node<'a, T: 'static>(name: &'static str, dependencies: &'a [Dependency]) -> Node<'a> {
+        Node {
+            name,
+            plugin_type: TypeId::of::<T>(),
+            key: None,
+            dependencies,
+        }
+    }
+
+    fn keyed<'a, T: 'static>(
+        name: &'static str,
+        key: &'a str,
+        dependencies: &'a [Dependency],
+    ) -> Node<'a> {
+        Node {
+            key: Some(key),
+            ..node::<T>(name, dependencies)
+        }
+    }
+
+    fn on<T: 'static>(name: &'static str) -> Dependency {
+        Dependency {
+            type_id: TypeId::of::<T>(),
+            name,
+            key: None,
+        }
+    }
+
+    fn on_keyed<T: 'static>(name: &'static str, key: &str) -> Dependency {
+        Dependency {
+            key: Some(key.to_owned()),
+            ..on::<T>(name)
+        }
+    }
+
+    #[test]
+    fn keeps_installation_order_without_dependencies() {
+        let nodes = [
+            node::<Alpha>("alpha", &[]),
+            node::<Beta>("beta", &[]),
+            node::<Gamma>("gamma", &[]),
+        ];
+
+        assert_eq!(init_order(&nodes), Ok(vec![0, 1, 2]));
+    }
+
+    #[test]
+    fn initializes_dependencies_first() {
+        let on_beta = [on::<Beta>("beta")];
+        let nodes = [node::<Alpha>("alpha", &on_beta), node::<Beta>("beta", &[])];
+
+        assert_eq!(init_order(&nodes), Ok(vec![1, 0]));
+    }
+
+    #[test]
+    fn keeps_installation_order_between_unrelated_plugins() {
+        let on_gamma = [on::<Gamma>("gamma")];
+        let nodes = [
+            node::<Alpha>("alpha", &[]),
+            node::<Beta>("beta", &on_gamma),
+            node::<Gamma>("gamma", &[]),
+            node::<Delta>("delta", &[]),
+        ];
+
+        // Beta only moves behind gamma, delta stays last
+        assert_eq!(init_order(&nodes), Ok(vec![0, 2, 1, 3]));
+    }
+
+    #[test]
+    fn rejects_two_plugin_cycle() {
+        let on_alpha = [on::<Alpha>("alpha")];
+        let on_beta = [on::<Beta>("beta")];
+        let nodes = [
+            node::<Alpha>("alpha", &on_beta),
+            node::<Beta>("beta", &on_alpha),
+            node::<Gamma>("gamma", &[]),
+        ];
+
+        assert_eq!(
+            init_order(&nodes),
+            Err(BuildError::DependencyCycle {
+                plugins: vec!["alpha", "beta"],
+            })
+        );
+    }
+
+    #[test]
+    fn rejects_self_dependency() {
+        let on_alpha = [on::<Alpha>("alpha")];
+        let nodes = [node::<Alpha>("alpha", &on_alpha)];
+
+        assert_eq!(
+            init_order(&nodes),
+            Err(BuildError::DependencyCycle {
+                plugins: vec!["alpha"],
+            })
+        );
+    }
+
+    #[test]
+    fn reports_plugins_waiting_on_a_cycle() {
+        let on_alpha = [on::<Alpha>("alpha")];
+        let on_beta = [on::<Beta>("beta")];
+        let nodes = [
+            node::<Alpha>("alpha", &on_beta),
+            node::<Beta>("beta", &on_alpha),
+            node::<Gamma>("gamma", &on_alpha),
+        ];
+
+        assert_eq!(
+            init_order(&nodes),
+            Err(BuildError::DependencyCycle {
+                plugins: vec!["alpha", "beta", "gamma"],
+            })
+        );
+    }
+
+    #[test]
+    fn rejects_missing_dependency() {
+        let on_gamma = [on::<Gamma>("gamma")];
+        let nodes = [node::<Alpha>("alpha", &on_gamma), node::<Beta>("beta", &[])];
+
+        assert_eq!(
+            init_order(&nodes),
+            Err(BuildError::MissingDependency {
+                plugin: "alpha",
+                dependency: "gamma",
+                key: None,
+            })
+        );
+    }
+
+    #[test]
+    fn allows_one_instance_per_key() {
+        let nodes = [
+            node::<Alpha>("alpha", &[]),
+            keyed::<Alpha>("alpha", "work", &[]),
+            keyed::<Alpha>("alpha", "home", &[]),
+        ];
+
+        assert_eq!(init_order(&nodes), Ok(vec![0, 1, 2]));
+    }
+
+    #[test]
+    fn rejects_duplicate_unkeyed_install() {
+        let nodes = [node::<Alpha>("alpha", &[]), node::<Alpha>("alpha", &[])];
+
+        assert_eq!(
+            init_order(&nodes),
+            Err(BuildError::DuplicatePlugin {
+                plugin: "alpha",
+                key: None,
+            })
+        );
+    }
+
+    #[test]
+    fn rejects_duplicate_keyed_install() {
+        let nodes = [
+            keyed::<Alpha>("alpha", "work", &[]),
+            keyed::<Beta>("beta", "work", &[]),
+            keyed::<Alpha>("alpha", "work", &[]),
+        ];
+
+        assert_eq!(
+            init_order(&nodes),
+            Err(BuildError::DuplicatePlugin {
+                plugin: "alpha",
+                key: Some("work".to_owned()),
+            })
+        );
+    }
+
+    #[test]
+    fn keyed_dependency_waits_for_its_instance_only() {
+        let on_work = [on_keyed::<Alpha>("alpha", "work")];
+        let nodes = [
+            node::<Beta>("beta", &on_work),
+            keyed::<Alpha>("alpha", "work", &[]),
+            node::<Alpha>("alpha", &[]),
+        ];
+
+        assert_eq!(init_order(&nodes), Ok(vec![1, 0, 2]));
+    }
+
+    #[test]
+    fn rejects_missing_keyed_dependency() {
+        let on_home = [on_keyed::<Alpha>("alpha", "home")];
+        let nodes = [
+            node::<Beta>("beta", &on_home),
+            keyed::<Alpha>("alpha", "work", &[]),
+        ];
+
+        assert_eq!(
+            init_order(&nodes),
+            Err(BuildError::MissingDependency {
+                plugin: "beta",
+                dependency: "alpha",
+                key: Some("home".to_owned()),
+            })
+        );
+    }
+}
@@ -0,0 +1,471 @@
+use crate::{Capability, DynamicError, Plugin, PluginContext, intern_name};
+use iced::{Subscription, Task};
+use serde_json::Value;
+use std::ffi::c_void;
+use std::fmt::Debug;
+use std::mem::ManuallyDrop;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// Version of the [`PluginVTable`] layout and calling convention
+///
+/// Bumped, together with [`ENTRY_SYMBOL`], whenever either changes.
+pub const ABI_VERSION: u32 = 1;
+
+/// Symbol a plugin library exports, an `extern "C" fn() -> PluginVTable`
+pub const ENTRY_SYMBOL: &str = "iced_plugins_entry_v1";
+
+/// Bits of [`PluginVTable::capabilities`]
+const CAPABILITY_BITS: [(Capability, u32); 5] = [
+    (Capability::FsRead, 1 << 0),
+    (Capability::FsWrite, 1 << 1),
+    (Capability::Network, 1 << 2),
+    (Capability::SpawnProcess, 1 << 3),
+    (Capability::Tray, 1 << 4),
+];
+
+/// Bytes handed from a library to the application
+///
+/// The library allocates the buffer, and frees it in [`PluginVTable::free_buffer`].
+#[repr(C)]
+#[derive(Debug)]
+pub struct FfiBuffer {
+    pub ptr: *mut u8,
+    pub len: usize,
+    pub capacity: usize,
+}
+
+impl FfiBuffer {
+    fn empty() -> Self {
+        Self {
+            ptr: std::ptr::null_mut(),
+            len: 0,
+            capacity: 0,
+        }
+    }
+
+    fn from_vec(bytes: Vec<u8>) -> Self {
+        let mut bytes = ManuallyDrop::new(bytes);
+        Self {
+            ptr: bytes.as_mut_ptr(),
+            len: bytes.len(),
+            capacity: bytes.capacity(),
+        }
+    }
+}
+
+/// Functions of a plugin library, returned by its entry symbol
+///
+/// The entry symbol is versioned, and the vtable repeats the version, so the application
+/// refuses libraries built against another ABI instead of calling into them. Messages
+/// and outputs cross the boundary as JSON. Instances may be moved between threads, but
+/// are never used from two threads at once.
+#[repr(C)]
+#[derive(Debug)]
+pub struct PluginVTable {
+    /// The [`ABI_VERSION`] the library was built against
+    pub abi_version: u32,
+    /// UTF-8 name of the plugin, valid as long as the library is loaded
+    pub name: *const u8,
+    pub name_len: usize,
+    /// Capabilities the plugin declares, as a bit set
+    pub capabilities: u32,
+    /// Create a plugin instance, or return null if that failed
+    pub create: unsafe extern "C" fn() -> *mut c_void,
+    /// Handle a JSON message and write the JSON output, if any, to the buffer.
+    /// Returns 0 on success, or another value with a UTF-8 error message in the buffer.
+    pub update: unsafe extern "C" fn(*mut c_void, *const u8, usize, *mut FfiBuffer) -> i32,
+    /// Free a buffer written by `update`
+    pub free_buffer: unsafe extern "C" fn(FfiBuffer),
+    /// Destroy an instance created by `create`
+    pub destroy: unsafe extern "C" fn(*mut c_void),
+}
+
+/// A plugin compiled into a shared library, exported with
+/// [`export_plugin!`](crate::export_plugin)
+///
+/// Instances are created on one thread and may be used from another, so plugins must
+/// be `Send`.
+///
+/// # Example
+/// ```ignore
+/// #[derive(Default)]
+/// struct Greeter {
+///     greeted: u32,
+/// }
+///
+/// impl NativePlugin for Greeter {
+///     const NAME: &'static str = "greeter";
+///
+///     fn update(&mut self, input: Value) -> Result<Option<Value>, String> {
+///         self.greeted += 1;
+///         let name = input["name"].as_str().ok_or("missing name")?;
+///         Ok(Some(json!({ "greeting": format!("Hello, {name}!"), "count": self.greeted })))
+///     }
+/// }
+///
+/// iced_plugins::export_plugin!(Greeter);
+/// ```
+pub trait NativePlugin: Default + Send + 'static {
+    /// Name the plugin is installed under
+    const NAME: &'static str;
+
+    /// Capabilities the plugin needs, see [`Plugin::capabilities`]
+    fn capabilities() -> Vec<Capability> {
+        Vec::new()
+    }
+
+    /// Handle an input and return the output to emit, if any
+    fn update(&mut self, input: Value) -> Result<Option<Value>, String>;
+}
+
+/// Export a [`NativePlugin`] from a `cdylib` under [`ENTRY_SYMBOL`]
+#[macro_export]
+macro_rules! export_plugin {
+    ($plugin:ty) => {
+        #[unsafe(no_mangle)]
+        pub extern "C" fn iced_plugins_entry_v1() -> $crate::PluginVTable {
+            $crate::PluginVTable::new::<$plugin>()
+        }
+    };
+}
+
+impl PluginVTable {
+    /// Build the vtable of a native plugin, see [`export_plugin!`](crate::export_plugin)
+    pub fn new<P: NativePlugin>() -> Self {
+        let capabilities = P::capabilities();
+        Self {
+            abi_version: ABI_VERSION,
+            name: P::NAME.as_ptr(),
+            name_len: P::NAME.len(),
+            capabilities: CAPABILITY_BITS
+                .iter()
+                .filter(|(capability, _)| capabilities.contains(capability))
+                .fold(0, |bits, (_, bit)| bits | bit),
+            create: create::<P>,
+            update: update::<P>,
+            free_buffer,
+            destroy: destroy::<P>,
+        }
+    }
+}
+
+unsafe extern "C" fn create<P: NativePlugin>() -> *mut c_void {
+    match panic::catch_unwind(P::default) {
+        Ok(plugin) => Box::into_raw(Box::new(plugin)).cast(),
+        Err(_) => std::ptr::null_mut(),
+    }
+}
+
+unsafe extern "C" fn update<P: NativePlugin>(
+    instance: *mut c_void,
+    message: *const u8,
+    len: usize,
+    out: *mut FfiBuffer,
+) -> i32 {
+    let result = panic::catch_unwind(AssertUnwindSafe(|| {
+        // SAFETY: the host passes an instance from `create` and a message it owns
+        let plugin = unsafe { &mut *instance.cast::<P>() };
+        let message = unsafe { std::slice::from_raw_parts(message, len) };
+        let input = serde_json::from_slice(message).map_err(|e| e.to_string())?;
+        match plugin.update(input)? {
+            Some(output) => serde_json::to_vec(&output).map_err(|e| e.to_string()),
+            None => Ok(Vec::new()),
+        }
+    }));
+
+    let (status, bytes) = match result {
+        Ok(Ok(output)) => (0, output),
+        Ok(Err(error)) => (1, error.into_bytes()),
+        Err(_) => (1, b"plugin panicked".to_vec()),
+    };
+    // SAFETY: the host passes a buffer to write to
+    unsafe { out.write(FfiBuffer::from_vec(bytes)) };
+    status
+}
+
+unsafe extern "C" fn free_buffer(buffer: FfiBuffer) {
+    if !buffer.ptr.is_null() {
+        // SAFETY: the buffer was created from a Vec by `update`
+        drop(unsafe { Vec::from_raw_parts(buffer.ptr, buffer.len, buffer.capacity) });
+    }
+}
+
+unsafe extern "C" fn destroy<P: NativePlugin>(instance: *mut c_void) {
+    if !instance.is_null() {
+        // SAFETY: the instance was created by `create` and is destroyed once
+        drop(unsafe { Box::from_raw(instance.cast::<P>()) });
+    }
+}
+
+/// The functions of a loaded library, kept alive with it
+struct Library {
+    create: unsafe extern "C" fn() -> *mut c_void,
+    update: unsafe extern "C" fn(*mut c_void, *const u8, usize, *mut FfiBuffer) -> i32,
+    free_buffer: unsafe extern "C" fn(FfiBuffer),
+    destroy: unsafe extern "C" fn(*mut c_void),
+    /// Unloaded once the plugin and all of its instances are gone
+    _library: libloading::Library,
+}
+
+/// A plugin loaded from a shared library, such as a `.so` file on Linux
+///
+/// Its inputs and outputs are JSON values. Several libraries are told apart by their
+/// plugin name, which is their instance key once installed with
+/// [`PluginManagerBuilder::with_dynamic_plugin`](crate::PluginManagerBuilder::with_dynamic_plugin).
+/// An error returned by the library faults the plugin, like a panic would, and so does
+/// failing to create an instance or emitting invalid JSON.
+///
+/// # Example
+/// ```ignore
+/// let mut builder = PluginManagerBuilder::new();
+/// for plugin in unsafe { DynamicPlugin::load_dir("plugins") }? {
+///     match plugin {
+///         Ok(plugin) => builder = builder.with_dynamic_plugin(plugin),
+///         Err(error) => eprintln!("skipping plugin: {}", error),
+///     }
+/// }
+/// let (plugins, init_task) = builder.build()?;
+///
+/// let greeter = plugins.get_handle_keyed::<DynamicPlugin>("greeter").unwrap();
+/// let task = greeter.dispatch(json!({ "name": "iced" }));
+/// ```
+#[derive(Clone)]
+pub struct DynamicPlugin {
+    name: &'static str,
+    capabilities: Vec<Capability>,
+    path: PathBuf,
+    library: Arc<Library>,
+}
+
+impl Debug for DynamicPlugin {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DynamicPlugin")
+            .field("name", &self.name)
+            .field("path", &self.path)
+            .finish()
+    }
+}
+
+impl DynamicPlugin {
+    /// Load a plugin library and check that it was built for this [`ABI_VERSION`]
+    ///
+    /// # Safety
+    ///
+    /// Loading a library runs its initialization code, and its functions are trusted to
+    /// follow the [`PluginVTable`] contract. Only load libraries you trust.
+    pub unsafe fn load(path: impl AsRef<Path>) -> Result<Self, DynamicError> {
+        let path = path.as_ref().to_path_buf();
+        let library =
+            unsafe { libloading::Library::new(&path) }.map_err(|e| DynamicError::Load {
+                path: path.clone(),
+                message: e.to_string(),
+            })?;
+
+        let vtable = {
+            let entry = unsafe {
+                library.get::<unsafe extern "C" fn() -> PluginVTable>(ENTRY_SYMBOL.as_bytes())
+            }
+            .map_err(|_| DynamicError::MissingEntry { path: path.clone() })?;
+            unsafe { entry() }
+        };
+
+        if vtable.abi_version != ABI_VERSION {
+            return Err(DynamicError::AbiMismatch {
+                path,
+                expected: ABI_VERSION,
+                found: vtable.abi_version,
+            });
+        }
+
+        let name = unsafe { std::slice::from_raw_parts(vtable.name, vtable.name_len) };
+        let name = std::str::from_utf8(name)
+            .map_err(|_| DynamicError::InvalidName { path: path.clone() })?;
+        let name = intern_name(name);
+
+        let capabilities = CAPABILITY_BITS
+            .iter()
+            .filter(|(_, bit)| vtable.capabilities & bit != 0)
+            .map(|(capability, _)| *capability)
+            .collect();
+
+        Ok(Self {
+            name,
+            capabilities,
+            path,
+            library: Arc::new(Library {
+                create: vtable.create,
+                update: vtable.update,
+                free_buffer: vtable.free_buffer,
+                destroy: vtable.destroy,
+                _library: library,
+            }),
+        })
+    }
+
+    /// Load every plugin library in a directory, in file name order
+    ///
+    /// Only files with the platform's library extension, `.so` on Linux, are loaded.
+    /// Returns the result of each file, so one library that fails to load doesn't keep
+    /// the others from loading, or an error if the directory can't be read.
+    ///
+    /// # Safety
+    ///
+    /// See [`DynamicPlugin::load`].
+    pub unsafe fn load_dir(
+        dir: impl AsRef<Path>,
+    ) -> Result<Vec<Result<Self, DynamicError>>, DynamicError> {
+        let dir = dir.as_ref();
+        let read_error = |e: std::io::Error| DynamicError::Load {
+            path: dir.to_path_buf(),
+            message: e.to_string(),
+        };
+
+        let mut paths = Vec::new();
+        for entry in std::fs::read_dir(dir).map_err(read_error)? {
+            let path = entry.map_err(read_error)?.path();
+            if path.extension() == Some(std::env::consts::DLL_EXTENSION.as_ref()) {
+                paths.push(path);
+            }
+        }
+        paths.sort();
+
+        Ok(paths
+            .into_iter()
+            .map(|path| unsafe { Self::load(path) })
+            .collect())
+    }
+
+    /// Get the path the library was loaded from
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+/// An instance of a [`DynamicPlugin`], destroyed through its library when dropped
+pub struct DynamicState {
+    name: &'static str,
+    instance: *mut c_void,
+    library: Arc<Library>,
+}
+
+// SAFETY: the vtable contract allows moving instances between threads, which
+// `NativePlugin: Send` upholds for exported plugins, and the manager only uses a state
+// from one thread at a time
+unsafe impl Send for DynamicState {}
+
+impl Debug for DynamicState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DynamicState")
+            .field("plugin", &self.name)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Drop for DynamicState {
+    fn drop(&mut self) {
+        if !self.instance.is_null() {
+            unsafe { (self.library.destroy)(self.instance) };
+        }
+    }
+}
+
+impl Plugin for DynamicPlugin {
+    type Input = Value;
+    type Message = Value;
+    type State = DynamicState;
+    type Output = Value;
+
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn capabilities(&self) -> Vec<Capability> {
+        self.capabilities.clone()
+    }
+
+    fn init(&self, ctx: &mut PluginContext<'_>) -> (Self::State, Task<Self::Message>) {
+        let state = DynamicState {
+            name: self.name,
+            instance: unsafe { (self.library.create)() },
+            library: Arc::clone(&self.library),
+        };
+        if state.instance.is_null() {
+            ctx.fault("failed to create an instance");
+        }
+        (state, Task::none())
+    }
+
+    fn update(
+        &self,
+        state: &mut Self::State,
+        message: Self::Message,
+        ctx: &mut PluginContext<'_>,
+    ) -> (Task<Self::Message>, Option<Self::Output>) {
+        // Plugins without an instance fault in `init`, and are never updated
+        debug_assert!(!state.instance.is_null());
+
+        let message = match serde_json::to_vec(&message) {
+            Ok(message) => message,
+            Err(error) => {
+                ctx.fault(format!(
+                    "received a message that can't be serialized: {}",
+                    error
+                ));
+                return (Task::none(), None);
+            }
+        };
+        let mut buffer = FfiBuffer::empty();
+        let status = unsafe {
+            (self.library.update)(state.instance, message.as_ptr(), message.len(), &mut buffer)
+        };
+        let bytes = if buffer.ptr.is_null() {
+            Vec::new()
+        } else {
+            unsafe { std::slice::from_raw_parts(buffer.ptr, buffer.len) }.to_vec()
+        };
+        unsafe { (self.library.free_buffer)(buffer) };
+
+        if status != 0 {
+            ctx.fault(String::from_utf8_lossy(&bytes));
+            return (Task::none(), None);
+        }
+        if bytes.is_empty() {
+            return (Task::none(), None);
+        }
+        match serde_json::from_slice(&bytes) {
+            Ok(output) => (Task::none(), Some(output)),
+            Err(error) => {
+                ctx.fault(format!("emitted invalid JSON: {}", error));
+                (Task::none(), None)
+            }
+        }
+    }
+
+    fn subscription(&self, _state: &Self::State) -> Subscription<Self::Message> {
+        Subscription::none()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn loads_each_library_of_a_directory_on_its_own() {
+        let dir = std::env::temp_dir().join(format!("iced-plugins-dynamic-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let library = dir.join(format!("broken.{}", std::env::consts::DLL_EXTENSION));
+        std::fs::write(&library, b"not a library").unwrap();
+        std::fs::write(dir.join("notes.txt"), b"not a plugin").unwrap();
+
+        let results = unsafe { DynamicPlugin::load_dir(&dir) }.unwrap();
+
+        assert!(matches!(
+            &results[..],
+            [Err(DynamicError::Load { path, .. })] if *path == library
+        ));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
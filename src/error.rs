@@ -0,0 +1,475 @@
+use crate::Capability;
+use std::fmt;
+
+/// Errors returned by [`PluginManagerBuilder::build`](crate::PluginManagerBuilder::build)
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum BuildError {
+    /// A plugin depends on a plugin type that was not installed
+    MissingDependency {
+        /// Name of the plugin declaring the dependency
+        plugin: &'static str,
+        /// Type name of the missing plugin
+        dependency: &'static str,
+        /// Instance key of the missing plugin, if the dependency names one
+        key: Option<String>,
+    },
+    /// A plugin type was installed more than once with the same key, or more than
+    /// once without a key
+    DuplicatePlugin {
+        /// Name of the plugin installed again
+        plugin: &'static str,
+        /// The instance key both installations share
+        key: Option<String>,
+    },
+    /// Plugins depend on each other in a cycle
+    DependencyCycle {
+        /// Names of the plugins that are part of, or wait on, the cycle
+        plugins: Vec<&'static str>,
+    },
+    /// A plugin declares capabilities the application didn't grant it
+    CapabilityNotGranted {
+        /// Name of the plugin
+        plugin: &'static str,
+        /// The declared capabilities that were not granted
+        capabilities: Vec<Capability>,
+    },
+    /// The plugin configs could not be loaded
+    #[cfg(feature = "config")]
+    Config(ConfigError),
+}
+
+impl fmt::Display for BuildError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BuildError::MissingDependency {
+                plugin,
+                dependency,
+                key: None,
+            } => write!(
+                f,
+                "plugin `{}` depends on `{}`, which is not installed",
+                plugin, dependency
+            ),
+            BuildError::MissingDependency {
+                plugin,
+                dependency,
+                key: Some(key),
+            } => write!(
+                f,
+                "plugin `{}` depends on `{}` with key `{}`, which is not installed",
+                plugin, dependency, key
+            ),
+            BuildError::DuplicatePlugin { plugin, key: None } => {
+                write!(f, "plugin `{}` is installed more than once", plugin)
+            }
+            BuildError::DuplicatePlugin {
+                plugin,
+                key: Some(key),
+            } => write!(
+                f,
+                "plugin `{}` is installed more than once with key `{}`",
+                plugin, key
+            ),
+            BuildError::DependencyCycle { plugins } => {
+                write!(f, "plugins have a dependency cycle: {}", plugins.join(", "))
+            }
+            BuildError::CapabilityNotGranted {
+                plugin,
+                capabilities,
+            } => {
+                let names: Vec<&str> = capabilities.iter().map(|c| c.name()).collect();
+                write!(
+                    f,
+                    "plugin `{}` needs capabilities that were not granted: {}",
+                    plugin,
+                    names.join(", ")
+                )
+            }
+            #[cfg(feature = "config")]
+            BuildError::Config(error) => error.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for BuildError {}
+
+/// Errors returned by [`PluginHandle::call`](crate::PluginHandle::call)
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum CallError {
+    /// The plugin did not emit an output in time
+    Timeout,
+    /// The plugin manager was dropped before the plugin emitted an output
+    Closed,
+    /// The plugin is disabled or was uninstalled
+    Unavailable,
+    /// The calling plugin was not granted a capability the plugin declares
+    MissingCapability(Capability),
+}
+
+impl fmt::Display for CallError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CallError::Timeout => write!(f, "plugin did not respond in time"),
+            CallError::Closed => write!(f, "plugin manager is gone"),
+            CallError::Unavailable => write!(f, "plugin is disabled or uninstalled"),
+            CallError::MissingCapability(capability) => {
+                write!(f, "caller lacks the `{}` capability", capability)
+            }
+        }
+    }
+}
+
+impl std::error::Error for CallError {}
+
+/// Errors returned when enabling, disabling or uninstalling a plugin at runtime
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum LifecycleError {
+    /// The plugin was already uninstalled
+    Uninstalled {
+        /// Name of the plugin
+        plugin: &'static str,
+    },
+    /// An enabled plugin depends on the plugin and no other instance can take its place
+    RequiredBy {
+        /// Name of the plugin
+        plugin: &'static str,
+        /// Name of the enabled plugin depending on it
+        dependent: &'static str,
+    },
+    /// A plugin the plugin depends on is not enabled
+    DependencyUnavailable {
+        /// Name of the plugin
+        plugin: &'static str,
+        /// Type name of the plugin it depends on
+        dependency: &'static str,
+    },
+    /// The plugin panicked in `init`, so it has no state to run with
+    NotInitialized {
+        /// Name of the plugin
+        plugin: &'static str,
+    },
+}
+
+impl fmt::Display for LifecycleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LifecycleError::Uninstalled { plugin } => {
+                write!(f, "plugin `{}` was uninstalled", plugin)
+            }
+            LifecycleError::RequiredBy { plugin, dependent } => write!(
+                f,
+                "plugin `{}` is required by `{}`, which is enabled",
+                plugin, dependent
+            ),
+            LifecycleError::DependencyUnavailable { plugin, dependency } => write!(
+                f,
+                "plugin `{}` depends on `{}`, which is not enabled",
+                plugin, dependency
+            ),
+            LifecycleError::NotInitialized { plugin } => {
+                write!(f, "plugin `{}` failed to initialize", plugin)
+            }
+        }
+    }
+}
+
+impl std::error::Error for LifecycleError {}
+
+/// Error returned by [`PluginContext::require`](crate::PluginContext::require) when a
+/// plugin uses a capability it wasn't granted
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CapabilityError {
+    /// Name of the plugin
+    pub plugin: &'static str,
+    /// The capability it is missing
+    pub capability: Capability,
+}
+
+impl fmt::Display for CapabilityError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "plugin `{}` does not have the `{}` capability",
+            self.plugin, self.capability
+        )
+    }
+}
+
+impl std::error::Error for CapabilityError {}
+
+/// Errors returned by [`PluginManager::replay`](crate::PluginManager::replay)
+#[cfg(feature = "replay")]
+#[derive(Debug)]
+pub enum ReplayError {
+    /// The recording could not be read
+    Io(std::io::Error),
+    /// A line of the recording is not a valid recorded message
+    Parse {
+        /// Line number, starting at 1
+        line: usize,
+        /// What went wrong
+        message: String,
+    },
+    /// A recorded message targets a plugin that is not installed
+    UnknownPlugin {
+        /// Line number, starting at 1
+        line: usize,
+        /// Name of the plugin
+        plugin: String,
+        /// Instance key of the plugin, if any
+        key: Option<String>,
+    },
+    /// A recorded message targets a plugin that was not registered with
+    /// [`PluginManagerBuilder::with_replay`](crate::PluginManagerBuilder::with_replay)
+    NotReplayable {
+        /// Line number, starting at 1
+        line: usize,
+        /// Name of the plugin
+        plugin: String,
+    },
+    /// Plugins are still running their `init_async` future, so replayed messages
+    /// would be queued for them rather than handled
+    NotReady {
+        /// Names of the plugins that are not ready
+        plugins: Vec<&'static str>,
+    },
+}
+
+#[cfg(feature = "replay")]
+impl fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReplayError::Io(error) => write!(f, "failed to read recording: {}", error),
+            ReplayError::Parse { line, message } => {
+                write!(f, "invalid recording at line {}: {}", line, message)
+            }
+            ReplayError::UnknownPlugin {
+                line,
+                plugin,
+                key: None,
+            } => write!(f, "plugin `{}` at line {} is not installed", plugin, line),
+            ReplayError::UnknownPlugin {
+                line,
+                plugin,
+                key: Some(key),
+            } => write!(
+                f,
+                "plugin `{}` with key `{}` at line {} is not installed",
+                plugin, key, line
+            ),
+            ReplayError::NotReplayable { line, plugin } => write!(
+                f,
+                "plugin `{}` at line {} was not registered for replay",
+                plugin, line
+            ),
+            ReplayError::NotReady { plugins } => {
+                write!(f, "plugins are still loading: {}", plugins.join(", "))
+            }
+        }
+    }
+}
+
+#[cfg(feature = "replay")]
+impl std::error::Error for ReplayError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ReplayError::Io(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(feature = "replay")]
+impl From<std::io::Error> for ReplayError {
+    fn from(error: std::io::Error) -> Self {
+        ReplayError::Io(error)
+    }
+}
+
+/// Errors returned by [`PluginManager::snapshot`](crate::PluginManager::snapshot) and
+/// [`PluginManager::restore`](crate::PluginManager::restore)
+#[cfg(feature = "snapshot")]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SnapshotError {
+    /// The state of a plugin could not be serialized
+    Serialize {
+        /// Name of the plugin
+        plugin: &'static str,
+        /// Instance key of the plugin, if any
+        key: Option<String>,
+        /// What went wrong
+        message: String,
+    },
+    /// A saved state doesn't fit the state type of its plugin, e.g. because the type
+    /// changed since the snapshot was taken
+    Deserialize {
+        /// Name of the plugin
+        plugin: &'static str,
+        /// Instance key of the plugin, if any
+        key: Option<String>,
+        /// What went wrong
+        message: String,
+    },
+    /// Plugins with a saved state are still running their `init_async` future, whose
+    /// result would replace the restored state
+    NotReady {
+        /// Names of the plugins that are not ready
+        plugins: Vec<&'static str>,
+    },
+}
+
+#[cfg(feature = "snapshot")]
+impl fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (action, plugin, key, message) = match self {
+            SnapshotError::NotReady { plugins } => {
+                return write!(f, "plugins are still loading: {}", plugins.join(", "));
+            }
+            SnapshotError::Serialize {
+                plugin,
+                key,
+                message,
+            } => ("save", plugin, key, message),
+            SnapshotError::Deserialize {
+                plugin,
+                key,
+                message,
+            } => ("restore", plugin, key, message),
+        };
+        match key {
+            Some(key) => write!(
+                f,
+                "failed to {} state of plugin `{}` with key `{}`: {}",
+                action, plugin, key, message
+            ),
+            None => write!(
+                f,
+                "failed to {} state of plugin `{}`: {}",
+                action, plugin, message
+            ),
+        }
+    }
+}
+
+#[cfg(feature = "snapshot")]
+impl std::error::Error for SnapshotError {}
+
+/// Errors returned when loading plugin configs, see
+/// [`ConfigSource`](crate::ConfigSource)
+#[cfg(feature = "config")]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ConfigError {
+    /// The config file exists but could not be read
+    Read {
+        /// Path of the config file
+        path: std::path::PathBuf,
+        /// What went wrong
+        message: String,
+    },
+    /// The config file is not valid TOML
+    Parse {
+        /// Path of the config file
+        path: std::path::PathBuf,
+        /// What went wrong
+        message: String,
+    },
+    /// The config of a plugin doesn't fit its config type
+    Invalid {
+        /// Name of the plugin
+        plugin: &'static str,
+        /// What went wrong
+        message: String,
+    },
+}
+
+#[cfg(feature = "config")]
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::Read { path, message } => {
+                write!(
+                    f,
+                    "failed to read config file {}: {}",
+                    path.display(),
+                    message
+                )
+            }
+            ConfigError::Parse { path, message } => {
+                write!(f, "invalid config file {}: {}", path.display(), message)
+            }
+            ConfigError::Invalid { plugin, message } => {
+                write!(f, "invalid config for plugin `{}`: {}", plugin, message)
+            }
+        }
+    }
+}
+
+#[cfg(feature = "config")]
+impl std::error::Error for ConfigError {}
+
+/// Errors returned when loading a [`DynamicPlugin`](crate::DynamicPlugin)
+#[cfg(feature = "dynamic")]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DynamicError {
+    /// The library or its directory could not be opened
+    Load {
+        /// Path of the library or directory
+        path: std::path::PathBuf,
+        /// What went wrong
+        message: String,
+    },
+    /// The library doesn't export the entry symbol of this ABI version, e.g. because it
+    /// is not a plugin or was built for another version
+    MissingEntry {
+        /// Path of the library
+        path: std::path::PathBuf,
+    },
+    /// The library was built against another ABI version
+    AbiMismatch {
+        /// Path of the library
+        path: std::path::PathBuf,
+        /// The version of this application
+        expected: u32,
+        /// The version of the library
+        found: u32,
+    },
+    /// The plugin name of the library is not valid UTF-8
+    InvalidName {
+        /// Path of the library
+        path: std::path::PathBuf,
+    },
+}
+
+#[cfg(feature = "dynamic")]
+impl fmt::Display for DynamicError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DynamicError::Load { path, message } => {
+                write!(f, "failed to load {}: {}", path.display(), message)
+            }
+            DynamicError::MissingEntry { path } => write!(
+                f,
+                "{} does not export `{}`",
+                path.display(),
+                crate::ENTRY_SYMBOL
+            ),
+            DynamicError::AbiMismatch {
+                path,
+                expected,
+                found,
+            } => write!(
+                f,
+                "{} was built for plugin ABI version {}, expected {}",
+                path.display(),
+                found,
+                expected
+            ),
+            DynamicError::InvalidName { path } => {
+                write!(f, "plugin name of {} is not valid UTF-8", path.display())
+            }
+        }
+    }
+}
+
+#[cfg(feature = "dynamic")]
+impl std::error::Error for DynamicError {}
@@ -0,0 +1,93 @@
+use crate::{Capability, PluginFault, PluginMessage, PluginOutput, PluginStatus};
+
+/// How many events the manager keeps while nobody listens to them, and as many faults
+pub const MANAGER_EVENT_BACKLOG: usize = 256;
+
+/// Lifecycle and routing event of a [`PluginManager`](crate::PluginManager)
+///
+/// Received through [`PluginManager::events`](crate::PluginManager::events).
+#[derive(Clone, Debug)]
+pub enum ManagerEvent {
+    /// A plugin was installed on the builder
+    PluginInstalled {
+        plugin: &'static str,
+        key: Option<String>,
+    },
+    /// A plugin's `init` ran while building the manager
+    PluginInitialized {
+        plugin: &'static str,
+        key: Option<String>,
+    },
+    /// A plugin became ready to handle messages, right after `init` or once its
+    /// [`Plugin::init_async`](crate::Plugin::init_async) future completed
+    PluginReady {
+        plugin: &'static str,
+        key: Option<String>,
+    },
+    /// A plugin was enabled, disabled or uninstalled at runtime
+    PluginStatusChanged {
+        plugin: &'static str,
+        key: Option<String>,
+        status: PluginStatus,
+    },
+    /// A message never reached its plugin
+    MessageDropped {
+        /// Name of the recipient, unless it doesn't exist
+        plugin: Option<&'static str>,
+        message: PluginMessage,
+        reason: DropReason,
+    },
+    /// A plugin emitted an output, after interceptors saw it
+    OutputEmitted {
+        plugin: &'static str,
+        key: Option<String>,
+        output: PluginOutput,
+    },
+    /// A plugin panicked or called
+    /// [`PluginContext::fault`](crate::PluginContext::fault), and was marked as
+    /// [`PluginStatus::Faulted`]
+    PluginFaulted(PluginFault),
+    /// Reloading the watched config file failed, so all plugins keep their config
+    #[cfg(feature = "config")]
+    ConfigReloadFailed(crate::ConfigError),
+}
+
+/// Why a message was dropped instead of being handled
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DropReason {
+    /// The message points to a plugin index this manager doesn't have, e.g. because
+    /// the handle that created it belongs to another manager
+    UnknownPlugin,
+    /// The message is not of the message type of its plugin
+    TypeMismatch,
+    /// An [`Interceptor`](crate::Interceptor) swallowed the message
+    Intercepted,
+    /// The plugin is disabled, faulted or uninstalled
+    NotRunning(PluginStatus),
+    /// The message was dispatched through a chain of more than
+    /// [`MAX_DISPATCH_DEPTH`](crate::MAX_DISPATCH_DEPTH) dispatches, e.g. by plugins
+    /// dispatching to each other in a loop
+    DispatchDepthExceeded,
+    /// The plugin dispatching the message was not granted a capability the recipient
+    /// declares
+    MissingCapability(Capability),
+}
+
+impl std::fmt::Display for DropReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DropReason::UnknownPlugin => write!(f, "no such plugin is installed"),
+            DropReason::TypeMismatch => write!(f, "message type does not match the plugin"),
+            DropReason::Intercepted => write!(f, "swallowed by an interceptor"),
+            DropReason::NotRunning(status) => write!(f, "plugin is {:?}", status),
+            DropReason::DispatchDepthExceeded => write!(
+                f,
+                "dispatched more than {} levels deep",
+                crate::MAX_DISPATCH_DEPTH
+            ),
+            DropReason::MissingCapability(capability) => {
+                write!(f, "sender lacks the `{}` capability", capability)
+            }
+        }
+    }
+}
@@ -0,0 +1,188 @@
+use crate::{PluginMessage, PluginOutput, PluginStatus};
+use iced::widget::{Column, Row, container, scrollable, text};
+use iced::{Element, Length};
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// How many messages and outputs the inspector keeps
+pub const INSPECTOR_HISTORY: usize = 200;
+
+/// Message toggling the inspector, routed by the manager itself
+#[derive(Debug)]
+pub(crate) struct ToggleInspector;
+
+/// Whether a recorded event went into a plugin or came out of it
+#[derive(Debug, Clone, Copy)]
+enum Direction {
+    Message,
+    Output,
+}
+
+#[derive(Debug)]
+struct Event {
+    elapsed: Duration,
+    plugin: &'static str,
+    direction: Direction,
+    payload: String,
+}
+
+/// Recent activity of a [`PluginManager`](crate::PluginManager) and whether it is shown
+#[derive(Debug)]
+pub(crate) struct Inspector {
+    visible: bool,
+    started: Instant,
+    events: VecDeque<Event>,
+    /// Subscription units per plugin index, as of the last
+    /// [`PluginManager::subscriptions`](crate::PluginManager::subscriptions)
+    subscriptions: Mutex<HashMap<usize, usize>>,
+}
+
+impl Inspector {
+    pub(crate) fn new() -> Self {
+        Self {
+            visible: false,
+            started: Instant::now(),
+            events: VecDeque::with_capacity(INSPECTOR_HISTORY),
+            subscriptions: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub(crate) fn toggle(&mut self) {
+        self.visible = !self.visible;
+    }
+
+    pub(crate) fn is_visible(&self) -> bool {
+        self.visible
+    }
+
+    /// Remember how many subscriptions the running plugins have, so the view doesn't
+    /// have to build them again
+    pub(crate) fn record_subscriptions(&self, counts: HashMap<usize, usize>) {
+        if let Ok(mut subscriptions) = self.subscriptions.lock() {
+            *subscriptions = counts;
+        }
+    }
+
+    pub(crate) fn subscription_count(&self, plugin_index: usize) -> usize {
+        self.subscriptions
+            .lock()
+            .ok()
+            .and_then(|subscriptions| subscriptions.get(&plugin_index).copied())
+            .unwrap_or(0)
+    }
+
+    pub(crate) fn record_message(&mut self, plugin: &'static str, message: &PluginMessage) {
+        self.record(
+            plugin,
+            Direction::Message,
+            format!("{:?}", message.payload()),
+        );
+    }
+
+    pub(crate) fn record_output(&mut self, plugin: &'static str, output: &PluginOutput) {
+        self.record(plugin, Direction::Output, format!("{:?}", output.payload()));
+    }
+
+    fn record(&mut self, plugin: &'static str, direction: Direction, payload: String) {
+        if self.events.len() == INSPECTOR_HISTORY {
+            self.events.pop_front();
+        }
+        self.events.push_back(Event {
+            elapsed: self.started.elapsed(),
+            plugin,
+            direction,
+            payload,
+        });
+    }
+}
+
+/// What the inspector shows about an installed plugin
+pub(crate) struct PluginSummary {
+    pub name: &'static str,
+    pub key: Option<String>,
+    pub status: PluginStatus,
+    pub state: String,
+    pub subscriptions: usize,
+    pub listeners: usize,
+    pub dropped: u64,
+}
+
+/// Render the installed plugins next to the most recent events, newest first
+pub(crate) fn view(
+    inspector: &Inspector,
+    plugins: Vec<PluginSummary>,
+) -> Element<'_, PluginMessage> {
+    let plugins = Column::with_children(plugins.into_iter().map(|plugin| {
+        let title = match &plugin.key {
+            Some(key) => format!("{} ({})", plugin.name, key),
+            None => plugin.name.to_string(),
+        };
+        let details = format!(
+            "{:?} · {} subscriptions · {} listeners · {} dropped outputs",
+            plugin.status, plugin.subscriptions, plugin.listeners, plugin.dropped
+        );
+
+        Column::new()
+            .push(text(title).size(14.0))
+            .push(text(details).size(11.0))
+            .push(text(plugin.state).size(11.0))
+            .spacing(2.0)
+            .into()
+    }))
+    .spacing(10.0);
+
+    let events = Column::with_children(inspector.events.iter().rev().map(|event| {
+        let arrow = match event.direction {
+            Direction::Message => "→",
+            Direction::Output => "←",
+        };
+        text(format!(
+            "{:>8.3}s {} {} {}",
+            event.elapsed.as_secs_f32(),
+            arrow,
+            event.plugin,
+            event.payload
+        ))
+        .size(11.0)
+        .into()
+    }))
+    .spacing(2.0);
+
+    let content = Column::new()
+        .push(text("Plugin Inspector").size(16.0))
+        .push(
+            Row::with_children([
+                container(scrollable(plugins).height(Length::Fill))
+                    .width(Length::Fill)
+                    .into(),
+                container(scrollable(events).height(Length::Fill))
+                    .width(Length::Fill)
+                    .into(),
+            ])
+            .spacing(10.0),
+        )
+        .spacing(10.0);
+
+    container(content)
+        .padding(10.0)
+        .width(Length::Fill)
+        .height(Length::Fill)
+        .style(container::rounded_box)
+        .into()
+}
+
+/// Toggle the inspector with F12
+#[cfg(debug_assertions)]
+pub(crate) fn hotkey() -> iced::Subscription<PluginMessage> {
+    use iced::keyboard::{self, Key, key::Named};
+
+    keyboard::listen().filter_map(|event| match event {
+        keyboard::Event::KeyPressed {
+            key: Key::Named(Named::F12),
+            repeat: false,
+            ..
+        } => Some(PluginMessage::toggle_inspector()),
+        _ => None,
+    })
+}
@@ -0,0 +1,71 @@
+use crate::{PluginMessage, PluginOutput};
+
+/// Hooks around [`PluginManager::update`](crate::PluginManager::update) for behavior
+/// that applies to every plugin, such as logging, rate limiting or auditing
+///
+/// Interceptors are registered with
+/// [`PluginManagerBuilder::with_interceptor`](crate::PluginManagerBuilder::with_interceptor)
+/// and run in registration order. Each one can pass a message or output on unchanged,
+/// replace it, or swallow it by returning `None`, in which case the interceptors after
+/// it don't see it either.
+///
+/// # Example
+/// ```ignore
+/// #[derive(Clone, Default)]
+/// struct ModalGuard(Arc<AtomicBool>);
+///
+/// impl Interceptor for ModalGuard {
+///     fn on_message(&mut self, plugin: &'static str, message: PluginMessage) -> Option<PluginMessage> {
+///         // Drop tray clicks while a modal is open
+///         (plugin != "tray_icon" || !self.0.load(Ordering::Relaxed)).then_some(message)
+///     }
+/// }
+/// ```
+pub trait Interceptor: Send {
+    /// Called with every message before it is routed to its plugin
+    ///
+    /// Messages carry the plugin's `Message` type, inputs having already been
+    /// converted. Swallowing a message that belongs to a
+    /// [`PluginHandle::call`](crate::PluginHandle::call) fails the call.
+    ///
+    /// Returning a message for another plugin, e.g. one created with the
+    /// [`PluginHandle`](crate::PluginHandle) of that plugin, delivers it there instead.
+    fn on_message(
+        &mut self,
+        _plugin: &'static str,
+        message: PluginMessage,
+    ) -> Option<PluginMessage> {
+        Some(message)
+    }
+
+    /// Called with every output before it is handed to callers and listeners
+    fn on_output(&mut self, _plugin: &'static str, output: PluginOutput) -> Option<PluginOutput> {
+        Some(output)
+    }
+}
+
+/// Run a message through all interceptors
+pub(crate) fn intercept_message(
+    interceptors: &mut [Box<dyn Interceptor>],
+    plugin: &'static str,
+    message: PluginMessage,
+) -> Option<PluginMessage> {
+    interceptors
+        .iter_mut()
+        .try_fold(message, |message, interceptor| {
+            interceptor.on_message(plugin, message)
+        })
+}
+
+/// Run an output through all interceptors
+pub(crate) fn intercept_output(
+    interceptors: &mut [Box<dyn Interceptor>],
+    plugin: &'static str,
+    output: PluginOutput,
+) -> Option<PluginOutput> {
+    interceptors
+        .iter_mut()
+        .try_fold(output, |output, interceptor| {
+            interceptor.on_output(plugin, output)
+        })
+}
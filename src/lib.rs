@@ -1,3 +1,53 @@
+mod capability;
+mod channel;
+#[cfg(feature = "config")]
+mod config;
+mod dependency;
+#[cfg(feature = "dynamic")]
+mod dynamic;
+mod error;
+mod event;
+mod inspector;
+mod interceptor;
+mod name;
 mod plugin;
+mod registry;
+#[cfg(feature = "replay")]
+mod replay;
+#[cfg(feature = "snapshot")]
+mod snapshot;
+#[cfg(feature = "testing")]
+mod testing;
+#[cfg(feature = "tracing")]
+mod trace;
 
+pub use capability::Capability;
+pub use channel::{OutputChannel, OutputMetrics, OverflowPolicy};
+pub use dependency::Dependency;
+pub use error::{BuildError, CallError, CapabilityError, LifecycleError};
+pub use event::{DropReason, MANAGER_EVENT_BACKLOG, ManagerEvent};
+pub use inspector::INSPECTOR_HISTORY;
+pub use interceptor::Interceptor;
+pub use name::intern_name;
 pub use plugin::*;
+
+#[cfg(feature = "config")]
+pub use config::{ConfigSource, Configurable};
+#[cfg(feature = "dynamic")]
+pub use dynamic::{
+    ABI_VERSION, DynamicPlugin, DynamicState, ENTRY_SYMBOL, FfiBuffer, NativePlugin, PluginVTable,
+};
+#[cfg(feature = "config")]
+pub use error::ConfigError;
+#[cfg(feature = "dynamic")]
+pub use error::DynamicError;
+#[cfg(feature = "replay")]
+pub use error::ReplayError;
+#[cfg(feature = "snapshot")]
+pub use error::SnapshotError;
+#[cfg(feature = "derive")]
+pub use iced_plugins_macros::PluginInput;
+#[cfg(feature = "snapshot")]
+pub use snapshot::Snapshot;
+#[cfg(feature = "testing")]
+pub use testing::{DEFAULT_TASK_TIMEOUT, PluginTester};
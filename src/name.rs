@@ -0,0 +1,38 @@
+use std::collections::BTreeSet;
+use std::sync::{Mutex, PoisonError};
+
+/// Names handed out by [`intern_name`]
+static NAMES: Mutex<BTreeSet<&'static str>> = Mutex::new(BTreeSet::new());
+
+/// Get a `&'static str` for the name of a plugin loaded at runtime
+///
+/// [`Plugin::name`](crate::Plugin::name) returns a static name, while plugins loaded
+/// from a library, a component or a script only know theirs once loaded. Each distinct
+/// name is leaked once and shared by every later load of it, so reloading plugins
+/// doesn't leak more memory.
+pub fn intern_name(name: &str) -> &'static str {
+    let mut names = NAMES.lock().unwrap_or_else(PoisonError::into_inner);
+    if let Some(interned) = names.get(name) {
+        return interned;
+    }
+
+    let interned: &'static str = Box::leak(name.to_owned().into_boxed_str());
+    names.insert(interned);
+    interned
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn interns_each_name_once() {
+        let first = intern_name("interned-plugin");
+        let second = intern_name(&String::from("interned-plugin"));
+        assert!(std::ptr::eq(first, second));
+
+        let other = intern_name("other-plugin");
+        assert_eq!(other, "other-plugin");
+        assert!(!std::ptr::eq(first, other));
+    }
+}
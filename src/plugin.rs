@@ -1,9 +1,45 @@
-use iced::futures::channel::mpsc;
-use iced::{Subscription, Task};
+#[cfg(feature = "config")]
+use crate::ConfigError;
+#[cfg(feature = "dynamic")]
+use crate::DynamicPlugin;
+#[cfg(feature = "replay")]
+use crate::ReplayError;
+use crate::capability;
+use crate::channel::{self, OutputChannel, OutputMetrics};
+#[cfg(feature = "config")]
+use crate::config::{self, ConfigCodec, ConfigSource, Configurable, LoadedConfig, ReloadConfig};
+use crate::dependency::{self, Node};
+use crate::event::{DropReason, ManagerEvent};
+use crate::inspector::{self, Inspector, PluginSummary, ToggleInspector};
+use crate::interceptor::{self, Interceptor};
+use crate::registry::{Registry, SharedRegistry};
+#[cfg(feature = "replay")]
+use crate::replay::{Codec, Record, Recorder};
+#[cfg(feature = "snapshot")]
+use crate::snapshot::{SavedState, StateCodec};
+#[cfg(feature = "tracing")]
+use crate::trace;
+use crate::{BuildError, CallError, Capability, CapabilityError, Dependency, LifecycleError};
+#[cfg(feature = "snapshot")]
+use crate::{Snapshot, SnapshotError};
+use iced::futures::FutureExt;
+use iced::futures::channel::oneshot;
+use iced::futures::future::BoxFuture;
+use iced::{Element, Subscription, Task};
 use std::any::{Any, TypeId};
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap, HashSet};
 use std::fmt::Debug;
+use std::panic::{self, AssertUnwindSafe};
 use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// How long [`PluginHandle::call`] waits for an output before failing
+pub const DEFAULT_CALL_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// How many levels deep inputs dispatched through a [`PluginContext`] may cause
+/// further dispatches before they are dropped, e.g. when two plugins keep
+/// dispatching to each other
+pub const MAX_DISPATCH_DEPTH: usize = 64;
 
 /// Core trait that all plugins must implement.
 /// Plugins can have their own state and respond to messages.
@@ -19,58 +55,186 @@ pub trait Plugin: Send + Sync + Debug {
 
     /// The output message type this plugin can emit
     /// These can be subscribed to by application code
-    type Output: Clone + Send + Sync + 'static;
+    type Output: Clone + Send + Sync + Debug + 'static;
 
     /// Returns the unique name/identifier for this plugin
     fn name(&self) -> &'static str;
 
+    /// Declare the plugin types this plugin requires
+    ///
+    /// Required plugins are initialized before this one. Building a manager
+    /// without them fails with [`BuildError::MissingDependency`].
+    fn dependencies(&self) -> Vec<Dependency> {
+        Vec::new()
+    }
+
+    /// Declare the access to the system this plugin needs, such as the network
+    ///
+    /// The application has to grant these with
+    /// [`PluginManagerBuilder::with_capabilities`], or building the manager fails with
+    /// [`BuildError::CapabilityNotGranted`]. Check them before use with
+    /// [`PluginContext::require`], which also covers optional capabilities the plugin
+    /// works without, and which are left out here.
+    ///
+    /// Other plugins must hold these capabilities too to dispatch to this plugin
+    /// through their [`PluginContext`], so a plugin can't write files by sending
+    /// inputs to the store.
+    fn capabilities(&self) -> Vec<Capability> {
+        Vec::new()
+    }
+
     /// Initialize the plugin and return its initial state
-    fn init(&self) -> (Self::State, Task<Self::Message>);
+    /// The context can be used to reach the other installed plugins
+    fn init(&self, ctx: &mut PluginContext<'_>) -> (Self::State, Task<Self::Message>);
+
+    /// Produce the state in the background, e.g. by reading it from disk
+    ///
+    /// A plugin returning a future here is not ready until the future completes and its
+    /// result replaces the state returned by [`Plugin::init`]. Messages sent to the plugin
+    /// in the meantime are queued and delivered in order once it is ready. Until then,
+    /// its subscription runs on the state from `init`, but it has no view.
+    ///
+    /// The context can be used like in `init`, before the future is spawned.
+    fn init_async(&self, _ctx: &mut PluginContext<'_>) -> Option<BoxFuture<'static, Self::State>> {
+        None
+    }
 
     /// Update the plugin state based on a message
     /// Returns a Task that can produce more messages and an optional output message
+    /// The context can be used to dispatch inputs to other plugins
     fn update(
         &self,
         state: &mut Self::State,
         message: Self::Message,
+        ctx: &mut PluginContext<'_>,
     ) -> (Task<Self::Message>, Option<Self::Output>);
 
     /// Subscribe to external events
     /// The state is passed as a reference to allow subscription to depend on state
     fn subscription(&self, state: &Self::State) -> Subscription<Self::Message>;
+
+    /// Handle an event broadcast to all plugins with [`PluginManager::broadcast`]
+    ///
+    /// Events are app-defined types, such as a theme change or the network going offline.
+    /// Downcast to the ones the plugin cares about and ignore the rest.
+    /// Returns a Task that can produce more messages, like [`Plugin::update`].
+    fn on_broadcast(
+        &self,
+        _state: &mut Self::State,
+        _event: &Broadcast,
+        _ctx: &mut PluginContext<'_>,
+    ) -> Task<Self::Message> {
+        Task::none()
+    }
+
+    /// Render a widget for the application to embed, e.g. an update prompt
+    /// Messages produced by the widget are routed back to this plugin.
+    /// Rendered through [`PluginManager::view_of`]; plugins without UI return None.
+    fn view<'a>(&'a self, _state: &'a Self::State) -> Option<Element<'a, Self::Message>> {
+        None
+    }
+
+    /// Clean up before the application exits, e.g. by flushing unsaved changes
+    /// Runs from [`PluginManager::shutdown`], before the plugins this one depends on shut down
+    fn shutdown(
+        &self,
+        _state: &mut Self::State,
+        _ctx: &mut PluginContext<'_>,
+    ) -> Task<Self::Message> {
+        Task::none()
+    }
+}
+
+/// Runtime status of an installed plugin
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PluginStatus {
+    /// The plugin handles messages and its subscription is active
+    Enabled,
+    /// The plugin keeps its state, but its subscription is dropped and its messages ignored
+    Disabled,
+    /// The plugin was removed from its manager along with its state
+    Uninstalled,
+    /// The plugin panicked or called [`PluginContext::fault`], and is disabled until
+    /// enabled again with [`PluginManager::enable`]
+    Faulted {
+        /// The panic message, or the reason passed to [`PluginContext::fault`]
+        reason: String,
+    },
+}
+
+impl PluginStatus {
+    /// Whether the plugin handles messages
+    pub fn is_enabled(&self) -> bool {
+        matches!(self, PluginStatus::Enabled)
+    }
+}
+
+/// A plugin panicked or called [`PluginContext::fault`], and was marked as
+/// [`PluginStatus::Faulted`]
+///
+/// Received through [`PluginManager::faults`] and as [`ManagerEvent::PluginFaulted`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PluginFault {
+    /// Name of the plugin
+    pub plugin: &'static str,
+    /// Instance key of the plugin, if installed with one
+    pub key: Option<String>,
+    /// The panic message, or the reason passed to [`PluginContext::fault`]
+    pub reason: String,
+}
+
+/// How many outputs of a plugin are kept for listeners that subscribe later
+///
+/// New listeners receive the buffered outputs, oldest first, before any new ones.
+/// A listener recreated with the same identity, see [`PluginHandle::listen_with`],
+/// only receives the buffered outputs it hasn't received before.
+/// See [`PluginManagerBuilder::with_output_buffer`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OutputBuffer {
+    /// Keep only the most recent output, e.g. the result of an update check
+    Latest,
+    /// Keep up to this many of the most recent outputs
+    Ring(usize),
 }
 
-/// Shared registry for managing output subscriptions
-type OutputRegistry = Arc<Mutex<HashMap<usize, Vec<mpsc::UnboundedSender<PluginOutput>>>>>;
+impl OutputBuffer {
+    fn capacity(self) -> usize {
+        match self {
+            OutputBuffer::Latest => 1,
+            OutputBuffer::Ring(capacity) => capacity,
+        }
+    }
+}
 
 /// Creates a stream that listens for plugin outputs with optional filtering
+///
+/// The stream reads the listener's output channel directly, so the channel is the
+/// only buffer between the plugin and the subscription and its overflow policy
+/// applies from the first output on.
 fn output_listener_filtered<O: Clone + Send + Sync + 'static, R>(
     plugin_index: usize,
+    listener_id: u64,
     output_type_id: TypeId,
-    registry: OutputRegistry,
+    output_channel: Option<OutputChannel>,
+    registry: SharedRegistry,
     filter: Arc<dyn Fn(O) -> Option<R> + Send + Sync>,
 ) -> impl iced::futures::Stream<Item = R> {
-    use iced::futures::{SinkExt, StreamExt};
+    use iced::futures::{StreamExt, future};
 
-    iced::stream::channel(100, move |mut output_sender: mpsc::Sender<R>| async move {
-        let (sender, mut receiver) = mpsc::unbounded();
+    let output_channel = output_channel.unwrap_or_else(|| registry.output_channel());
+    let (sender, receiver) = channel::channel(output_channel);
+    registry.listen(plugin_index, listener_id, sender);
 
-        if let Ok(mut reg) = registry.lock() {
-            reg.entry(plugin_index)
-                .or_insert_with(Vec::new)
-                .push(sender);
-        }
+    receiver.filter_map(move |output| {
+        let event = if plugin_index == output.plugin_index() && output_type_id == output.type_id {
+            output
+                .downcast::<O>()
+                .and_then(|output| filter(output.clone()))
+        } else {
+            None
+        };
 
-        while let Some(output) = receiver.next().await {
-            if plugin_index == output.plugin_index()
-                && output_type_id == output.type_id
-                && let Some(output) = output.downcast::<O>()
-                && let Some(event) = filter(output.clone())
-                && output_sender.send(event).await.is_err()
-            {
-                break;
-            };
-        }
+        future::ready(event)
     })
 }
 
@@ -78,19 +242,61 @@ fn output_listener_filtered<O: Clone + Send + Sync + 'static, R>(
 #[derive(Clone, Debug)]
 pub struct PluginHandle<P: Plugin> {
     plugin_index: usize,
-    output_registry: OutputRegistry,
+    registry: SharedRegistry,
+    /// Channel of the listeners created by this handle, instead of the manager's default
+    output_channel: Option<OutputChannel>,
     _phantom: std::marker::PhantomData<P>,
 }
 
 impl<P: Plugin> PluginHandle<P> {
-    fn new(plugin_index: usize, output_registry: OutputRegistry) -> Self {
+    fn new(plugin_index: usize, registry: SharedRegistry) -> Self {
         Self {
             plugin_index,
-            output_registry,
+            registry,
+            output_channel: None,
             _phantom: std::marker::PhantomData,
         }
     }
 
+    /// Use a different output channel for the listeners created by this handle
+    ///
+    /// Overrides the default set with [`PluginManagerBuilder::with_output_channel`].
+    ///
+    /// # Example
+    /// ```ignore
+    /// // Only the latest progress matters to a progress bar
+    /// self.updater_handle
+    ///     .with_output_channel(OutputChannel::bounded(1, OverflowPolicy::CoalesceLatest))
+    ///     .listen()
+    ///     .map(Message::UpdaterOutput)
+    /// ```
+    pub fn with_output_channel(mut self, output_channel: OutputChannel) -> Self {
+        self.output_channel = Some(output_channel);
+        self
+    }
+
+    #[cfg(feature = "testing")]
+    pub(crate) fn plugin_index(&self) -> usize {
+        self.plugin_index
+    }
+
+    /// Wrap an internal message of the plugin, e.g. one its subscription produces
+    #[cfg(feature = "testing")]
+    pub(crate) fn message(&self, message: P::Message) -> PluginMessage {
+        PluginMessage::new(self.plugin_index, message)
+    }
+
+    /// Get the current status of the plugin this handle points to
+    pub fn status(&self) -> PluginStatus {
+        self.registry.status(self.plugin_index)
+    }
+
+    /// Whether the plugin is still installed, enabled or not.
+    /// Returns false once it was uninstalled, after which the handle is stale.
+    pub fn is_installed(&self) -> bool {
+        self.status() != PluginStatus::Uninstalled
+    }
+
     /// Create a task that dispatches an input to this plugin
     ///
     /// # Example
@@ -107,6 +313,56 @@ impl<P: Plugin> PluginHandle<P> {
         PluginMessage::new(self.plugin_index, input.into())
     }
 
+    /// Create a task that sends an input to this plugin and resolves with the output it causes
+    ///
+    /// The message is tagged with a correlation id, and so are all messages produced by
+    /// the tasks of the plugin while handling it. The first output emitted for any of
+    /// them is routed back to this caller only. Listeners registered with
+    /// [`PluginHandle::listen`] still receive it as well.
+    ///
+    /// Fails with [`CallError::Timeout`] if no output arrives within [`DEFAULT_CALL_TIMEOUT`],
+    /// and with [`CallError::Unavailable`] if the plugin is disabled or uninstalled.
+    /// The message is routed through [`PluginManager::subscriptions`], which must be
+    /// part of the application's subscriptions.
+    ///
+    /// # Example
+    /// ```ignore
+    /// Message::Load => self
+    ///     .store_handle
+    ///     .call(StoreInput::get("ui", "prefs"))
+    ///     .map(Message::PrefsLoaded),
+    /// ```
+    pub fn call(&self, input: P::Input) -> Task<Result<P::Output, CallError>> {
+        self.call_with_timeout(input, DEFAULT_CALL_TIMEOUT)
+    }
+
+    /// Same as [`PluginHandle::call`] with a custom timeout
+    pub fn call_with_timeout(
+        &self,
+        input: P::Input,
+        timeout: Duration,
+    ) -> Task<Result<P::Output, CallError>> {
+        let registry = Arc::clone(&self.registry);
+        let plugin_index = self.plugin_index;
+        let message = self.input(input);
+
+        Task::future(async move {
+            if !registry.status(plugin_index).is_enabled() {
+                return Err(CallError::Unavailable);
+            }
+
+            let (correlation_id, receiver) = registry.start_call();
+
+            if !registry.send(message.with_correlation_id(correlation_id)) {
+                registry.cancel_call(correlation_id);
+                return Err(CallError::Closed);
+            }
+
+            wait_for_output::<P::Output>(registry, plugin_index, correlation_id, receiver, timeout)
+                .await
+        })
+    }
+
     /// Subscribe to outputs from this plugin with an optional filter
     ///
     /// Creates a subscription that will receive outputs emitted by this plugin.
@@ -124,13 +380,18 @@ impl<P: Plugin> PluginHandle<P> {
     /// }
     /// ```
     pub fn listen(&self) -> iced::Subscription<P::Output> {
-        self.listen_with_filter(Arc::new(Some))
+        self.listen_with_filter(Some)
     }
 
     /// Subscribe to filtered outputs from this plugin
     ///
     /// Creates a subscription that will only receive outputs that pass the filter predicate.
     ///
+    /// The subscription is identified by the plugin, the output channel and the type
+    /// of the filter, so it stays the same across calls to `subscription`. Closures
+    /// written once but capturing different values share an identity; listen with
+    /// separate closures to get separate subscriptions.
+    ///
     /// # Example
     /// ```ignore
     /// // Only listen to specific window events
@@ -147,36 +408,52 @@ impl<P: Plugin> PluginHandle<P> {
         F: Fn(P::Output) -> Option<O> + Send + Sync + 'static,
         O: Clone + Send + Sync + 'static,
     {
-        self.listen_with_filter(Arc::new(filter))
+        self.listen_with_filter(filter)
     }
 
-    fn listen_with_filter<O: Clone + Send + Sync + 'static>(
-        &self,
-        filter: Arc<dyn Fn(P::Output) -> Option<O> + Send + Sync + 'static>,
-    ) -> iced::Subscription<O> {
+    fn listen_with_filter<F, O>(&self, filter: F) -> iced::Subscription<O>
+    where
+        F: Fn(P::Output) -> Option<O> + Send + Sync + 'static,
+        O: Clone + Send + Sync + 'static,
+    {
+        use std::hash::{DefaultHasher, Hash, Hasher};
+
         struct ListenState<O, R> {
             plugin_index: usize,
             output_type_id: TypeId,
-            registry: OutputRegistry,
+            output_channel: Option<OutputChannel>,
+            registry: SharedRegistry,
             filter: Arc<dyn Fn(O) -> Option<R> + Send + Sync>,
-            filter_id: u64,
+            filter_id: TypeId,
             _phantom: std::marker::PhantomData<O>,
             _phantom_r: std::marker::PhantomData<R>,
         }
 
-        impl<O, R> std::hash::Hash for ListenState<O, R> {
-            fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        impl<O, R> Hash for ListenState<O, R> {
+            fn hash<H: Hasher>(&self, state: &mut H) {
                 self.plugin_index.hash(state);
                 std::any::type_name::<O>().hash(state);
+                self.output_channel.hash(state);
                 self.filter_id.hash(state);
             }
         }
 
+        impl<O, R> ListenState<O, R> {
+            /// Identity of the listener, the same for every subscription iced
+            /// considers equal
+            fn listener_id(&self) -> u64 {
+                let mut hasher = DefaultHasher::new();
+                self.hash(&mut hasher);
+                hasher.finish()
+            }
+        }
+
         impl<O, R> Clone for ListenState<O, R> {
             fn clone(&self) -> Self {
                 Self {
                     plugin_index: self.plugin_index,
                     output_type_id: self.output_type_id,
+                    output_channel: self.output_channel,
                     registry: Arc::clone(&self.registry),
                     filter: self.filter.clone(),
                     filter_id: self.filter_id,
@@ -191,7 +468,9 @@ impl<P: Plugin> PluginHandle<P> {
         ) -> iced::futures::stream::BoxStream<'static, R> {
             Box::pin(output_listener_filtered::<O, R>(
                 state.plugin_index,
+                state.listener_id(),
                 state.output_type_id,
+                state.output_channel,
                 Arc::clone(&state.registry),
                 state.filter.clone(),
             ))
@@ -200,9 +479,10 @@ impl<P: Plugin> PluginHandle<P> {
         let state = ListenState::<P::Output, O> {
             plugin_index: self.plugin_index,
             output_type_id: TypeId::of::<P::Output>(),
-            registry: Arc::clone(&self.output_registry),
-            filter_id: Arc::as_ptr(&filter) as *const () as u64,
-            filter,
+            output_channel: self.output_channel,
+            registry: Arc::clone(&self.registry),
+            filter: Arc::new(filter),
+            filter_id: TypeId::of::<F>(),
             _phantom: std::marker::PhantomData,
             _phantom_r: std::marker::PhantomData,
         };
@@ -211,28 +491,456 @@ impl<P: Plugin> PluginHandle<P> {
     }
 }
 
+/// Wait for the output of a call, giving up after `timeout`
+async fn wait_for_output<O: Clone + 'static>(
+    registry: SharedRegistry,
+    plugin_index: usize,
+    correlation_id: u64,
+    receiver: oneshot::Receiver<PluginOutput>,
+    timeout: Duration,
+) -> Result<O, CallError> {
+    let result = match tokio::time::timeout(timeout, receiver).await {
+        Ok(Ok(output)) => output.downcast::<O>().cloned().ok_or(CallError::Closed),
+        // The manager dropped the message without handling it
+        Ok(Err(_)) if !registry.status(plugin_index).is_enabled() => Err(CallError::Unavailable),
+        Ok(Err(_)) => Err(CallError::Closed),
+        Err(_) => Err(CallError::Timeout),
+    };
+
+    registry.cancel_call(correlation_id);
+    result
+}
+
+/// Context handed to a plugin in [`Plugin::init`] and [`Plugin::update`]
+///
+/// It resolves handles to the other installed plugins and collects messages and
+/// tasks targeting them. Plugins can work together through the manager this way
+/// instead of going around it.
+///
+/// # Example
+/// ```ignore
+/// fn update(&self, state: &mut Self::State, message: Self::Message, ctx: &mut PluginContext<'_>)
+///     -> (Task<Self::Message>, Option<Self::Output>)
+/// {
+///     match message {
+///         MyMessage::Save => {
+///             ctx.dispatch::<StorePlugin>(StoreInput::set("my_plugin", "state", &state.data));
+///             (Task::none(), None)
+///         }
+///     }
+/// }
+/// ```
+pub struct PluginContext<'a> {
+    plugins: &'a [PluginEntry],
+    registry: &'a SharedRegistry,
+    /// The plugin the context was created for
+    plugin: &'a PluginEntry,
+    messages: Vec<PluginMessage>,
+    tasks: Vec<Task<PluginMessage>>,
+    /// Why the plugin faulted itself, see [`PluginContext::fault`]
+    fault: Option<String>,
+    /// Config of the plugin the context was created for
+    #[cfg(feature = "config")]
+    config: Option<&'a AnyRef>,
+}
+
+impl<'a> PluginContext<'a> {
+    fn new(plugins: &'a [PluginEntry], registry: &'a SharedRegistry, plugin_index: usize) -> Self {
+        Self {
+            plugins,
+            registry,
+            plugin: &plugins[plugin_index],
+            messages: Vec::new(),
+            tasks: Vec::new(),
+            fault: None,
+            #[cfg(feature = "config")]
+            config: None,
+        }
+    }
+
+    /// Get what a hook run with this context returned, or why the plugin faulted in it
+    fn outcome<T>(&mut self, result: std::thread::Result<T>) -> Result<T, String> {
+        let value = result.map_err(|panic| panic_reason(panic.as_ref()))?;
+        match self.fault.take() {
+            Some(reason) => Err(reason),
+            None => Ok(value),
+        }
+    }
+
+    /// Get the config of the plugin being initialized or updated, layered from its
+    /// defaults, the config file and the environment
+    ///
+    /// Returns None if `P` was not registered with
+    /// [`PluginManagerBuilder::with_config`], or is not the plugin this context
+    /// belongs to.
+    ///
+    /// # Example
+    /// ```ignore
+    /// fn init(&self, ctx: &mut PluginContext<'_>) -> (Self::State, Task<Self::Message>) {
+    ///     let config = ctx.config::<Self>().unwrap_or_else(|| self.default_config());
+    ///     // ...
+    /// }
+    /// ```
+    #[cfg(feature = "config")]
+    pub fn config<P: Configurable + 'static>(&self) -> Option<P::Config> {
+        self.config?.downcast_ref::<P::Config>().cloned()
+    }
+
+    /// Whether the application granted the plugin a capability
+    pub fn has_capability(&self, capability: Capability) -> bool {
+        self.plugin.granted.contains(&capability)
+    }
+
+    /// Check that the plugin may use a capability before using it
+    ///
+    /// The capabilities declared in [`Plugin::capabilities`] are always granted, since
+    /// building fails otherwise. Optional ones are only granted if the application
+    /// chose to.
+    ///
+    /// # Example
+    /// ```ignore
+    /// MyMessage::Install(path) => {
+    ///     if let Err(error) = ctx.require(Capability::SpawnProcess) {
+    ///         return (Task::none(), Some(MyOutput::Error(error.to_string())));
+    ///     }
+    ///     (Task::perform(install(path), MyMessage::Installed), None)
+    /// }
+    /// ```
+    pub fn require(&self, capability: Capability) -> Result<(), CapabilityError> {
+        if self.has_capability(capability) {
+            Ok(())
+        } else {
+            Err(CapabilityError {
+                plugin: self.plugin.name,
+                capability,
+            })
+        }
+    }
+
+    /// Get a handle to another installed plugin by its type.
+    /// Returns None if the plugin is not installed without a key.
+    pub fn handle<P: Plugin + 'static>(&self) -> Option<PluginHandle<P>> {
+        self.find_handle(None)
+    }
+
+    /// Get a handle to the instance of a plugin type installed with `key`.
+    /// Returns None if no such instance is installed.
+    pub fn handle_keyed<P: Plugin + 'static>(&self, key: &str) -> Option<PluginHandle<P>> {
+        self.find_handle(Some(key))
+    }
+
+    /// Get the name of the plugin of type `P` installed without a key.
+    /// Returns None if no such plugin is installed.
+    pub fn plugin_name<P: Plugin + 'static>(&self) -> Option<&'static str> {
+        find_plugin::<P>(self.plugins, self.registry, None).map(|index| self.plugins[index].name)
+    }
+
+    fn find_handle<P: Plugin + 'static>(&self, key: Option<&str>) -> Option<PluginHandle<P>> {
+        find_plugin::<P>(self.plugins, self.registry, key)
+            .map(|index| PluginHandle::new(index, Arc::clone(self.registry)))
+    }
+
+    /// Dispatch an input to another installed plugin
+    ///
+    /// The message is delivered right after the current `init` or `update` returns,
+    /// before any task runs. Returns false if the plugin is not installed without a
+    /// key, or disabled. Chains of dispatches deeper than [`MAX_DISPATCH_DEPTH`] are
+    /// dropped with [`DropReason::DispatchDepthExceeded`].
+    ///
+    /// This plugin must have been granted the capabilities the other plugin declares.
+    /// Otherwise the message is dropped with [`DropReason::MissingCapability`], and
+    /// false is returned.
+    pub fn dispatch<P: Plugin + 'static>(&mut self, input: P::Input) -> bool {
+        let handle = self.handle::<P>();
+        self.dispatch_to(handle, input)
+    }
+
+    /// Same as [`PluginContext::dispatch`] for the instance installed with `key`
+    pub fn dispatch_keyed<P: Plugin + 'static>(&mut self, key: &str, input: P::Input) -> bool {
+        let handle = self.handle_keyed::<P>(key);
+        self.dispatch_to(handle, input)
+    }
+
+    fn dispatch_to<P: Plugin + 'static>(
+        &mut self,
+        handle: Option<PluginHandle<P>>,
+        input: P::Input,
+    ) -> bool {
+        match handle {
+            Some(handle) if handle.status().is_enabled() => {
+                let message = handle.input(input);
+                if let Some(missing) = self.missing_capability(handle.plugin_index) {
+                    report_drop(
+                        self.registry,
+                        Some(self.plugins[handle.plugin_index].name),
+                        message,
+                        DropReason::MissingCapability(missing),
+                    );
+                    return false;
+                }
+                self.messages.push(message);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Get a capability another plugin declares which this plugin wasn't granted
+    fn missing_capability(&self, plugin_index: usize) -> Option<Capability> {
+        self.plugins[plugin_index]
+            .capabilities
+            .iter()
+            .find(|capability| !self.plugin.granted.contains(capability))
+            .copied()
+    }
+
+    /// Dispatch an input to another installed plugin like [`PluginContext::dispatch`],
+    /// and get a task resolving with the output it causes like [`PluginHandle::call`]
+    ///
+    /// Fails with [`CallError::Unavailable`] if the plugin is not installed without a
+    /// key, or disabled, and with [`CallError::MissingCapability`] if this plugin
+    /// lacks a capability the other plugin declares.
+    ///
+    /// # Example
+    /// ```ignore
+    /// MyMessage::Save => {
+    ///     let task = ctx
+    ///         .call::<StorePlugin>(StoreInput::set("my_plugin", "state", &state.data))
+    ///         .map(MyMessage::Saved);
+    ///     (task, None)
+    /// }
+    /// ```
+    pub fn call<P: Plugin + 'static>(
+        &mut self,
+        input: P::Input,
+    ) -> Task<Result<P::Output, CallError>> {
+        let handle = self.handle::<P>();
+        self.call_to(handle, input)
+    }
+
+    /// Same as [`PluginContext::call`] for the instance installed with `key`
+    pub fn call_keyed<P: Plugin + 'static>(
+        &mut self,
+        key: &str,
+        input: P::Input,
+    ) -> Task<Result<P::Output, CallError>> {
+        let handle = self.handle_keyed::<P>(key);
+        self.call_to(handle, input)
+    }
+
+    fn call_to<P: Plugin + 'static>(
+        &mut self,
+        handle: Option<PluginHandle<P>>,
+        input: P::Input,
+    ) -> Task<Result<P::Output, CallError>> {
+        let Some(handle) = handle.filter(|handle| handle.status().is_enabled()) else {
+            return Task::done(Err(CallError::Unavailable));
+        };
+        if let Some(missing) = self.missing_capability(handle.plugin_index) {
+            report_drop(
+                self.registry,
+                Some(self.plugins[handle.plugin_index].name),
+                handle.input(input),
+                DropReason::MissingCapability(missing),
+            );
+            return Task::done(Err(CallError::MissingCapability(missing)));
+        }
+
+        let (correlation_id, receiver) = self.registry.start_call();
+        let message = handle.input(input).with_correlation_id(correlation_id);
+        self.messages.push(message);
+
+        Task::future(wait_for_output::<P::Output>(
+            Arc::clone(self.registry),
+            handle.plugin_index,
+            correlation_id,
+            receiver,
+            DEFAULT_CALL_TIMEOUT,
+        ))
+    }
+
+    /// Run a task producing messages for any plugin, such as one created with
+    /// [`PluginHandle::dispatch`]
+    pub fn emit(&mut self, task: Task<PluginMessage>) {
+        self.tasks.push(task);
+    }
+
+    /// Fault the plugin once the current hook returns, like a panic would
+    ///
+    /// The plugin is marked as [`PluginStatus::Faulted`] with `reason`, and what the
+    /// hook returns, dispatches or emits is dropped. For errors the plugin can't recover
+    /// from, such as a script that fails to run. Only the first reason is kept.
+    ///
+    /// # Example
+    /// ```ignore
+    /// fn update(&self, state: &mut Self::State, message: Self::Message, ctx: &mut PluginContext<'_>)
+    ///     -> (Task<Self::Message>, Option<Self::Output>)
+    /// {
+    ///     match state.engine.run(message) {
+    ///         Ok(output) => (Task::none(), output),
+    ///         Err(error) => {
+    ///             ctx.fault(error.to_string());
+    ///             (Task::none(), None)
+    ///         }
+    ///     }
+    /// }
+    /// ```
+    pub fn fault(&mut self, reason: impl Into<String>) {
+        self.fault.get_or_insert_with(|| reason.into());
+    }
+}
+
+/// Formats a type-erased payload with the `Debug` impl of its concrete type
+type DebugFn = fn(&AnyRef, &mut std::fmt::Formatter<'_>) -> std::fmt::Result;
+
+fn debug_payload<T: Debug + 'static>(
+    value: &AnyRef,
+    f: &mut std::fmt::Formatter<'_>,
+) -> std::fmt::Result {
+    match value.downcast_ref::<T>() {
+        Some(value) => value.fmt(f),
+        None => f.write_str(".."),
+    }
+}
+
+/// A type-erased payload that formats like its concrete type
+pub(crate) struct Payload<'a> {
+    value: &'a AnyRef,
+    debug: DebugFn,
+}
+
+impl Debug for Payload<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        (self.debug)(self.value, f)
+    }
+}
+
+/// An app-defined event sent to every plugin with [`PluginManager::broadcast`]
+///
+/// # Example
+/// ```ignore
+/// fn on_broadcast(
+///     &self,
+///     state: &mut Self::State,
+///     event: &Broadcast,
+///     _ctx: &mut PluginContext<'_>,
+/// ) -> Task<Self::Message> {
+///     if let Some(AppEvent::NetworkChanged { online }) = event.downcast::<AppEvent>() {
+///         state.online = *online;
+///     }
+///     Task::none()
+/// }
+/// ```
+#[derive(Clone)]
+pub struct Broadcast {
+    event: Arc<AnyRef>,
+    debug: DebugFn,
+}
+
+impl Broadcast {
+    fn new<E: 'static + Send + Sync + Debug>(event: E) -> Self {
+        Self {
+            event: Arc::new(event),
+            debug: debug_payload::<E>,
+        }
+    }
+
+    /// Get the event if it is of type `E`
+    pub fn downcast<E: 'static>(&self) -> Option<&E> {
+        self.event.downcast_ref::<E>()
+    }
+}
+
+impl Debug for Broadcast {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let payload = Payload {
+            value: self.event.as_ref(),
+            debug: self.debug,
+        };
+        f.debug_tuple("Broadcast").field(&payload).finish()
+    }
+}
+
 /// A type-erased plugin message that can be routed automatically
-#[derive(Clone, Debug)]
+#[derive(Clone)]
 pub struct PluginMessage {
     plugin_index: usize,
     message: Arc<dyn Any + Send + Sync>,
     type_id: TypeId,
+    debug: DebugFn,
+    correlation_id: Option<u64>,
 }
 
 impl PluginMessage {
     /// Create a new plugin message
-    fn new<M: 'static + Send + Sync>(plugin_index: usize, message: M) -> Self {
+    pub(crate) fn new<M: 'static + Send + Sync + Debug>(plugin_index: usize, message: M) -> Self {
         Self {
             plugin_index,
             type_id: TypeId::of::<M>(),
             message: Arc::new(message),
+            debug: debug_payload::<M>,
+            correlation_id: None,
+        }
+    }
+
+    /// Message routed to the manager itself, toggling its inspector
+    pub(crate) fn toggle_inspector() -> Self {
+        Self::new(usize::MAX, ToggleInspector)
+    }
+
+    pub(crate) fn payload(&self) -> Payload<'_> {
+        Payload {
+            value: self.message.as_ref(),
+            debug: self.debug,
         }
     }
 
+    fn with_correlation_id(mut self, correlation_id: u64) -> Self {
+        self.correlation_id = Some(correlation_id);
+        self
+    }
+
     /// Get the plugin index this message is for
     pub fn plugin_index(&self) -> usize {
         self.plugin_index
     }
+
+    /// Get the correlation id of the [`PluginHandle::call`] this message belongs to, if any
+    pub fn correlation_id(&self) -> Option<u64> {
+        self.correlation_id
+    }
+
+    /// Try to downcast the message to the `Message` type of its plugin
+    pub fn downcast<M: 'static>(&self) -> Option<&M> {
+        if self.type_id == TypeId::of::<M>() {
+            self.message.downcast_ref::<M>()
+        } else {
+            None
+        }
+    }
+
+    /// Replace the message, keeping its recipient and correlation id
+    ///
+    /// Used by [`Interceptor`]s to transform messages. The new message must be of the
+    /// `Message` type of the plugin, or the plugin ignores it.
+    pub fn with_message<M: 'static + Send + Sync + Debug>(self, message: M) -> Self {
+        Self {
+            type_id: TypeId::of::<M>(),
+            message: Arc::new(message),
+            debug: debug_payload::<M>,
+            ..self
+        }
+    }
+}
+
+impl Debug for PluginMessage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PluginMessage")
+            .field("plugin_index", &self.plugin_index)
+            .field("message", &self.payload())
+            .field("correlation_id", &self.correlation_id)
+            .finish()
+    }
 }
 
 /// Non-capturing function pointer for plugin subscriptions
@@ -241,8 +949,12 @@ fn plugin_subscription_fn<P: Plugin + 'static>(
     plugin: &AnyRef,
     plugin_index: usize,
 ) -> Subscription<PluginMessage> {
-    let typed_state = state.downcast_ref::<P::State>().unwrap();
-    let typed_plugin = plugin.downcast_ref::<Arc<P>>().unwrap();
+    let (Some(typed_state), Some(typed_plugin)) = (
+        state.downcast_ref::<P::State>(),
+        plugin.downcast_ref::<Arc<P>>(),
+    ) else {
+        return Subscription::none();
+    };
     let inner_sub = typed_plugin.subscription(typed_state);
 
     inner_sub
@@ -250,20 +962,43 @@ fn plugin_subscription_fn<P: Plugin + 'static>(
         .map(|(plugin_index, msg)| PluginMessage::new(plugin_index, msg))
 }
 
+/// Non-capturing function pointer for plugin views
+fn plugin_view_fn<'a, P: Plugin + 'static>(
+    state: &'a dyn Any,
+    plugin: &'a AnyRef,
+    plugin_index: usize,
+) -> Option<Element<'a, PluginMessage>> {
+    let typed_state = state.downcast_ref::<P::State>()?;
+    let typed_plugin = plugin.downcast_ref::<Arc<P>>()?;
+
+    typed_plugin
+        .view(typed_state)
+        .map(|element| element.map(move |msg| PluginMessage::new(plugin_index, msg)))
+}
+
 /// Type-erased output message from a plugin
 #[derive(Clone)]
 pub struct PluginOutput {
     plugin_index: usize,
     output: Arc<dyn Any + Send + Sync>,
     type_id: TypeId,
+    debug: DebugFn,
 }
 
 impl PluginOutput {
-    fn new<O: 'static + Send + Sync>(plugin_index: usize, output: O) -> Self {
+    pub(crate) fn new<O: 'static + Send + Sync + Debug>(plugin_index: usize, output: O) -> Self {
         Self {
             plugin_index,
             type_id: TypeId::of::<O>(),
             output: Arc::new(output),
+            debug: debug_payload::<O>,
+        }
+    }
+
+    pub(crate) fn payload(&self) -> Payload<'_> {
+        Payload {
+            value: self.output.as_ref(),
+            debug: self.debug,
         }
     }
 
@@ -280,15 +1015,27 @@ impl PluginOutput {
             None
         }
     }
+
+    /// Replace the output, keeping the plugin it is from
+    ///
+    /// Used by [`Interceptor`]s to transform outputs. Listeners and callers only receive
+    /// the new output if it is of the `Output` type of the plugin.
+    pub fn with_output<O: 'static + Send + Sync + Debug>(self, output: O) -> Self {
+        Self {
+            type_id: TypeId::of::<O>(),
+            output: Arc::new(output),
+            debug: debug_payload::<O>,
+            ..self
+        }
+    }
 }
 
 impl std::fmt::Debug for PluginOutput {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(
-            f,
-            "PluginOutput {{ plugin_index: {}, type_id: {:?} }}",
-            self.plugin_index, self.type_id
-        )
+        f.debug_struct("PluginOutput")
+            .field("plugin_index", &self.plugin_index)
+            .field("output", &self.payload())
+            .finish()
     }
 }
 
@@ -296,28 +1043,103 @@ type AnyRef = dyn Any + Send + Sync;
 type AnyPlugin = Arc<dyn Any + Send + Sync>;
 type AnyMessage = Arc<dyn Any + Send + Sync>;
 
-type UpdateFn =
-    dyn Fn(&mut dyn Any, AnyMessage) -> (Task<PluginMessage>, Option<PluginOutput>) + Send + Sync;
+type AnyState = Box<dyn Any + Send>;
+
+type InitFn = dyn Fn(&mut PluginContext<'_>) -> (AnyState, Task<PluginMessage>) + Send + Sync;
+
+type InitAsyncFn = dyn Fn(&mut PluginContext<'_>) -> Option<Task<PluginMessage>> + Send + Sync;
+
+type UpdateFn = dyn Fn(
+        &mut dyn Any,
+        AnyMessage,
+        &mut PluginContext<'_>,
+    ) -> (Task<PluginMessage>, Option<PluginOutput>)
+    + Send
+    + Sync;
+
+type ShutdownFn = dyn Fn(&mut dyn Any, &mut PluginContext<'_>) -> Task<PluginMessage> + Send + Sync;
+
+/// Message routed to the manager itself, carrying the state produced by a plugin's
+/// [`Plugin::init_async`] future, or the message of its panic
+#[derive(Debug)]
+struct Loaded(Mutex<Option<Result<AnyState, String>>>);
+
+/// Find the index of the installed plugin of type `P` with the given instance key.
+/// A type is installed at most once per key, so the match is unique.
+fn find_plugin<P: Plugin + 'static>(
+    plugins: &[PluginEntry],
+    registry: &Registry,
+    key: Option<&str>,
+) -> Option<usize> {
+    plugins.iter().position(|p| {
+        TypeId::of::<P>() == p.plugin_type
+            && p.key.as_deref() == key
+            && registry.status(p.plugin_index) != PluginStatus::Uninstalled
+    })
+}
 
-/// Holds a single plugin instance with its state and behavior
+/// Report a message that is never handled
+fn report_drop(
+    registry: &SharedRegistry,
+    plugin: Option<&'static str>,
+    message: PluginMessage,
+    reason: DropReason,
+) {
+    #[cfg(feature = "tracing")]
+    tracing::warn!(
+        plugin,
+        message = %trace::variant(&message.payload()),
+        %reason,
+        "message dropped"
+    );
+
+    // Don't leave a caller waiting on it
+    if let Some(correlation_id) = message.correlation_id {
+        registry.cancel_call(correlation_id);
+    }
+    registry.emit(ManagerEvent::MessageDropped {
+        plugin,
+        message,
+        reason,
+    });
+}
+
+/// Holds a single plugin instance with its behavior
 struct PluginEntry {
     name: &'static str,
-    state: Box<dyn Any + Send>,
     plugin_type: TypeId,
+    /// Instance key, to tell apart plugins of the same type
+    key: Option<String>,
+    dependencies: Vec<Dependency>,
+    /// Declared capabilities, sorted and without duplicates
+    capabilities: Vec<Capability>,
+    /// Capabilities the application granted, set when building
+    granted: BTreeSet<Capability>,
     message_type_id: TypeId,
     output_type_id: TypeId,
     plugin: AnyPlugin,
     plugin_index: usize,
+    init_fn: Box<InitFn>,
+    init_async_fn: Box<InitAsyncFn>,
     update_fn: Box<UpdateFn>,
+    shutdown_fn: Box<ShutdownFn>,
     subscription_fn: fn(&dyn Any, &AnyRef, usize) -> Subscription<PluginMessage>,
+    view_fn: for<'a> fn(&'a dyn Any, &'a AnyRef, usize) -> Option<Element<'a, PluginMessage>>,
+    state_debug_fn: fn(&dyn Any) -> String,
 }
 
 impl std::fmt::Debug for PluginEntry {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(
             f,
-            "PluginEntry {{ name: {}, plugin_type: {:?}, message_type_id: {:?}, output_type_id: {:?}, state: {:?} }}",
-            self.name, self.plugin_type, self.message_type_id, self.output_type_id, self.state
+            "PluginEntry {{ name: {}, plugin_type: {:?}, key: {:?}, dependencies: {:?}, capabilities: {:?}, message_type_id: {:?}, output_type_id: {:?} }}",
+            self.name,
+            self.plugin_type,
+            self.key,
+            self.dependencies,
+            self.capabilities,
+            self.message_type_id,
+            self.output_type_id
         )
     }
 }
@@ -334,12 +1156,45 @@ impl std::fmt::Debug for PluginEntry {
 /// ```
 pub struct PluginManager {
     plugins: Vec<PluginEntry>,
-    output_registry: OutputRegistry,
+    /// Plugin states, indexed like `plugins` and filled in when the manager is built.
+    /// Uninstalled plugins keep their slot so indices held by handles stay valid.
+    states: Vec<Option<AnyState>>,
+    /// Plugin indices in the order they were initialized
+    init_order: Vec<usize>,
+    /// Plugins whose [`Plugin::init_async`] future hasn't completed yet, with the
+    /// messages queued for them
+    loading: HashMap<usize, Vec<PluginMessage>>,
+    /// Plugins whose [`Plugin::shutdown`] hook has run
+    shut_down: HashSet<usize>,
+    interceptors: Vec<Box<dyn Interceptor>>,
+    /// Present when built with [`PluginManagerBuilder::with_inspector`]
+    inspector: Option<Inspector>,
+    /// Codecs of the plugin types registered with [`PluginManagerBuilder::with_replay`]
+    #[cfg(feature = "replay")]
+    codecs: HashMap<TypeId, Codec>,
+    #[cfg(feature = "replay")]
+    recorder: Option<Recorder>,
+    /// Codecs of the plugin types registered with [`PluginManagerBuilder::with_snapshot`]
+    #[cfg(feature = "snapshot")]
+    state_codecs: HashMap<TypeId, StateCodec>,
+    #[cfg(feature = "config")]
+    config_source: Option<ConfigSource>,
+    /// Codecs of the plugin types registered with [`PluginManagerBuilder::with_config`]
+    #[cfg(feature = "config")]
+    config_codecs: HashMap<TypeId, ConfigCodec>,
+    /// Current configs, by plugin index
+    #[cfg(feature = "config")]
+    configs: HashMap<usize, LoadedConfig>,
+    registry: SharedRegistry,
 }
 
 impl std::fmt::Debug for PluginManager {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "PluginManager {{ plugins: {:?} }}", self.plugins,)
+        write!(
+            f,
+            "PluginManager {{ plugins: {:?}, states: {:?} }}",
+            self.plugins, self.states
+        )
     }
 }
 
@@ -354,63 +1209,236 @@ impl PluginManager {
     pub fn new() -> Self {
         Self {
             plugins: Vec::new(),
-            output_registry: Arc::new(Mutex::new(HashMap::new())),
+            states: Vec::new(),
+            init_order: Vec::new(),
+            loading: HashMap::new(),
+            shut_down: HashSet::new(),
+            interceptors: Vec::new(),
+            inspector: None,
+            #[cfg(feature = "replay")]
+            codecs: HashMap::new(),
+            #[cfg(feature = "replay")]
+            recorder: None,
+            #[cfg(feature = "snapshot")]
+            state_codecs: HashMap::new(),
+            #[cfg(feature = "config")]
+            config_source: None,
+            #[cfg(feature = "config")]
+            config_codecs: HashMap::new(),
+            #[cfg(feature = "config")]
+            configs: HashMap::new(),
+            registry: Registry::new(),
         }
     }
 }
 
 impl PluginManager {
-    /// Internal method to install a plugin into the manager.
-    /// Returns the initial task for this plugin.
+    /// Internal method to register a plugin with the manager.
+    /// The plugin is initialized later, once all plugins are known.
     ///
     /// Users should use PluginManagerBuilder to install plugins instead.
-    fn install_internal<P>(&mut self, plugin: P) -> (PluginHandle<P>, Task<PluginMessage>)
+    fn register<P>(&mut self, key: Option<String>, plugin: P) -> PluginHandle<P>
     where
         P: Plugin + 'static,
     {
         let name = plugin.name();
+        let dependencies = plugin.dependencies();
+        let mut capabilities = plugin.capabilities();
+        capabilities.sort();
+        capabilities.dedup();
         let plugin = Arc::new(plugin);
-        let (state, init_task) = plugin.init();
         let plugin_index = self.plugins.len();
         let message_type_id = TypeId::of::<P::Message>();
         let output_type_id = TypeId::of::<P::Output>();
 
+        let plugin_for_init = Arc::clone(&plugin);
+        let init_fn = Box::new(move |ctx: &mut PluginContext<'_>| {
+            let (state, init_task) = plugin_for_init.init(ctx);
+            let state: AnyState = Box::new(state);
+            (
+                state,
+                init_task.map(move |msg| {
+                    #[cfg(feature = "tracing")]
+                    trace::task_completed(name, &msg);
+                    PluginMessage::new(plugin_index, msg)
+                }),
+            )
+        });
+
+        let plugin_for_init_async = Arc::clone(&plugin);
+        let init_async_fn = Box::new(move |ctx: &mut PluginContext<'_>| {
+            plugin_for_init_async.init_async(ctx).map(|future| {
+                Task::future(AssertUnwindSafe(future).catch_unwind()).map(move |result| {
+                    let state = result
+                        .map(|state| Box::new(state) as AnyState)
+                        .map_err(|panic| panic_reason(panic.as_ref()));
+                    PluginMessage::new(plugin_index, Loaded(Mutex::new(Some(state))))
+                })
+            })
+        });
+
         let plugin_for_update = Arc::clone(&plugin);
-        let update_fn = Box::new(move |state: &mut dyn Any, message: AnyMessage| {
-            if let Some(msg) = message.downcast_ref::<P::Message>()
-                && let Some(typed_state) = state.downcast_mut::<P::State>()
-            {
-                let (task, output) = plugin_for_update.update(typed_state, msg.clone());
-                let task = task.map(move |plugin_msg| PluginMessage::new(plugin_index, plugin_msg));
+        let update_fn = Box::new(
+            move |state: &mut dyn Any, message: AnyMessage, ctx: &mut PluginContext<'_>| {
+                let Some(typed_state) = state.downcast_mut::<P::State>() else {
+                    return (Task::none(), None);
+                };
+                let (task, output) = if let Some(msg) = message.downcast_ref::<P::Message>() {
+                    plugin_for_update.update(typed_state, msg.clone(), ctx)
+                } else if let Some(event) = message.downcast_ref::<Broadcast>() {
+                    (
+                        plugin_for_update.on_broadcast(typed_state, event, ctx),
+                        None,
+                    )
+                } else {
+                    return (Task::none(), None);
+                };
+
+                let task = task.map(move |plugin_msg| {
+                    #[cfg(feature = "tracing")]
+                    trace::task_completed(name, &plugin_msg);
+                    PluginMessage::new(plugin_index, plugin_msg)
+                });
                 let plugin_output = output.map(|o| PluginOutput::new(plugin_index, o));
                 (task, plugin_output)
-            } else {
-                (Task::none(), None)
+            },
+        );
+
+        let plugin_for_shutdown = Arc::clone(&plugin);
+        let shutdown_fn = Box::new(move |state: &mut dyn Any, ctx: &mut PluginContext<'_>| {
+            match state.downcast_mut::<P::State>() {
+                Some(typed_state) => {
+                    plugin_for_shutdown
+                        .shutdown(typed_state, ctx)
+                        .map(move |msg| {
+                            #[cfg(feature = "tracing")]
+                            trace::task_completed(name, &msg);
+                            PluginMessage::new(plugin_index, msg)
+                        })
+                }
+                None => Task::none(),
             }
         });
 
         let entry = PluginEntry {
             name,
-            state: Box::new(state),
             plugin_type: TypeId::of::<P>(),
+            key,
+            dependencies,
+            capabilities,
+            granted: BTreeSet::new(),
             message_type_id,
             output_type_id,
             plugin: Arc::new(plugin),
             plugin_index,
+            init_fn,
+            init_async_fn,
             update_fn,
+            shutdown_fn,
             subscription_fn: plugin_subscription_fn::<P>,
+            view_fn: plugin_view_fn::<P>,
+            state_debug_fn: |state| match state.downcast_ref::<P::State>() {
+                Some(state) => format!("{:#?}", state),
+                None => String::new(),
+            },
         };
 
+        self.registry.emit(ManagerEvent::PluginInstalled {
+            plugin: entry.name,
+            key: entry.key.clone(),
+        });
         self.plugins.push(entry);
-        let handle = PluginHandle::new(plugin_index, Arc::clone(&self.output_registry));
-        (
-            handle,
-            init_task.map(move |msg| PluginMessage::new(plugin_index, msg)),
-        )
+        self.registry.add_plugin();
+        PluginHandle::new(plugin_index, Arc::clone(&self.registry))
+    }
+
+    /// Initialize all registered plugins, dependencies first.
+    /// Returns the batched init tasks of all plugins.
+    fn initialize(&mut self) -> Result<Task<PluginMessage>, BuildError> {
+        let nodes: Vec<Node<'_>> = self
+            .plugins
+            .iter()
+            .map(|entry| Node {
+                name: entry.name,
+                plugin_type: entry.plugin_type,
+                key: entry.key.as_deref(),
+                dependencies: &entry.dependencies,
+            })
+            .collect();
+        let order = dependency::init_order(&nodes)?;
+
+        let mut states: Vec<Option<AnyState>> = self.plugins.iter().map(|_| None).collect();
+        let mut tasks = Vec::with_capacity(order.len());
+
+        for &plugin_index in &order {
+            let mut ctx = PluginContext::new(&self.plugins, &self.registry, plugin_index);
+            #[cfg(feature = "config")]
+            {
+                ctx.config = self
+                    .configs
+                    .get(&plugin_index)
+                    .map(|loaded| loaded.config.as_ref());
+            }
+            let entry = &self.plugins[plugin_index];
+            #[cfg(feature = "tracing")]
+            let _span = tracing::info_span!(
+                "plugin_init",
+                plugin = entry.name,
+                key = entry.key.as_deref()
+            )
+            .entered();
+            // A plugin that panics is faulted, along with what it dispatched so far
+            let result = panic::catch_unwind(AssertUnwindSafe(|| (entry.init_fn)(&mut ctx)));
+            let (state, task) = match ctx.outcome(result) {
+                Ok(init) => init,
+                Err(reason) => {
+                    fault(&self.registry, entry, reason);
+                    continue;
+                }
+            };
+            states[plugin_index] = Some(state);
+            self.registry.emit(ManagerEvent::PluginInitialized {
+                plugin: entry.name,
+                key: entry.key.clone(),
+            });
+            let result = panic::catch_unwind(AssertUnwindSafe(|| (entry.init_async_fn)(&mut ctx)));
+            match ctx.outcome(result) {
+                Ok(Some(load_task)) => {
+                    self.loading.insert(plugin_index, Vec::new());
+                    tasks.push(load_task);
+                }
+                Ok(None) => self.registry.emit(ManagerEvent::PluginReady {
+                    plugin: entry.name,
+                    key: entry.key.clone(),
+                }),
+                Err(reason) => {
+                    fault(&self.registry, entry, reason);
+                    continue;
+                }
+            }
+            tasks.push(task);
+            // Not every recipient is initialized yet, so deliver these once the app runs
+            tasks.extend(ctx.messages.into_iter().map(Task::done));
+            tasks.extend(ctx.tasks);
+        }
+
+        self.states = states;
+        self.init_order = order;
+        Ok(Task::batch(tasks))
     }
 
     /// Update the plugin manager with a plugin message.
     /// This automatically routes the message to the correct plugin and distributes outputs to subscribers.
+    /// Messages the plugin dispatched to other plugins through its [`PluginContext`]
+    /// are routed right after.
+    ///
+    /// Messages produced by the plugin's own task keep the correlation id of the handled
+    /// message, so an output emitted later still resolves the [`PluginHandle::call`]
+    /// that caused it.
+    ///
+    /// The message and the output it causes pass through the registered
+    /// [`Interceptor`]s first. Messages to a plugin that is not ready yet are queued
+    /// until its [`Plugin::init_async`] future completes.
     ///
     /// # Example
     /// ```ignore
@@ -422,30 +1450,526 @@ impl PluginManager {
     /// }
     /// ```
     pub fn update(&mut self, message: PluginMessage) -> Task<PluginMessage> {
-        let plugin_index = message.plugin_index;
+        if message.type_id == TypeId::of::<ToggleInspector>() {
+            self.toggle_inspector();
+            return Task::none();
+        }
+        if message.type_id == TypeId::of::<Loaded>() {
+            return self.finish_loading(message);
+        }
+        #[cfg(feature = "config")]
+        if message.type_id == TypeId::of::<ReloadConfig>() {
+            return self.reload_config().unwrap_or_else(|error| {
+                self.registry.emit(ManagerEvent::ConfigReloadFailed(error));
+                Task::none()
+            });
+        }
+
+        #[cfg(feature = "replay")]
+        self.record_message(&message);
+
+        self.route(message)
+    }
+
+    /// Deliver a message to its plugin, along with the messages it dispatches
+    ///
+    /// Dispatched messages are delivered depth first, right after the update that
+    /// dispatched them, from a work list rather than through recursion, so plugins
+    /// dispatching to each other can't overflow the stack.
+    fn route(&mut self, message: PluginMessage) -> Task<PluginMessage> {
+        let mut tasks = Vec::new();
+        let mut pending = vec![(message, 0)];
+
+        while let Some((message, depth)) = pending.pop() {
+            if depth > MAX_DISPATCH_DEPTH {
+                let plugin = self
+                    .plugins
+                    .get(message.plugin_index)
+                    .map(|entry| entry.name);
+                tasks.push(self.drop_message(plugin, message, DropReason::DispatchDepthExceeded));
+                continue;
+            }
+
+            let (task, dispatched) = self.deliver(message);
+            tasks.push(task);
+            pending.extend(
+                dispatched
+                    .into_iter()
+                    .rev()
+                    .map(|message| (message, depth + 1)),
+            );
+        }
+
+        Task::batch(tasks)
+    }
 
-        if let Some(entry) = self.plugins.get_mut(plugin_index)
-            && entry.message_type_id == message.type_id
+    /// Deliver a message to its plugin, returning the task of the plugin and the
+    /// messages it dispatched
+    fn deliver(&mut self, message: PluginMessage) -> (Task<PluginMessage>, Vec<PluginMessage>) {
+        if let Some(queued) = self.loading.get_mut(&message.plugin_index) {
+            queued.push(message);
+            return (Task::none(), Vec::new());
+        }
+        let Some(entry) = self.plugins.get(message.plugin_index) else {
+            let task = self.drop_message(None, message, DropReason::UnknownPlugin);
+            return (task, Vec::new());
+        };
+        let Some(message) =
+            interceptor::intercept_message(&mut self.interceptors, entry.name, message.clone())
+        else {
+            let task = self.drop_message(Some(entry.name), message, DropReason::Intercepted);
+            return (task, Vec::new());
+        };
+
+        // Interceptors may have pointed the message at another plugin
+        let plugin_index = message.plugin_index;
+        let correlation_id = message.correlation_id;
+        if let Some(queued) = self.loading.get_mut(&plugin_index) {
+            queued.push(message);
+            return (Task::none(), Vec::new());
+        }
+        let Some(entry) = self.plugins.get(plugin_index) else {
+            let task = self.drop_message(None, message, DropReason::UnknownPlugin);
+            return (task, Vec::new());
+        };
+        let status = self.registry.status(plugin_index);
+        if !status.is_enabled() {
+            let reason = DropReason::NotRunning(status);
+            return (
+                self.drop_message(Some(entry.name), message, reason),
+                Vec::new(),
+            );
+        }
+        if entry.message_type_id != message.type_id && message.type_id != TypeId::of::<Broadcast>()
         {
-            let (task, output) =
-                (entry.update_fn)(entry.state.as_mut(), Arc::clone(&message.message));
+            let task = self.drop_message(Some(entry.name), message, DropReason::TypeMismatch);
+            return (task, Vec::new());
+        }
+
+        if let Some(state) = self.states.get_mut(plugin_index).and_then(Option::as_mut) {
+            #[cfg(feature = "tracing")]
+            let _span = tracing::debug_span!(
+                "plugin_update",
+                plugin = entry.name,
+                key = entry.key.as_deref(),
+                message = %trace::variant(&message.payload())
+            )
+            .entered();
+
+            if let Some(inspector) = &mut self.inspector {
+                inspector.record_message(entry.name, &message);
+            }
 
-            if let Some(output) = output
-                && let Ok(mut registry) = self.output_registry.lock()
-                && let Some(senders) = registry.get_mut(&plugin_index)
+            let mut ctx = PluginContext::new(&self.plugins, &self.registry, plugin_index);
+            #[cfg(feature = "config")]
             {
-                senders.retain(|sender| sender.unbounded_send(output.clone()).is_ok());
+                ctx.config = self
+                    .configs
+                    .get(&plugin_index)
+                    .map(|loaded| loaded.config.as_ref());
+            }
+            let result = panic::catch_unwind(AssertUnwindSafe(|| {
+                (entry.update_fn)(state.as_mut(), Arc::clone(&message.message), &mut ctx)
+            }));
+            let (task, output) = match ctx.outcome(result) {
+                Ok(result) => result,
+                Err(reason) => {
+                    // Inputs dispatched before the fault are dropped along with its task
+                    fault(&self.registry, entry, reason);
+                    if let Some(correlation_id) = correlation_id {
+                        self.registry.cancel_call(correlation_id);
+                    }
+                    return (Task::none(), Vec::new());
+                }
+            };
+            let PluginContext {
+                messages,
+                mut tasks,
+                ..
+            } = ctx;
+
+            let output = output.and_then(|output| {
+                interceptor::intercept_output(&mut self.interceptors, entry.name, output)
+            });
+            if let Some(output) = output {
+                if let Some(inspector) = &mut self.inspector {
+                    inspector.record_output(entry.name, &output);
+                }
+                if let Some(correlation_id) = correlation_id {
+                    self.registry.complete_call(correlation_id, &output);
+                }
+                self.registry.publish(&output);
+                self.registry.emit(ManagerEvent::OutputEmitted {
+                    plugin: entry.name,
+                    key: entry.key.clone(),
+                    output,
+                });
             }
 
-            task
+            match correlation_id {
+                Some(correlation_id) => {
+                    tasks.push(task.map(move |msg| msg.with_correlation_id(correlation_id)))
+                }
+                None => tasks.push(task),
+            }
+            (Task::batch(tasks), messages)
         } else {
-            Task::none()
+            // Uninstalled plugins have no state
+            let reason = DropReason::NotRunning(PluginStatus::Uninstalled);
+            (
+                self.drop_message(Some(entry.name), message, reason),
+                Vec::new(),
+            )
+        }
+    }
+
+    /// Send an app-defined event to every plugin, e.g. when the app goes to the background
+    ///
+    /// Enabled plugins receive it through [`Plugin::on_broadcast`], dependencies first.
+    /// Plugins that are not ready yet receive it once they are. Disabled, faulted and
+    /// uninstalled plugins don't receive it. The event passes through the registered
+    /// [`Interceptor`]s like any message.
+    ///
+    /// Returns the batched tasks of all plugins.
+    ///
+    /// # Example
+    /// ```ignore
+    /// Message::ThemeChanged(theme) => self
+    ///     .plugins
+    ///     .broadcast(AppEvent::ThemeChanged(theme))
+    ///     .map(Message::Plugin),
+    /// ```
+    pub fn broadcast<E: 'static + Send + Sync + Debug>(&mut self, event: E) -> Task<PluginMessage> {
+        let broadcast = Broadcast::new(event);
+        let recipients: Vec<usize> = self
+            .init_order
+            .iter()
+            .copied()
+            .filter(|&plugin_index| self.registry.status(plugin_index).is_enabled())
+            .collect();
+
+        let mut tasks = Vec::with_capacity(recipients.len());
+        for plugin_index in recipients {
+            tasks.push(self.route(PluginMessage::new(plugin_index, broadcast.clone())));
+        }
+        Task::batch(tasks)
+    }
+
+    /// Swap in the state produced by a plugin's [`Plugin::init_async`] future, then
+    /// deliver the messages queued while it was loading
+    ///
+    /// If the future panicked, the plugin is faulted with the state from
+    /// [`Plugin::init`] and the queued messages are dropped.
+    fn finish_loading(&mut self, message: PluginMessage) -> Task<PluginMessage> {
+        let plugin_index = message.plugin_index;
+        // Gone if the plugin was uninstalled in the meantime
+        let Some(queued) = self.loading.remove(&plugin_index) else {
+            return Task::none();
+        };
+
+        let entry = &self.plugins[plugin_index];
+        let loaded = message
+            .downcast::<Loaded>()
+            .and_then(|loaded| loaded.0.lock().ok()?.take());
+        match loaded {
+            Some(Ok(state)) => self.states[plugin_index] = Some(state),
+            Some(Err(reason)) => {
+                fault(&self.registry, entry, reason);
+                let reason = DropReason::NotRunning(self.registry.status(plugin_index));
+                for message in queued {
+                    let _ = self.drop_message(Some(entry.name), message, reason.clone());
+                }
+                return Task::none();
+            }
+            None => {}
+        }
+
+        self.registry.emit(ManagerEvent::PluginReady {
+            plugin: entry.name,
+            key: entry.key.clone(),
+        });
+
+        let tasks: Vec<_> = queued
+            .into_iter()
+            .map(|message| self.route(message))
+            .collect();
+        Task::batch(tasks)
+    }
+
+    /// Report a message that is never handled
+    fn drop_message(
+        &self,
+        plugin: Option<&'static str>,
+        message: PluginMessage,
+        reason: DropReason,
+    ) -> Task<PluginMessage> {
+        report_drop(&self.registry, plugin, message, reason);
+        Task::none()
+    }
+
+    /// Shut down all plugins in reverse dependency order
+    ///
+    /// Runs the [`Plugin::shutdown`] hook of every enabled plugin, after the hooks of the
+    /// plugins depending on it. Inputs a hook dispatches through its [`PluginContext`]
+    /// are routed before the next hook runs, while their recipient is still running.
+    ///
+    /// Each hook runs at most once, so calling this again does nothing. Disabled and
+    /// faulted plugins are skipped, since the state of a faulted plugin may have been
+    /// left half-updated by a panic.
+    ///
+    /// The returned task completes once the tasks of all hooks have finished, so the
+    /// application can exit right after it.
+    ///
+    /// # Example
+    /// ```ignore
+    /// match message {
+    ///     Message::CloseRequested(id) => {
+    ///         return self
+    ///             .plugins
+    ///             .shutdown()
+    ///             .map(Message::Plugin)
+    ///             .chain(window::close(id));
+    ///     }
+    ///     // ... other messages
+    /// }
+    /// ```
+    pub fn shutdown(&mut self) -> Task<PluginMessage> {
+        let mut tasks = Vec::new();
+
+        for plugin_index in self.init_order.clone().into_iter().rev() {
+            tasks.push(self.shutdown_plugin(plugin_index));
+        }
+
+        Task::batch(tasks)
+    }
+
+    /// Run the shutdown hook of a single plugin, if it is enabled and the hook didn't
+    /// run yet
+    fn shutdown_plugin(&mut self, plugin_index: usize) -> Task<PluginMessage> {
+        if !self.registry.status(plugin_index).is_enabled() {
+            return Task::none();
+        }
+        let entry = &self.plugins[plugin_index];
+        let Some(state) = self.states[plugin_index].as_mut() else {
+            return Task::none();
+        };
+        if !self.shut_down.insert(plugin_index) {
+            return Task::none();
+        }
+
+        let mut ctx = PluginContext::new(&self.plugins, &self.registry, plugin_index);
+        #[cfg(feature = "config")]
+        {
+            ctx.config = self
+                .configs
+                .get(&plugin_index)
+                .map(|loaded| loaded.config.as_ref());
+        }
+        let task = panic::catch_unwind(AssertUnwindSafe(|| {
+            (entry.shutdown_fn)(state.as_mut(), &mut ctx)
+        }));
+        let mut tasks = match ctx.outcome(task) {
+            Ok(task) => vec![task],
+            Err(reason) => {
+                fault(&self.registry, entry, reason);
+                return Task::none();
+            }
+        };
+
+        let PluginContext {
+            messages,
+            tasks: emitted,
+            ..
+        } = ctx;
+        tasks.extend(emitted);
+        for message in messages {
+            tasks.push(self.route(message));
+        }
+
+        Task::batch(tasks)
+    }
+
+    /// Disable a plugin at runtime
+    ///
+    /// The plugin keeps its state, but its subscription is dropped and messages sent
+    /// to it are ignored until it is enabled again. Pending calls to it fail with
+    /// [`CallError::Unavailable`].
+    ///
+    /// # Errors
+    ///
+    /// Fails if the plugin was uninstalled, or if an enabled plugin depends on it.
+    ///
+    /// # Example
+    /// ```ignore
+    /// Message::TrayToggled(false) => {
+    ///     if let Err(error) = self.plugins.disable(&self.tray_handle) {
+    ///         self.status = error.to_string();
+    ///     }
+    /// }
+    /// ```
+    pub fn disable<P: Plugin + 'static>(
+        &mut self,
+        handle: &PluginHandle<P>,
+    ) -> Result<(), LifecycleError> {
+        let plugin_index = handle.plugin_index;
+        self.check_installed(plugin_index)?;
+        self.check_not_required(plugin_index)?;
+        self.set_status(plugin_index, PluginStatus::Disabled);
+        Ok(())
+    }
+
+    /// Enable a plugin that was disabled with [`PluginManager::disable`], or faulted
+    ///
+    /// A faulted plugin keeps the state it had when it panicked.
+    ///
+    /// Enabling a plugin that is already enabled does nothing.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the plugin was uninstalled, if it panicked in [`Plugin::init`] and has
+    /// no state, or if a plugin it depends on is not enabled.
+    pub fn enable<P: Plugin + 'static>(
+        &mut self,
+        handle: &PluginHandle<P>,
+    ) -> Result<(), LifecycleError> {
+        let plugin_index = handle.plugin_index;
+        self.check_installed(plugin_index)?;
+
+        let entry = &self.plugins[plugin_index];
+        if self.states[plugin_index].is_none() {
+            return Err(LifecycleError::NotInitialized { plugin: entry.name });
+        }
+        for dependency in &entry.dependencies {
+            let available = self.plugins.iter().any(|other| {
+                dependency.matches(other.plugin_type, other.key.as_deref())
+                    && self.registry.status(other.plugin_index).is_enabled()
+            });
+            if !available {
+                return Err(LifecycleError::DependencyUnavailable {
+                    plugin: entry.name,
+                    dependency: dependency.name(),
+                });
+            }
+        }
+
+        self.set_status(plugin_index, PluginStatus::Enabled);
+        Ok(())
+    }
+
+    /// Uninstall a plugin at runtime
+    ///
+    /// Runs the plugin's [`Plugin::shutdown`] hook if it is enabled, then drops its
+    /// state and ends the subscriptions listening to its outputs. Handles to the plugin
+    /// become stale: [`PluginHandle::is_installed`] returns false and calls fail with
+    /// [`CallError::Unavailable`]. Returns the task of the shutdown hook.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the plugin was already uninstalled, or if an enabled plugin depends on it.
+    ///
+    /// # Example
+    /// ```ignore
+    /// Message::AutoUpdatesToggled(false) => self
+    ///     .plugins
+    ///     .uninstall(&self.updater_handle)
+    ///     .map(|task| task.map(Message::Plugin))
+    ///     .unwrap_or_else(|_| Task::none()),
+    /// ```
+    pub fn uninstall<P: Plugin + 'static>(
+        &mut self,
+        handle: &PluginHandle<P>,
+    ) -> Result<Task<PluginMessage>, LifecycleError> {
+        let plugin_index = handle.plugin_index;
+        self.check_installed(plugin_index)?;
+        self.check_not_required(plugin_index)?;
+
+        let task = self.shutdown_plugin(plugin_index);
+        self.set_status(plugin_index, PluginStatus::Uninstalled);
+        self.states[plugin_index] = None;
+        self.registry.remove_listeners(plugin_index);
+
+        // Messages queued while it was loading never reach it
+        if let Some(queued) = self.loading.remove(&plugin_index) {
+            let name = self.plugins[plugin_index].name;
+            let reason = DropReason::NotRunning(PluginStatus::Uninstalled);
+            for message in queued {
+                let _ = self.drop_message(Some(name), message, reason.clone());
+            }
+        }
+        Ok(task)
+    }
+
+    /// Get the status of a plugin
+    pub fn status<P: Plugin + 'static>(&self, handle: &PluginHandle<P>) -> PluginStatus {
+        self.registry.status(handle.plugin_index)
+    }
+
+    /// Whether the plugin of type `P` installed without a key is ready
+    ///
+    /// Plugins are ready right after `init`, or once their [`Plugin::init_async`]
+    /// future completed. [`ManagerEvent::PluginReady`] reports when that happens.
+    pub fn is_ready<P: Plugin + 'static>(&self) -> bool {
+        self.find_ready::<P>(None)
+    }
+
+    /// Whether the plugin of type `P` installed with `key` is ready
+    ///
+    /// See [`PluginManager::is_ready`].
+    pub fn is_ready_keyed<P: Plugin + 'static>(&self, key: &str) -> bool {
+        self.find_ready::<P>(Some(key))
+    }
+
+    fn find_ready<P: Plugin + 'static>(&self, key: Option<&str>) -> bool {
+        find_plugin::<P>(&self.plugins, &self.registry, key)
+            .is_some_and(|index| !self.loading.contains_key(&index))
+    }
+
+    fn set_status(&self, plugin_index: usize, status: PluginStatus) {
+        self.registry.set_status(plugin_index, status.clone());
+        let entry = &self.plugins[plugin_index];
+        self.registry.emit(ManagerEvent::PluginStatusChanged {
+            plugin: entry.name,
+            key: entry.key.clone(),
+            status,
+        });
+    }
+
+    fn check_installed(&self, plugin_index: usize) -> Result<(), LifecycleError> {
+        match self.registry.status(plugin_index) {
+            PluginStatus::Uninstalled => Err(LifecycleError::Uninstalled {
+                plugin: self.plugins.get(plugin_index).map_or("unknown", |p| p.name),
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    /// Fail if an enabled plugin depends on this one
+    fn check_not_required(&self, plugin_index: usize) -> Result<(), LifecycleError> {
+        let entry = &self.plugins[plugin_index];
+        let dependent = self.plugins.iter().find(|other| {
+            other.plugin_index != plugin_index
+                && self.registry.status(other.plugin_index).is_enabled()
+                && other
+                    .dependencies
+                    .iter()
+                    .any(|dependency| dependency.matches(entry.plugin_type, entry.key.as_deref()))
+        });
+        match dependent {
+            Some(dependent) => Err(LifecycleError::RequiredBy {
+                plugin: entry.name,
+                dependent: dependent.name,
+            }),
+            None => Ok(()),
         }
     }
 
     /// Collect all subscriptions from installed plugins
     /// Call this from your application's subscription method
     ///
+    /// This also delivers the messages sent by [`PluginHandle::call`], and in debug
+    /// builds toggles the inspector on F12 if the manager has one.
+    /// Disabled and uninstalled plugins don't contribute a subscription.
+    ///
     /// # Example
     /// ```ignore
     /// fn subscription(&self) -> Subscription<Message> {
@@ -453,52 +1977,666 @@ impl PluginManager {
     /// }
     /// ```
     pub fn subscriptions(&self) -> Subscription<PluginMessage> {
-        let subs: Vec<Subscription<PluginMessage>> = self
+        let plugin_subs: Vec<(usize, Subscription<PluginMessage>)> = self
             .plugins
             .iter()
-            .map(|entry| {
-                (entry.subscription_fn)(
-                    entry.state.as_ref(),
-                    entry.plugin.as_ref(),
-                    entry.plugin_index,
-                )
+            .zip(&self.states)
+            .filter(|(entry, _)| self.registry.status(entry.plugin_index).is_enabled())
+            .filter_map(|(entry, state)| {
+                let subscription = self.subscription_of(entry, state.as_ref()?)?;
+                Some((entry.plugin_index, subscription))
             })
             .collect();
+        if let Some(inspector) = &self.inspector {
+            inspector.record_subscriptions(
+                plugin_subs
+                    .iter()
+                    .map(|(plugin_index, subscription)| (*plugin_index, subscription.units()))
+                    .collect(),
+            );
+        }
+
+        let mut subs: Vec<Subscription<PluginMessage>> = plugin_subs
+            .into_iter()
+            .map(|(_, subscription)| subscription)
+            .collect();
+        subs.push(self.registry.inbox());
+        #[cfg(feature = "config")]
+        if let Some(source) = &self.config_source
+            && source.is_watched()
+        {
+            subs.push(config::watch(source));
+        }
+        #[cfg(debug_assertions)]
+        if self.inspector.is_some() {
+            subs.push(inspector::hotkey());
+        }
 
         Subscription::batch(subs)
     }
+
+    /// Subscribe to the faults of plugins that panicked
+    ///
+    /// A panic in any hook of a plugin, from `init` and its `init_async` future to
+    /// `update`, `subscription`, `view` and `shutdown`, doesn't take down the
+    /// application. The manager catches it, marks the plugin as
+    /// [`PluginStatus::Faulted`] and keeps the other plugins running. Plugins fault
+    /// themselves without panicking through [`PluginContext::fault`].
+    ///
+    /// Faults that happen while nobody listens are kept for the first listener, up to
+    /// [`MANAGER_EVENT_BACKLOG`](crate::MANAGER_EVENT_BACKLOG) of them, independently of
+    /// the backlog of [`PluginManager::events`].
+    ///
+    /// # Example
+    /// ```ignore
+    /// fn subscription(&self) -> Subscription<Message> {
+    ///     Subscription::batch([
+    ///         self.plugins.subscriptions().map(Message::Plugin),
+    ///         self.plugins.faults().map(Message::PluginFaulted),
+    ///     ])
+    /// }
+    /// ```
+    pub fn faults(&self) -> Subscription<PluginFault> {
+        self.registry.faults()
+    }
+
+    /// Subscribe to lifecycle and routing events, e.g. to surface wiring bugs
+    ///
+    /// Events emitted while nobody listens, such as [`ManagerEvent::PluginInstalled`]
+    /// while building the manager, are kept and delivered to the first listener, up to
+    /// [`MANAGER_EVENT_BACKLOG`](crate::MANAGER_EVENT_BACKLOG) of them.
+    /// [`ManagerEvent::OutputEmitted`] is only sent to current listeners, so frequent
+    /// outputs don't push the other events out of the backlog.
+    ///
+    /// # Example
+    /// ```ignore
+    /// fn subscription(&self) -> Subscription<Message> {
+    ///     Subscription::batch([
+    ///         self.plugins.subscriptions().map(Message::Plugin),
+    ///         self.plugins.events().map(Message::PluginEvent),
+    ///     ])
+    /// }
+    ///
+    /// // In update
+    /// Message::PluginEvent(ManagerEvent::MessageDropped { plugin, reason, .. }) => {
+    ///     eprintln!("message to {:?} dropped: {}", plugin, reason);
+    /// }
+    /// ```
+    pub fn events(&self) -> Subscription<ManagerEvent> {
+        self.registry.events()
+    }
+
+    /// Get the subscription of a plugin, marking the plugin as faulted if it panics
+    fn subscription_of(
+        &self,
+        entry: &PluginEntry,
+        state: &AnyState,
+    ) -> Option<Subscription<PluginMessage>> {
+        panic::catch_unwind(AssertUnwindSafe(|| {
+            (entry.subscription_fn)(state.as_ref(), entry.plugin.as_ref(), entry.plugin_index)
+        }))
+        .map_err(|panic| fault(&self.registry, entry, panic_reason(panic.as_ref())))
+        .ok()
+    }
+}
+
+/// Get the message of a caught panic
+fn panic_reason(panic: &(dyn Any + Send)) -> String {
+    if let Some(reason) = panic.downcast_ref::<&str>() {
+        reason.to_string()
+    } else if let Some(reason) = panic.downcast_ref::<String>() {
+        reason.clone()
+    } else {
+        "unknown panic".to_string()
+    }
+}
+
+/// Mark a plugin that panicked or faulted itself as faulted and report it to fault
+/// listeners
+fn fault(registry: &Registry, entry: &PluginEntry, reason: String) {
+    #[cfg(feature = "tracing")]
+    tracing::error!(plugin = entry.name, key = entry.key.as_deref(), %reason, "plugin faulted");
+
+    registry.set_status(
+        entry.plugin_index,
+        PluginStatus::Faulted {
+            reason: reason.clone(),
+        },
+    );
+    registry.emit(ManagerEvent::PluginFaulted(PluginFault {
+        plugin: entry.name,
+        key: entry.key.clone(),
+        reason,
+    }));
 }
 
 // Methods available for all PluginManager instances
 impl PluginManager {
-    /// Get the number of installed plugins
+    /// Get the number of installed plugins, including disabled ones
     pub fn plugin_count(&self) -> usize {
-        self.plugins.len()
+        self.installed().count()
     }
 
-    /// Get a list of all installed plugin names in order
+    /// Get a list of all installed plugin names in order, including disabled ones
     pub fn plugin_names(&self) -> Vec<&'static str> {
-        self.plugins.iter().map(|p| p.name).collect()
+        self.installed().map(|p| p.name).collect()
     }
 
-    pub fn get_plugin_state<P: Plugin + 'static>(&self) -> Option<&P::State> {
+    /// Get output listener counts and dropped outputs of all installed plugins, in order
+    ///
+    /// Outputs are only dropped by listeners with a bounded [`OutputChannel`].
+    pub fn output_metrics(&self) -> Vec<OutputMetrics> {
+        self.installed()
+            .map(|entry| OutputMetrics {
+                plugin: entry.name,
+                key: entry.key.clone(),
+                listeners: self.registry.listener_count(entry.plugin_index),
+                dropped: self.registry.dropped_outputs(entry.plugin_index),
+            })
+            .collect()
+    }
+
+    /// Get the number of outputs of a plugin dropped by its bounded listeners so far
+    pub fn dropped_outputs<P: Plugin + 'static>(&self, handle: &PluginHandle<P>) -> u64 {
+        self.registry.dropped_outputs(handle.plugin_index)
+    }
+
+    /// Names of the installed plugins matching `filter` whose
+    /// [`Plugin::init_async`] future hasn't completed, in install order
+    #[cfg(any(feature = "replay", feature = "snapshot"))]
+    fn loading_plugins(&self, filter: impl Fn(&PluginEntry) -> bool) -> Vec<&'static str> {
+        self.installed()
+            .filter(|entry| self.loading.contains_key(&entry.plugin_index) && filter(entry))
+            .map(|entry| entry.name)
+            .collect()
+    }
+
+    fn installed(&self) -> impl Iterator<Item = &PluginEntry> {
         self.plugins
             .iter()
-            .find(|p| TypeId::of::<P>() == p.plugin_type)
-            .map(|p| p.state.as_ref())
-            .and_then(|state| state.downcast_ref::<P::State>())
+            .filter(|p| self.registry.status(p.plugin_index) != PluginStatus::Uninstalled)
+    }
+
+    #[cfg(feature = "testing")]
+    pub(crate) fn registry(&self) -> &SharedRegistry {
+        &self.registry
+    }
+
+    /// Start recording the messages passed to [`PluginManager::update`] as JSON lines
+    ///
+    /// Only messages of plugin types registered with [`PluginManagerBuilder::with_replay`]
+    /// are recorded. Messages plugins dispatch to each other through their
+    /// [`PluginContext`] are not, since replaying the message that caused them
+    /// dispatches them again. Recording stops if writing fails.
+    #[cfg(feature = "replay")]
+    pub fn record(&mut self, writer: impl std::io::Write + Send + 'static) {
+        self.recorder = Some(Recorder::new(writer));
+    }
+
+    /// Start recording to a file, replacing it if it exists
+    ///
+    /// See [`PluginManager::record`].
+    ///
+    /// # Example
+    /// ```ignore
+    /// let (mut plugins, init_task) = PluginManagerBuilder::new()
+    ///     .with_plugin(CounterPlugin)
+    ///     .with_replay::<CounterPlugin>()
+    ///     .build()?;
+    /// plugins.record_to_file("session.jsonl")?;
+    /// ```
+    #[cfg(feature = "replay")]
+    pub fn record_to_file(&mut self, path: impl AsRef<std::path::Path>) -> std::io::Result<()> {
+        let file = std::fs::File::create(path)?;
+        self.record(std::io::BufWriter::new(file));
+        Ok(())
+    }
+
+    /// Stop recording
+    #[cfg(feature = "replay")]
+    pub fn stop_recording(&mut self) {
+        self.recorder = None;
+    }
+
+    /// Whether messages are being recorded
+    #[cfg(feature = "replay")]
+    pub fn is_recording(&self) -> bool {
+        self.recorder.is_some()
+    }
+
+    #[cfg(feature = "replay")]
+    fn record_message(&mut self, message: &PluginMessage) {
+        let Some(recorder) = &mut self.recorder else {
+            return;
+        };
+        let Some(entry) = self.plugins.get(message.plugin_index) else {
+            return;
+        };
+        let Some(Ok(value)) = self
+            .codecs
+            .get(&entry.plugin_type)
+            .and_then(|codec| codec.encode(message))
+        else {
+            return;
+        };
+
+        let record = Record {
+            plugin: entry.name.to_string(),
+            key: entry.key.clone(),
+            message: value,
+        };
+        if recorder.write(&record).is_err() {
+            self.recorder = None;
+        }
+    }
+
+    /// Replay recorded messages, as written by [`PluginManager::record`]
+    ///
+    /// Every message is passed to [`PluginManager::update`] in order. The tasks it
+    /// returns are dropped rather than run, because the messages they produced were
+    /// recorded as well. This needs no window or runtime, so recordings can be
+    /// replayed in tests. Don't run the init task of the manager either, for the same
+    /// reason.
+    ///
+    /// Since the init task is not run, plugins with a [`Plugin::init_async`] future
+    /// never become ready, and messages to them would be queued forever. Replaying
+    /// fails up front while any plugin is loading.
+    ///
+    /// Returns the number of replayed messages.
+    ///
+    /// # Errors
+    ///
+    /// Fails with [`ReplayError::NotReady`] while plugins are loading. Otherwise fails
+    /// on the first line that can't be read, or that targets a plugin which is not
+    /// installed or not registered with [`PluginManagerBuilder::with_replay`]. The
+    /// messages before it stay applied.
+    ///
+    /// # Example
+    /// ```ignore
+    /// #[test]
+    /// fn reproduces_bug_report() {
+    ///     let (mut plugins, _) = PluginManagerBuilder::new()
+    ///         .with_plugin(CounterPlugin)
+    ///         .with_replay::<CounterPlugin>()
+    ///         .build()
+    ///         .unwrap();
+    ///
+    ///     plugins.replay(include_bytes!("session.jsonl").as_slice()).unwrap();
+    ///     assert_eq!(plugins.get_plugin_state::<CounterPlugin>().unwrap().value, 3);
+    /// }
+    /// ```
+    #[cfg(feature = "replay")]
+    pub fn replay(&mut self, reader: impl std::io::BufRead) -> Result<usize, ReplayError> {
+        let loading = self.loading_plugins(|_| true);
+        if !loading.is_empty() {
+            return Err(ReplayError::NotReady { plugins: loading });
+        }
+
+        let mut replayed = 0;
+
+        for (index, line) in reader.lines().enumerate() {
+            let line_number = index + 1;
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let record: Record =
+                serde_json::from_str(&line).map_err(|error| ReplayError::Parse {
+                    line: line_number,
+                    message: error.to_string(),
+                })?;
+
+            let entry = self
+                .installed()
+                .find(|entry| entry.name == record.plugin && entry.key == record.key)
+                .ok_or_else(|| ReplayError::UnknownPlugin {
+                    line: line_number,
+                    plugin: record.plugin.clone(),
+                    key: record.key.clone(),
+                })?;
+            let codec =
+                self.codecs
+                    .get(&entry.plugin_type)
+                    .ok_or_else(|| ReplayError::NotReplayable {
+                        line: line_number,
+                        plugin: record.plugin.clone(),
+                    })?;
+            let message = codec
+                .decode(entry.plugin_index, record.message)
+                .map_err(|error| ReplayError::Parse {
+                    line: line_number,
+                    message: error.to_string(),
+                })?;
+
+            let _ = self.update(message);
+            replayed += 1;
+        }
+
+        Ok(replayed)
+    }
+
+    /// Replay recorded messages from a file
+    ///
+    /// See [`PluginManager::replay`].
+    #[cfg(feature = "replay")]
+    pub fn replay_file(&mut self, path: impl AsRef<std::path::Path>) -> Result<usize, ReplayError> {
+        let file = std::fs::File::open(path)?;
+        self.replay(std::io::BufReader::new(file))
+    }
+
+    /// Save the states of the plugins registered with
+    /// [`PluginManagerBuilder::with_snapshot`]
+    ///
+    /// Disabled and faulted plugins are included. Uninstalled plugins, and plugins whose
+    /// [`Plugin::init_async`] future hasn't completed, are not.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the state of a plugin can't be serialized.
+    ///
+    /// # Example
+    /// ```ignore
+    /// // Persist the session with the store plugin
+    /// let snapshot = self.plugins.snapshot()?;
+    /// self.store.dispatch(StoreInput::set("session", "plugins", &snapshot))
+    /// ```
+    #[cfg(feature = "snapshot")]
+    pub fn snapshot(&self) -> Result<Snapshot, SnapshotError> {
+        let mut states = Vec::new();
+
+        for entry in self.installed() {
+            let Some(codec) = self.state_codecs.get(&entry.plugin_type) else {
+                continue;
+            };
+            if self.loading.contains_key(&entry.plugin_index) {
+                continue;
+            }
+            let Some(result) = self.states[entry.plugin_index]
+                .as_ref()
+                .and_then(|state| codec.encode(state.as_ref()))
+            else {
+                continue;
+            };
+
+            let state = result.map_err(|error| SnapshotError::Serialize {
+                plugin: entry.name,
+                key: entry.key.clone(),
+                message: error.to_string(),
+            })?;
+            states.push(SavedState {
+                plugin: entry.name.to_string(),
+                key: entry.key.clone(),
+                state,
+            });
+        }
+
+        Ok(Snapshot { states })
+    }
+
+    /// Bring back the plugin states saved with [`PluginManager::snapshot`]
+    ///
+    /// States are matched to installed plugins by name and instance key, so a snapshot
+    /// taken before plugins were added or removed still applies. Saved states of plugins
+    /// that are no longer installed or registered with
+    /// [`PluginManagerBuilder::with_snapshot`] are skipped, and plugins missing from the
+    /// snapshot keep their state.
+    ///
+    /// Returns the number of restored plugins.
+    ///
+    /// # Errors
+    ///
+    /// Fails if a saved state doesn't fit the state type of its plugin, or with
+    /// [`SnapshotError::NotReady`] if a plugin with a saved state hasn't completed its
+    /// [`Plugin::init_async`] future, whose result would replace the restored state.
+    /// No state is restored then.
+    ///
+    /// # Example
+    /// ```ignore
+    /// let snapshot = read_value::<Snapshot>(&app_name, "session", "plugins").await?;
+    /// plugins.restore(&snapshot)?;
+    /// ```
+    #[cfg(feature = "snapshot")]
+    pub fn restore(&mut self, snapshot: &Snapshot) -> Result<usize, SnapshotError> {
+        let loading = self.loading_plugins(|entry| {
+            self.state_codecs.contains_key(&entry.plugin_type)
+                && snapshot
+                    .states
+                    .iter()
+                    .any(|saved| entry.name == saved.plugin && entry.key == saved.key)
+        });
+        if !loading.is_empty() {
+            return Err(SnapshotError::NotReady { plugins: loading });
+        }
+
+        let mut restored = Vec::new();
+
+        for saved in &snapshot.states {
+            let Some(entry) = self
+                .installed()
+                .find(|entry| entry.name == saved.plugin && entry.key == saved.key)
+            else {
+                continue;
+            };
+            let Some(codec) = self.state_codecs.get(&entry.plugin_type) else {
+                continue;
+            };
+
+            let state =
+                codec
+                    .decode(saved.state.clone())
+                    .map_err(|error| SnapshotError::Deserialize {
+                        plugin: entry.name,
+                        key: entry.key.clone(),
+                        message: error.to_string(),
+                    })?;
+            restored.push((entry.plugin_index, state));
+        }
+
+        let count = restored.len();
+        for (plugin_index, state) in restored {
+            self.states[plugin_index] = Some(state);
+        }
+        Ok(count)
+    }
+
+    /// Load the config file again and deliver the changed configs to their plugins
+    ///
+    /// Plugins whose layered config changed receive the message from
+    /// [`Configurable::config_changed`], the others receive nothing. Runs automatically
+    /// when the file changes if the source is watched, see [`ConfigSource::watch`].
+    ///
+    /// # Errors
+    ///
+    /// Fails if the file can't be read or parsed, or if the config of a plugin doesn't
+    /// fit its config type. All plugins keep their config then.
+    #[cfg(feature = "config")]
+    pub fn reload_config(&mut self) -> Result<Task<PluginMessage>, ConfigError> {
+        let changed = self.load_configs()?;
+
+        let mut tasks = Vec::with_capacity(changed.len());
+        for plugin_index in changed {
+            let entry = &self.plugins[plugin_index];
+            let message = self
+                .config_codecs
+                .get(&entry.plugin_type)
+                .zip(self.configs.get(&plugin_index))
+                .and_then(|(codec, loaded)| codec.changed(plugin_index, loaded.config.as_ref()));
+            if let Some(message) = message {
+                tasks.push(self.route(message));
+            }
+        }
+        Ok(Task::batch(tasks))
+    }
+
+    /// Layer the configs of all configurable plugins.
+    /// Returns the indices of the plugins whose config changed.
+    #[cfg(feature = "config")]
+    fn load_configs(&mut self) -> Result<Vec<usize>, ConfigError> {
+        let file = config::read(self.config_source.as_ref())?;
+
+        let mut loaded = Vec::new();
+        for entry in self.installed() {
+            let Some(codec) = self.config_codecs.get(&entry.plugin_type) else {
+                continue;
+            };
+            let config = codec.load(
+                entry.plugin.as_ref(),
+                entry.name,
+                &file,
+                self.config_source.as_ref(),
+            )?;
+            loaded.push((entry.plugin_index, config));
+        }
+
+        let mut changed = Vec::new();
+        for (plugin_index, config) in loaded {
+            if self
+                .configs
+                .get(&plugin_index)
+                .is_none_or(|current| current.value != config.value)
+            {
+                changed.push(plugin_index);
+            }
+            self.configs.insert(plugin_index, config);
+        }
+        Ok(changed)
+    }
+
+    /// Show or hide the inspector, if the manager was built with
+    /// [`PluginManagerBuilder::with_inspector`]
+    ///
+    /// In debug builds, F12 does the same through [`PluginManager::subscriptions`].
+    pub fn toggle_inspector(&mut self) {
+        if let Some(inspector) = &mut self.inspector {
+            inspector.toggle();
+        }
+    }
+
+    /// Render the inspector while it is shown
+    ///
+    /// The inspector lists the installed plugins with their status, `Debug`-formatted
+    /// state and subscription counts, next to the most recent messages and outputs.
+    /// Returns None if the manager has no inspector or it is hidden.
+    ///
+    /// # Example
+    /// ```ignore
+    /// fn view(&self) -> Element<'_, Message> {
+    ///     let content = self.content();
+    ///     match self.plugins.inspector() {
+    ///         Some(inspector) => stack![content, inspector.map(Message::Plugin)].into(),
+    ///         None => content,
+    ///     }
+    /// }
+    /// ```
+    pub fn inspector(&self) -> Option<Element<'_, PluginMessage>> {
+        let inspector = self.inspector.as_ref().filter(|i| i.is_visible())?;
+
+        let plugins = self
+            .installed()
+            .filter_map(|entry| {
+                let state = self.states[entry.plugin_index].as_ref()?;
+                Some(PluginSummary {
+                    name: entry.name,
+                    key: entry.key.clone(),
+                    status: self.registry.status(entry.plugin_index),
+                    state: (entry.state_debug_fn)(state.as_ref()),
+                    subscriptions: inspector.subscription_count(entry.plugin_index),
+                    listeners: self.registry.listener_count(entry.plugin_index),
+                    dropped: self.registry.dropped_outputs(entry.plugin_index),
+                })
+            })
+            .collect();
+
+        Some(inspector::view(inspector, plugins))
+    }
+
+    /// Render the widget of the plugin of type `P` installed without a key
+    ///
+    /// Interactions with the widget are routed back to the plugin through
+    /// [`PluginManager::update`]. Returns None if the plugin is not installed, is
+    /// disabled, or has no view.
+    ///
+    /// # Example
+    /// ```ignore
+    /// fn view(&self) -> Element<'_, Message> {
+    ///     let mut content = Column::new();
+    ///     if let Some(prompt) = self.plugins.view_of::<AutoUpdaterPlugin>() {
+    ///         content = content.push(prompt.map(Message::Plugin));
+    ///     }
+    ///     content.push(self.main_view()).into()
+    /// }
+    /// ```
+    pub fn view_of<P: Plugin + 'static>(&self) -> Option<Element<'_, PluginMessage>> {
+        self.find_view::<P>(None)
+    }
+
+    /// Render the widget of the plugin of type `P` installed with `key`
+    ///
+    /// See [`PluginManager::view_of`].
+    pub fn view_of_keyed<P: Plugin + 'static>(
+        &self,
+        key: &str,
+    ) -> Option<Element<'_, PluginMessage>> {
+        self.find_view::<P>(Some(key))
+    }
+
+    fn find_view<P: Plugin + 'static>(
+        &self,
+        key: Option<&str>,
+    ) -> Option<Element<'_, PluginMessage>> {
+        let index = find_plugin::<P>(&self.plugins, &self.registry, key)?;
+        if !self.registry.status(index).is_enabled() || self.loading.contains_key(&index) {
+            return None;
+        }
+
+        let entry = &self.plugins[index];
+        let state = self.states.get(index)?.as_ref()?;
+        panic::catch_unwind(AssertUnwindSafe(|| {
+            (entry.view_fn)(state.as_ref(), entry.plugin.as_ref(), index)
+        }))
+        .map_err(|panic| fault(&self.registry, entry, panic_reason(panic.as_ref())))
+        .ok()
+        .flatten()
+    }
+
+    /// Get the state of the plugin of type `P` installed without a key
+    pub fn get_plugin_state<P: Plugin + 'static>(&self) -> Option<&P::State> {
+        self.find_state::<P>(None)
+    }
+
+    /// Get the state of the plugin of type `P` installed with `key`
+    pub fn get_plugin_state_keyed<P: Plugin + 'static>(&self, key: &str) -> Option<&P::State> {
+        self.find_state::<P>(Some(key))
     }
 
     pub fn get_plugin_state_mut<P: Plugin + 'static>(&mut self) -> Option<&mut P::State> {
-        self.plugins
-            .iter_mut()
-            .find(|p| TypeId::of::<P>() == p.plugin_type)
-            .map(|p| p.state.as_mut())
+        self.find_state_mut::<P>(None)
+    }
+
+    pub fn get_plugin_state_keyed_mut<P: Plugin + 'static>(
+        &mut self,
+        key: &str,
+    ) -> Option<&mut P::State> {
+        self.find_state_mut::<P>(Some(key))
+    }
+
+    fn find_state<P: Plugin + 'static>(&self, key: Option<&str>) -> Option<&P::State> {
+        find_plugin::<P>(&self.plugins, &self.registry, key)
+            .and_then(|index| self.states.get(index))
+            .and_then(|state| state.as_ref())
+            .and_then(|state| state.downcast_ref::<P::State>())
+    }
+
+    fn find_state_mut<P: Plugin + 'static>(&mut self, key: Option<&str>) -> Option<&mut P::State> {
+        find_plugin::<P>(&self.plugins, &self.registry, key)
+            .and_then(|index| self.states.get_mut(index))
+            .and_then(|state| state.as_mut())
             .and_then(|state| state.downcast_mut::<P::State>())
     }
 
     /// Get a handle to an installed plugin by its type.
-    /// Returns None if the plugin is not installed.
+    /// Returns None if the plugin is not installed without a key.
     ///
     /// # Example
     /// ```ignore
@@ -509,31 +2647,47 @@ impl PluginManager {
     /// }
     /// ```
     pub fn get_handle<P: Plugin + 'static>(&self) -> Option<PluginHandle<P>> {
-        self.plugins
-            .iter()
-            .find(|p| TypeId::of::<P>() == p.plugin_type)
-            .map(|p| PluginHandle::new(p.plugin_index, Arc::clone(&self.output_registry)))
+        find_plugin::<P>(&self.plugins, &self.registry, None)
+            .map(|index| PluginHandle::new(index, Arc::clone(&self.registry)))
+    }
+
+    /// Get a handle to the instance of a plugin type installed with `key`.
+    /// Returns None if no such instance is installed.
+    ///
+    /// # Example
+    /// ```ignore
+    /// let work_store = manager.get_handle_keyed::<StorePlugin>("work").unwrap();
+    /// ```
+    pub fn get_handle_keyed<P: Plugin + 'static>(&self, key: &str) -> Option<PluginHandle<P>> {
+        find_plugin::<P>(&self.plugins, &self.registry, Some(key))
+            .map(|index| PluginHandle::new(index, Arc::clone(&self.registry)))
     }
 }
 
 /// Builder pattern for constructing a PluginManager
 ///
-/// This is the recommended way to set up plugins. It collects all initialization tasks
-/// and allows you to retrieve plugin handles after building.
+/// This is the recommended way to set up plugins. Plugins are initialized when the
+/// manager is built, after the plugins they declare as dependencies, and all
+/// initialization tasks are collected. Handles can be retrieved after building.
 ///
 /// # Example
 /// ```ignore
 /// let (plugins, init_task) = PluginManagerBuilder::new()
 ///     .with_plugin(CounterPlugin)
 ///     .with_plugin(TimerPlugin)
-///     .build();
+///     .build()?;
 ///
 /// // Retrieve handles after building
 /// let counter_handle = plugins.get_handle::<CounterPlugin>().unwrap();
 /// ```
 pub struct PluginManagerBuilder {
     manager: PluginManager,
-    tasks: Vec<Task<PluginMessage>>,
+    /// Output buffers by plugin type, applied to every instance when building
+    output_buffers: HashMap<TypeId, OutputBuffer>,
+    /// Capabilities granted by plugin type
+    grants: HashMap<TypeId, BTreeSet<Capability>>,
+    /// Capabilities granted by plugin type and instance key
+    keyed_grants: HashMap<(TypeId, String), BTreeSet<Capability>>,
 }
 
 impl PluginManagerBuilder {
@@ -541,7 +2695,9 @@ impl PluginManagerBuilder {
     pub fn new() -> Self {
         Self {
             manager: PluginManager::new(),
-            tasks: Vec::new(),
+            output_buffers: HashMap::new(),
+            grants: HashMap::new(),
+            keyed_grants: HashMap::new(),
         }
     }
 
@@ -550,8 +2706,19 @@ impl PluginManagerBuilder {
     where
         P: Plugin + 'static,
     {
-        let (_, task) = self.manager.install_internal(plugin);
-        self.tasks.push(task);
+        self.manager.register(None, plugin);
+        self
+    }
+
+    /// Add a plugin to the builder under an instance key
+    ///
+    /// Keys tell apart several plugins of the same type. Each type can be installed
+    /// once without a key and once per key.
+    pub fn with_plugin_keyed<P>(mut self, key: impl Into<String>, plugin: P) -> Self
+    where
+        P: Plugin + 'static,
+    {
+        self.manager.register(Some(key.into()), plugin);
         self
     }
 
@@ -560,21 +2727,290 @@ impl PluginManagerBuilder {
     where
         P: Plugin + 'static,
     {
-        let (handle, task) = self.manager.install_internal(plugin);
-        self.tasks.push(task);
-        handle
+        self.manager.register(None, plugin)
+    }
+
+    /// Grant capabilities to a plugin type
+    ///
+    /// Applies to every installed instance of `P`, and adds to the capabilities granted
+    /// before. A plugin declaring capabilities in [`Plugin::capabilities`] that were not
+    /// granted is refused when building.
+    ///
+    /// # Example
+    /// ```ignore
+    /// let (plugins, init_task) = PluginManagerBuilder::new()
+    ///     .with_plugin(StorePlugin::new(app_name))
+    ///     .with_capabilities::<StorePlugin>([Capability::FsRead, Capability::FsWrite])
+    ///     .build()?;
+    /// ```
+    pub fn with_capabilities<P>(
+        mut self,
+        capabilities: impl IntoIterator<Item = Capability>,
+    ) -> Self
+    where
+        P: Plugin + 'static,
+    {
+        self.grants
+            .entry(TypeId::of::<P>())
+            .or_default()
+            .extend(capabilities);
+        self
+    }
+
+    /// Grant capabilities to the instance of a plugin type installed with `key`
+    ///
+    /// Adds to the capabilities granted to every instance with
+    /// [`PluginManagerBuilder::with_capabilities`].
+    pub fn with_capabilities_keyed<P>(
+        mut self,
+        key: impl Into<String>,
+        capabilities: impl IntoIterator<Item = Capability>,
+    ) -> Self
+    where
+        P: Plugin + 'static,
+    {
+        self.keyed_grants
+            .entry((TypeId::of::<P>(), key.into()))
+            .or_default()
+            .extend(capabilities);
+        self
+    }
+
+    /// Add a plugin loaded from a shared library, keyed by its plugin name
+    ///
+    /// Get its handle with `get_handle_keyed::<DynamicPlugin>(name)`. Capabilities
+    /// granted to [`DynamicPlugin`] apply to every loaded library, so grant a single
+    /// library's capabilities with [`PluginManagerBuilder::with_dynamic_capabilities`].
+    #[cfg(feature = "dynamic")]
+    pub fn with_dynamic_plugin(self, plugin: DynamicPlugin) -> Self {
+        let key = plugin.name();
+        self.with_plugin_keyed(key, plugin)
+    }
+
+    /// Grant capabilities to the plugin library named `name` only
+    ///
+    /// # Example
+    /// ```ignore
+    /// let mut builder = PluginManagerBuilder::new()
+    ///     .with_dynamic_capabilities("greeter", [Capability::Network]);
+    /// for plugin in unsafe { DynamicPlugin::load_dir("plugins") }?.into_iter().flatten() {
+    ///     builder = builder.with_dynamic_plugin(plugin);
+    /// }
+    /// ```
+    #[cfg(feature = "dynamic")]
+    pub fn with_dynamic_capabilities(
+        self,
+        name: impl Into<String>,
+        capabilities: impl IntoIterator<Item = Capability>,
+    ) -> Self {
+        self.with_capabilities_keyed::<DynamicPlugin>(name, capabilities)
+    }
+
+    /// Add an interceptor that sees every message and output routed by the manager
+    ///
+    /// Interceptors run in the order they were added.
+    ///
+    /// # Example
+    /// ```ignore
+    /// let (plugins, init_task) = PluginManagerBuilder::new()
+    ///     .with_plugin(StorePlugin::new(app_name))
+    ///     .with_interceptor(AuditLog::default())
+    ///     .build()?;
+    /// ```
+    pub fn with_interceptor<I>(mut self, interceptor: I) -> Self
+    where
+        I: Interceptor + 'static,
+    {
+        self.manager.interceptors.push(Box::new(interceptor));
+        self
+    }
+
+    /// Record the messages and outputs routed by the manager for its inspector
+    ///
+    /// See [`PluginManager::inspector`]. Recording formats every message with `Debug`,
+    /// so this is usually only enabled in debug builds.
+    ///
+    /// # Example
+    /// ```ignore
+    /// let mut builder = PluginManagerBuilder::new().with_plugin(MyPlugin);
+    /// if cfg!(debug_assertions) {
+    ///     builder = builder.with_inspector();
+    /// }
+    /// ```
+    pub fn with_inspector(mut self) -> Self {
+        self.manager.inspector = Some(Inspector::new());
+        self
+    }
+
+    /// Make the messages of a plugin type recordable and replayable
+    ///
+    /// Applies to every installed instance of `P`. See [`PluginManager::record`] and
+    /// [`PluginManager::replay`].
+    #[cfg(feature = "replay")]
+    pub fn with_replay<P>(mut self) -> Self
+    where
+        P: Plugin + 'static,
+        P::Message: serde::Serialize + serde::de::DeserializeOwned,
+    {
+        self.manager
+            .codecs
+            .insert(TypeId::of::<P>(), Codec::of::<P>());
+        self
+    }
+
+    /// Include the states of a plugin type in [`PluginManager::snapshot`]
+    ///
+    /// Applies to every installed instance of `P`. See [`PluginManager::restore`].
+    #[cfg(feature = "snapshot")]
+    pub fn with_snapshot<P>(mut self) -> Self
+    where
+        P: Plugin + 'static,
+        P::State: serde::Serialize + serde::de::DeserializeOwned,
+    {
+        self.manager
+            .state_codecs
+            .insert(TypeId::of::<P>(), StateCodec::of::<P>());
+        self
+    }
+
+    /// Load plugin configs from a TOML file and the environment
+    ///
+    /// Only plugin types registered with [`PluginManagerBuilder::with_config`] read it.
+    ///
+    /// # Example
+    /// ```ignore
+    /// let source = ConfigSource::for_app("com", "example", "myapp")
+    ///     .expect("no home directory")
+    ///     .watch(Duration::from_secs(2));
+    ///
+    /// let (plugins, init_task) = PluginManagerBuilder::new()
+    ///     .with_plugin(WindowStatePlugin::new(app_name))
+    ///     .with_config::<WindowStatePlugin>()
+    ///     .with_config_source(source)
+    ///     .build()?;
+    /// ```
+    #[cfg(feature = "config")]
+    pub fn with_config_source(mut self, source: ConfigSource) -> Self {
+        self.manager.config_source = Some(source);
+        self
+    }
+
+    /// Load the config of a plugin type from the config source
+    ///
+    /// Applies to every installed instance of `P`, which share the table named after the
+    /// plugin. Without a config source, plugins get their defaults, see
+    /// [`Configurable::default_config`].
+    #[cfg(feature = "config")]
+    pub fn with_config<P>(mut self) -> Self
+    where
+        P: Configurable + 'static,
+    {
+        self.manager
+            .config_codecs
+            .insert(TypeId::of::<P>(), ConfigCodec::of::<P>());
+        self
+    }
+
+    /// Keep recent outputs of a plugin type and replay them to listeners that subscribe
+    /// later, so outputs emitted before the application's first `subscription()` call,
+    /// such as the results of init tasks, are not lost
+    ///
+    /// Applies to every installed instance of `P`. Every new listener, including one
+    /// recreated by iced after its subscription changed, receives the buffered outputs.
+    ///
+    /// # Example
+    /// ```ignore
+    /// let (plugins, init_task) = PluginManagerBuilder::new()
+    ///     .with_plugin(AutoUpdaterPlugin::new(app_name, config.with_check_on_start(true)))
+    ///     .with_output_buffer::<AutoUpdaterPlugin>(OutputBuffer::Latest)
+    ///     .build()?;
+    /// ```
+    pub fn with_output_buffer<P>(mut self, buffer: OutputBuffer) -> Self
+    where
+        P: Plugin + 'static,
+    {
+        self.output_buffers.insert(TypeId::of::<P>(), buffer);
+        self
+    }
+
+    /// Set the channel used by output listeners that don't choose their own with
+    /// [`PluginHandle::with_output_channel`]
+    ///
+    /// Listeners use an unbounded channel by default.
+    ///
+    /// # Example
+    /// ```ignore
+    /// let (plugins, init_task) = PluginManagerBuilder::new()
+    ///     .with_plugin(AutoUpdaterPlugin::new(app_name, config))
+    ///     .with_output_channel(OutputChannel::bounded(64, OverflowPolicy::DropOldest))
+    ///     .build()?;
+    /// ```
+    pub fn with_output_channel(self, output_channel: OutputChannel) -> Self {
+        self.manager.registry.set_output_channel(output_channel);
+        self
+    }
+
+    /// Install a plugin under an instance key and return a handle to it
+    ///
+    /// # Example
+    /// ```ignore
+    /// let mut builder = PluginManagerBuilder::new();
+    /// let personal = builder.install_keyed("personal", StorePlugin::new(personal_app));
+    /// let work = builder.install_keyed("work", StorePlugin::new(work_app));
+    /// ```
+    pub fn install_keyed<P>(&mut self, key: impl Into<String>, plugin: P) -> PluginHandle<P>
+    where
+        P: Plugin + 'static,
+    {
+        self.manager.register(Some(key.into()), plugin)
     }
 
     /// Build the plugin manager and return it with all batched init tasks
     ///
+    /// Plugins are initialized in dependency order: every plugin is initialized after
+    /// the plugins it declares in [`Plugin::dependencies`]. Plugins without dependencies
+    /// between them are initialized in installation order.
+    ///
     /// Returns a tuple of (PluginManager, Task) where the task contains all
     /// plugin initialization tasks batched together. Map this task to your
     /// application's message type.
     ///
     /// After building, use `get_handle()` to retrieve handles to installed plugins.
-    pub fn build(self) -> (PluginManager, Task<PluginMessage>) {
-        let combined_task = Task::batch(self.tasks);
-        (self.manager, combined_task)
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`BuildError`] if a plugin declares a capability that was not granted,
+    /// if a declared dependency is not installed, if a plugin type is installed twice
+    /// under the same key, or if plugins depend on each other in a cycle.
+    pub fn build(mut self) -> Result<(PluginManager, Task<PluginMessage>), BuildError> {
+        for entry in &mut self.manager.plugins {
+            let mut granted = self
+                .grants
+                .get(&entry.plugin_type)
+                .cloned()
+                .unwrap_or_default();
+            if let Some(keyed) = entry
+                .key
+                .as_ref()
+                .and_then(|key| self.keyed_grants.get(&(entry.plugin_type, key.clone())))
+            {
+                granted.extend(keyed);
+            }
+            capability::check(entry.name, &entry.capabilities, &granted)?;
+            entry.granted = granted;
+
+            if let Some(buffer) = self.output_buffers.get(&entry.plugin_type) {
+                self.manager
+                    .registry
+                    .buffer_outputs(entry.plugin_index, buffer.capacity());
+            }
+        }
+
+        #[cfg(feature = "config")]
+        self.manager.load_configs().map_err(BuildError::Config)?;
+
+        let init_task = self.manager.initialize()?;
+        Ok((self.manager, init_task))
     }
 }
 
@@ -583,3 +3019,31 @@ impl Default for PluginManagerBuilder {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::OverflowPolicy;
+    use iced::futures::StreamExt;
+
+    #[test]
+    fn buffers_listener_outputs_only_in_their_channel() {
+        let registry = Registry::new();
+        registry.add_plugin();
+        let mut outputs = Box::pin(output_listener_filtered::<u32, u32>(
+            0,
+            1,
+            TypeId::of::<u32>(),
+            Some(OutputChannel::bounded(1, OverflowPolicy::CoalesceLatest)),
+            Arc::clone(&registry),
+            Arc::new(|output| Some(output * 10)),
+        ));
+
+        for value in 1..=3u32 {
+            registry.publish(&PluginOutput::new(0, value));
+        }
+
+        assert_eq!(outputs.next().now_or_never(), Some(Some(30)));
+        assert_eq!(outputs.next().now_or_never(), None);
+    }
+}
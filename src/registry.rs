@@ -0,0 +1,651 @@
+use crate::channel::{Delivery, OutputChannel, OutputSender};
+use crate::event::MANAGER_EVENT_BACKLOG;
+use crate::{ManagerEvent, PluginFault, PluginMessage, PluginOutput, PluginStatus};
+use iced::Subscription;
+use iced::futures::Stream;
+use iced::futures::channel::{mpsc, oneshot};
+use std::collections::{HashMap, VecDeque};
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+
+/// State shared between a [`PluginManager`](crate::PluginManager) and its handles
+#[derive(Debug)]
+pub(crate) struct Registry {
+    /// Status of every registered plugin, by plugin index
+    statuses: Mutex<Vec<PluginStatus>>,
+    /// Output listeners by plugin index
+    outputs: Mutex<HashMap<usize, Listeners>>,
+    /// Channel of listeners that don't pick their own
+    output_channel: Mutex<OutputChannel>,
+    /// Recent outputs replayed to new listeners, by plugin index.
+    /// Always locked after `outputs`, so listeners never miss or repeat an output.
+    buffers: Mutex<HashMap<usize, OutputHistory>>,
+    /// Callers waiting for an output, by correlation id
+    calls: Mutex<HashMap<u64, oneshot::Sender<PluginOutput>>>,
+    next_correlation_id: AtomicU64,
+    /// Listeners of manager events
+    events: Mutex<EventListeners<ManagerEvent>>,
+    /// Listeners of plugin faults, with a backlog of their own
+    faults: Mutex<EventListeners<PluginFault>>,
+    /// Messages sent from outside of the application's update loop
+    inbox: mpsc::UnboundedSender<PluginMessage>,
+    inbox_receiver: Mutex<Option<mpsc::UnboundedReceiver<PluginMessage>>>,
+}
+
+pub(crate) type SharedRegistry = Arc<Registry>;
+
+/// Output listeners of a plugin
+#[derive(Debug, Default)]
+struct Listeners {
+    /// Senders with the id of their listener
+    senders: Vec<(u64, OutputSender)>,
+    /// Outputs dropped by the overflow policies of the senders
+    dropped: u64,
+}
+
+impl Listeners {
+    /// Send an output to every listener, dropping closed listeners
+    fn send(&mut self, output: &PluginOutput) {
+        let mut dropped = 0;
+        self.senders
+            .retain(|(_, sender)| match sender.send(output.clone()) {
+                Some(Delivery::Queued) => true,
+                Some(Delivery::Dropped) => {
+                    dropped += 1;
+                    true
+                }
+                None => false,
+            });
+        self.dropped += dropped;
+    }
+}
+
+/// Listeners of an event stream, and the events emitted while there were none
+#[derive(Debug)]
+struct EventListeners<T> {
+    senders: Vec<mpsc::UnboundedSender<T>>,
+    backlog: VecDeque<T>,
+}
+
+impl<T> Default for EventListeners<T> {
+    fn default() -> Self {
+        Self {
+            senders: Vec::new(),
+            backlog: VecDeque::new(),
+        }
+    }
+}
+
+impl<T: Clone> EventListeners<T> {
+    /// Send an event to all listeners, dropping closed listeners. Without listeners,
+    /// the event is kept for the next one if `keep` is set.
+    fn send(&mut self, event: T, keep: bool) {
+        self.senders
+            .retain(|sender| sender.unbounded_send(event.clone()).is_ok());
+
+        if keep && self.senders.is_empty() {
+            if self.backlog.len() == MANAGER_EVENT_BACKLOG {
+                self.backlog.pop_front();
+            }
+            self.backlog.push_back(event);
+        }
+    }
+
+    /// Add a listener, which receives the backlog first
+    fn listen(&mut self) -> mpsc::UnboundedReceiver<T> {
+        let (sender, receiver) = mpsc::unbounded();
+        for event in self.backlog.drain(..) {
+            let _ = sender.unbounded_send(event);
+        }
+        self.senders.push(sender);
+        receiver
+    }
+}
+
+/// Subscription data of one of the event streams of a registry
+struct EventStream<T> {
+    registry: SharedRegistry,
+    listeners: fn(&Registry) -> &Mutex<EventListeners<T>>,
+}
+
+impl<T> std::hash::Hash for EventStream<T> {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        (Arc::as_ptr(&self.registry) as usize).hash(state);
+        std::any::type_name::<T>().hash(state);
+    }
+}
+
+impl<T: Clone + Send + 'static> EventStream<T> {
+    fn subscription(self) -> Subscription<T> {
+        fn create_stream<T: Clone>(stream: &EventStream<T>) -> mpsc::UnboundedReceiver<T> {
+            match (stream.listeners)(&stream.registry).lock() {
+                Ok(mut listeners) => listeners.listen(),
+                // Ends right away
+                Err(_) => mpsc::unbounded().1,
+            }
+        }
+
+        Subscription::run_with(self, create_stream::<T>)
+    }
+}
+
+/// The most recent outputs of a plugin, oldest first
+#[derive(Debug)]
+struct OutputHistory {
+    capacity: usize,
+    /// Buffered outputs with their sequence numbers
+    outputs: VecDeque<(u64, PluginOutput)>,
+    next_sequence: u64,
+    /// Sequence number of the first output each listener hasn't received, by listener id
+    received: HashMap<u64, u64>,
+}
+
+impl OutputHistory {
+    fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            outputs: VecDeque::with_capacity(capacity),
+            next_sequence: 0,
+            received: HashMap::new(),
+        }
+    }
+
+    /// Buffer an output that is sent to the listeners with the given ids
+    fn push(&mut self, output: PluginOutput, listener_ids: impl Iterator<Item = u64>) {
+        if self.capacity == 0 {
+            return;
+        }
+        if self.outputs.len() == self.capacity {
+            self.outputs.pop_front();
+        }
+        self.outputs.push_back((self.next_sequence, output));
+        self.next_sequence += 1;
+
+        for listener_id in listener_ids {
+            self.received.insert(listener_id, self.next_sequence);
+        }
+    }
+
+    /// Take the buffered outputs a listener hasn't received yet, oldest first
+    fn replay(&mut self, listener_id: u64) -> Vec<PluginOutput> {
+        let first = self.received.insert(listener_id, self.next_sequence);
+        self.outputs
+            .iter()
+            .filter(|(sequence, _)| first.is_none_or(|first| *sequence >= first))
+            .map(|(_, output)| output.clone())
+            .collect()
+    }
+}
+
+impl Registry {
+    pub(crate) fn new() -> SharedRegistry {
+        let (inbox, inbox_receiver) = mpsc::unbounded();
+        Arc::new(Self {
+            statuses: Mutex::new(Vec::new()),
+            outputs: Mutex::new(HashMap::new()),
+            output_channel: Mutex::new(OutputChannel::Unbounded),
+            buffers: Mutex::new(HashMap::new()),
+            calls: Mutex::new(HashMap::new()),
+            next_correlation_id: AtomicU64::new(0),
+            events: Mutex::new(EventListeners::default()),
+            faults: Mutex::new(EventListeners::default()),
+            inbox,
+            inbox_receiver: Mutex::new(Some(inbox_receiver)),
+        })
+    }
+
+    /// Track the status of a newly registered plugin, enabled to begin with
+    pub(crate) fn add_plugin(&self) {
+        if let Ok(mut statuses) = self.statuses.lock() {
+            statuses.push(PluginStatus::Enabled);
+        }
+    }
+
+    /// Get the status of a plugin
+    pub(crate) fn status(&self, plugin_index: usize) -> PluginStatus {
+        self.statuses
+            .lock()
+            .ok()
+            .and_then(|statuses| statuses.get(plugin_index).cloned())
+            .unwrap_or(PluginStatus::Uninstalled)
+    }
+
+    pub(crate) fn set_status(&self, plugin_index: usize, status: PluginStatus) {
+        if let Ok(mut statuses) = self.statuses.lock()
+            && let Some(slot) = statuses.get_mut(plugin_index)
+        {
+            *slot = status;
+        }
+    }
+
+    /// Keep the last `capacity` outputs of a plugin for listeners registered later
+    pub(crate) fn buffer_outputs(&self, plugin_index: usize, capacity: usize) {
+        if let Ok(mut buffers) = self.buffers.lock() {
+            buffers.insert(plugin_index, OutputHistory::new(capacity));
+        }
+    }
+
+    /// Register a listener for the outputs of a plugin, sending it the buffered
+    /// outputs it didn't receive under the same listener id before
+    pub(crate) fn listen(&self, plugin_index: usize, listener_id: u64, sender: OutputSender) {
+        if let Ok(mut outputs) = self.outputs.lock() {
+            let listeners = outputs.entry(plugin_index).or_default();
+            if let Ok(mut buffers) = self.buffers.lock()
+                && let Some(history) = buffers.get_mut(&plugin_index)
+            {
+                for output in history.replay(listener_id) {
+                    if sender.send(output) == Some(Delivery::Dropped) {
+                        listeners.dropped += 1;
+                    }
+                }
+            }
+
+            listeners.senders.push((listener_id, sender));
+        }
+    }
+
+    /// Get the default channel of output listeners
+    pub(crate) fn output_channel(&self) -> OutputChannel {
+        self.output_channel
+            .lock()
+            .map(|channel| *channel)
+            .unwrap_or_default()
+    }
+
+    pub(crate) fn set_output_channel(&self, output_channel: OutputChannel) {
+        if let Ok(mut channel) = self.output_channel.lock() {
+            *channel = output_channel;
+        }
+    }
+
+    /// Count the live listeners of a plugin
+    pub(crate) fn listener_count(&self, plugin_index: usize) -> usize {
+        self.outputs
+            .lock()
+            .ok()
+            .and_then(|outputs| {
+                outputs.get(&plugin_index).map(|listeners| {
+                    listeners
+                        .senders
+                        .iter()
+                        .filter(|(_, sender)| !sender.is_closed())
+                        .count()
+                })
+            })
+            .unwrap_or(0)
+    }
+
+    /// Count the outputs of a plugin dropped by its listeners' overflow policies
+    pub(crate) fn dropped_outputs(&self, plugin_index: usize) -> u64 {
+        self.outputs
+            .lock()
+            .ok()
+            .and_then(|outputs| {
+                outputs
+                    .get(&plugin_index)
+                    .map(|listeners| listeners.dropped)
+            })
+            .unwrap_or(0)
+    }
+
+    /// Send an output to all listeners of its plugin, dropping closed listeners,
+    /// and buffer it if the plugin keeps outputs for later listeners
+    pub(crate) fn publish(&self, output: &PluginOutput) {
+        if let Ok(mut outputs) = self.outputs.lock() {
+            let listeners = outputs.get_mut(&output.plugin_index());
+            if let Ok(mut buffers) = self.buffers.lock()
+                && let Some(history) = buffers.get_mut(&output.plugin_index())
+            {
+                let listener_ids = listeners
+                    .iter()
+                    .flat_map(|listeners| &listeners.senders)
+                    .filter(|(_, sender)| !sender.is_closed())
+                    .map(|(listener_id, _)| *listener_id);
+                history.push(output.clone(), listener_ids);
+            }
+
+            if let Some(listeners) = listeners {
+                listeners.send(output);
+            }
+        }
+    }
+
+    /// Drop all listeners of a plugin, ending their streams, and its buffered outputs
+    pub(crate) fn remove_listeners(&self, plugin_index: usize) {
+        if let Ok(mut outputs) = self.outputs.lock() {
+            outputs.remove(&plugin_index);
+        }
+        if let Ok(mut buffers) = self.buffers.lock()
+            && let Some(history) = buffers.get_mut(&plugin_index)
+        {
+            history.outputs.clear();
+        }
+    }
+
+    /// Start waiting for the output of a message.
+    /// Returns the correlation id to tag the message with.
+    pub(crate) fn start_call(&self) -> (u64, oneshot::Receiver<PluginOutput>) {
+        let correlation_id = self.next_correlation_id.fetch_add(1, Ordering::Relaxed);
+        let (sender, receiver) = oneshot::channel();
+        if let Ok(mut calls) = self.calls.lock() {
+            calls.insert(correlation_id, sender);
+        }
+        (correlation_id, receiver)
+    }
+
+    /// Hand an output to the caller waiting on the correlation id, if any
+    pub(crate) fn complete_call(&self, correlation_id: u64, output: &PluginOutput) {
+        let sender = self
+            .calls
+            .lock()
+            .ok()
+            .and_then(|mut calls| calls.remove(&correlation_id));
+
+        if let Some(sender) = sender {
+            let _ = sender.send(output.clone());
+        }
+    }
+
+    /// Stop waiting for the output of a message
+    pub(crate) fn cancel_call(&self, correlation_id: u64) {
+        if let Ok(mut calls) = self.calls.lock() {
+            calls.remove(&correlation_id);
+        }
+    }
+
+    /// Send an event to all event listeners, and faults to the fault listeners too.
+    ///
+    /// Without listeners, the event is kept for the next one, except for outputs,
+    /// which would crowd out the rarer events. Events and faults have separate
+    /// backlogs, so listening to one doesn't drain the other.
+    pub(crate) fn emit(&self, event: ManagerEvent) {
+        if let ManagerEvent::PluginFaulted(fault) = &event
+            && let Ok(mut faults) = self.faults.lock()
+        {
+            faults.send(fault.clone(), true);
+        }
+        if let Ok(mut events) = self.events.lock() {
+            let keep = !matches!(event, ManagerEvent::OutputEmitted { .. });
+            events.send(event, keep);
+        }
+    }
+
+    /// Subscription producing the events sent with [`Registry::emit`], starting with
+    /// the backlog
+    pub(crate) fn events(self: &Arc<Self>) -> Subscription<ManagerEvent> {
+        EventStream {
+            registry: Arc::clone(self),
+            listeners: |registry| &registry.events,
+        }
+        .subscription()
+    }
+
+    /// Subscription producing the faults sent with [`Registry::emit`], starting with
+    /// their backlog
+    pub(crate) fn faults(self: &Arc<Self>) -> Subscription<PluginFault> {
+        EventStream {
+            registry: Arc::clone(self),
+            listeners: |registry| &registry.faults,
+        }
+        .subscription()
+    }
+
+    /// Queue a message to be routed through the application's update loop
+    pub(crate) fn send(&self, message: PluginMessage) -> bool {
+        self.inbox.unbounded_send(message).is_ok()
+    }
+
+    /// Take the receiving end of the inbox, to drain it without a subscription
+    #[cfg(feature = "testing")]
+    pub(crate) fn take_inbox(&self) -> Option<mpsc::UnboundedReceiver<PluginMessage>> {
+        self.inbox_receiver
+            .lock()
+            .ok()
+            .and_then(|mut receiver| receiver.take())
+    }
+
+    /// Subscription producing the messages queued with [`Registry::send`]
+    pub(crate) fn inbox(self: &Arc<Self>) -> Subscription<PluginMessage> {
+        struct Inbox(SharedRegistry);
+
+        impl std::hash::Hash for Inbox {
+            fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+                (Arc::as_ptr(&self.0) as usize).hash(state);
+            }
+        }
+
+        fn create_stream(inbox: &Inbox) -> InboxStream {
+            let receiver = inbox
+                .0
+                .inbox_receiver
+                .lock()
+                .ok()
+                .and_then(|mut receiver| receiver.take());
+
+            InboxStream {
+                registry: Arc::clone(&inbox.0),
+                receiver,
+            }
+        }
+
+        Subscription::run_with(Inbox(Arc::clone(self)), create_stream)
+    }
+}
+
+/// Stream over the inbox that hands the receiver back when the subscription stops,
+/// so a later subscription can pick it up again
+struct InboxStream {
+    registry: SharedRegistry,
+    receiver: Option<mpsc::UnboundedReceiver<PluginMessage>>,
+}
+
+impl Stream for InboxStream {
+    type Item = PluginMessage;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        match self.receiver.as_mut() {
+            Some(receiver) => Pin::new(receiver).poll_next(cx),
+            None => Poll::Ready(None),
+        }
+    }
+}
+
+impl Drop for InboxStream {
+    fn drop(&mut self) {
+        if let Some(receiver) = self.receiver.take()
+            && let Ok(mut slot) = self.registry.inbox_receiver.lock()
+        {
+            *slot = Some(receiver);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::channel::{self, OutputReceiver};
+    use iced::futures::{FutureExt, StreamExt};
+
+    const PLUGIN: usize = 0;
+
+    fn registry(capacity: usize) -> SharedRegistry {
+        let registry = Registry::new();
+        registry.add_plugin();
+        registry.buffer_outputs(PLUGIN, capacity);
+        registry
+    }
+
+    fn listen(registry: &Registry, listener_id: u64) -> OutputReceiver {
+        let (sender, receiver) = channel::channel(OutputChannel::Unbounded);
+        registry.listen(PLUGIN, listener_id, sender);
+        receiver
+    }
+
+    fn publish(registry: &Registry, value: u32) {
+        registry.publish(&PluginOutput::new(PLUGIN, value));
+    }
+
+    /// Take the outputs that are ready
+    fn received(receiver: &mut OutputReceiver) -> Vec<u32> {
+        std::iter::from_fn(|| receiver.next().now_or_never().flatten())
+            .filter_map(|output| output.downcast::<u32>().copied())
+            .collect()
+    }
+
+    #[test]
+    fn replays_buffered_outputs_to_new_listeners() {
+        let registry = registry(2);
+        publish(&registry, 1);
+        publish(&registry, 2);
+        publish(&registry, 3);
+
+        let mut receiver = listen(&registry, 1);
+        assert_eq!(received(&mut receiver), [2, 3]);
+
+        let mut other = listen(&registry, 2);
+        assert_eq!(received(&mut other), [2, 3]);
+    }
+
+    #[test]
+    fn replays_outputs_once_per_listener() {
+        let registry = registry(4);
+        publish(&registry, 1);
+
+        let mut receiver = listen(&registry, 1);
+        assert_eq!(received(&mut receiver), [1]);
+        drop(receiver);
+
+        let mut receiver = listen(&registry, 1);
+        assert!(received(&mut receiver).is_empty());
+    }
+
+    #[test]
+    fn skips_outputs_a_listener_received_live() {
+        let registry = registry(4);
+
+        let mut receiver = listen(&registry, 1);
+        publish(&registry, 1);
+        assert_eq!(received(&mut receiver), [1]);
+        drop(receiver);
+
+        publish(&registry, 2);
+        let mut receiver = listen(&registry, 1);
+        assert_eq!(received(&mut receiver), [2]);
+    }
+
+    fn fault() -> ManagerEvent {
+        ManagerEvent::PluginFaulted(PluginFault {
+            plugin: "plugin",
+            key: None,
+            reason: "panicked".to_string(),
+        })
+    }
+
+    fn output() -> ManagerEvent {
+        ManagerEvent::OutputEmitted {
+            plugin: "plugin",
+            key: None,
+            output: PluginOutput::new(PLUGIN, 1_u32),
+        }
+    }
+
+    /// Take the events that are ready
+    fn ready<T>(receiver: &mut mpsc::UnboundedReceiver<T>) -> Vec<T> {
+        std::iter::from_fn(|| receiver.next().now_or_never().flatten()).collect()
+    }
+
+    #[test]
+    fn keeps_separate_backlogs_for_events_and_faults() {
+        let registry = registry(0);
+        registry.emit(fault());
+
+        let mut events = registry.events.lock().unwrap().listen();
+        assert!(matches!(
+            ready(&mut events)[..],
+            [ManagerEvent::PluginFaulted(_)]
+        ));
+
+        let mut faults = registry.faults.lock().unwrap().listen();
+        assert_eq!(ready(&mut faults).len(), 1);
+    }
+
+    #[test]
+    fn sends_outputs_only_to_current_listeners() {
+        let registry = registry(0);
+        registry.emit(output());
+        registry.emit(fault());
+
+        let mut events = registry.events.lock().unwrap().listen();
+        assert!(matches!(
+            ready(&mut events)[..],
+            [ManagerEvent::PluginFaulted(_)]
+        ));
+
+        registry.emit(output());
+        assert!(matches!(
+            ready(&mut events)[..],
+            [ManagerEvent::OutputEmitted { .. }]
+        ));
+    }
+
+    #[test]
+    fn keeps_nothing_without_a_buffer() {
+        let registry = registry(0);
+        publish(&registry, 1);
+
+        let mut receiver = listen(&registry, 1);
+        assert!(received(&mut receiver).is_empty());
+    }
+
+    fn call_output(value: u32) -> PluginOutput {
+        PluginOutput::new(0, value)
+    }
+
+    /// Take the value a call received, if it completed
+    fn answer(receiver: oneshot::Receiver<PluginOutput>) -> Option<u32> {
+        receiver
+            .now_or_never()
+            .and_then(Result::ok)
+            .and_then(|output| output.downcast::<u32>().copied())
+    }
+
+    #[test]
+    fn hands_outputs_to_their_caller() {
+        let registry = Registry::new();
+        let (first, first_receiver) = registry.start_call();
+        let (second, second_receiver) = registry.start_call();
+        assert_ne!(first, second);
+
+        registry.complete_call(second, &call_output(2));
+        registry.complete_call(first, &call_output(1));
+
+        assert_eq!(answer(first_receiver), Some(1));
+        assert_eq!(answer(second_receiver), Some(2));
+    }
+
+    #[test]
+    fn completes_a_call_once() {
+        let registry = Registry::new();
+        let (correlation_id, receiver) = registry.start_call();
+
+        registry.complete_call(correlation_id, &call_output(1));
+        registry.complete_call(correlation_id, &call_output(2));
+
+        assert_eq!(answer(receiver), Some(1));
+        assert!(registry.calls.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn drops_cancelled_calls() {
+        let registry = Registry::new();
+        let (correlation_id, receiver) = registry.start_call();
+
+        registry.cancel_call(correlation_id);
+        registry.complete_call(correlation_id, &call_output(1));
+
+        assert_eq!(answer(receiver), None);
+        assert!(registry.calls.lock().unwrap().is_empty());
+    }
+}
@@ -0,0 +1,69 @@
+use crate::{Plugin, PluginMessage};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::io::{self, Write};
+
+/// Converts the messages of one plugin type to and from JSON
+#[derive(Clone, Copy)]
+pub(crate) struct Codec {
+    encode: fn(&PluginMessage) -> Option<serde_json::Result<Value>>,
+    decode: fn(usize, Value) -> serde_json::Result<PluginMessage>,
+}
+
+impl Codec {
+    pub(crate) fn of<P>() -> Self
+    where
+        P: Plugin + 'static,
+        P::Message: Serialize + DeserializeOwned,
+    {
+        Self {
+            encode: |message| message.downcast::<P::Message>().map(serde_json::to_value),
+            decode: |plugin_index, value| {
+                serde_json::from_value::<P::Message>(value)
+                    .map(|message| PluginMessage::new(plugin_index, message))
+            },
+        }
+    }
+
+    /// Serialize a message, or return None if it is not of this plugin's type
+    pub(crate) fn encode(&self, message: &PluginMessage) -> Option<serde_json::Result<Value>> {
+        (self.encode)(message)
+    }
+
+    pub(crate) fn decode(
+        &self,
+        plugin_index: usize,
+        value: Value,
+    ) -> serde_json::Result<PluginMessage> {
+        (self.decode)(plugin_index, value)
+    }
+}
+
+/// A recorded message, stored as one line of JSON
+#[derive(Serialize, Deserialize)]
+pub(crate) struct Record {
+    pub plugin: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key: Option<String>,
+    pub message: Value,
+}
+
+/// Writes routed messages as JSON lines
+pub(crate) struct Recorder {
+    writer: Box<dyn Write + Send>,
+}
+
+impl Recorder {
+    pub(crate) fn new(writer: impl Write + Send + 'static) -> Self {
+        Self {
+            writer: Box::new(writer),
+        }
+    }
+
+    pub(crate) fn write(&mut self, record: &Record) -> io::Result<()> {
+        serde_json::to_writer(&mut self.writer, record)?;
+        self.writer.write_all(b"\n")?;
+        self.writer.flush()
+    }
+}
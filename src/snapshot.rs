@@ -0,0 +1,67 @@
+use crate::Plugin;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::any::Any;
+
+/// Converts the states of one plugin type to and from JSON
+#[derive(Clone, Copy)]
+pub(crate) struct StateCodec {
+    encode: fn(&dyn Any) -> Option<serde_json::Result<Value>>,
+    decode: fn(Value) -> serde_json::Result<Box<dyn Any + Send>>,
+}
+
+impl StateCodec {
+    pub(crate) fn of<P>() -> Self
+    where
+        P: Plugin + 'static,
+        P::State: Serialize + DeserializeOwned,
+    {
+        Self {
+            encode: |state| state.downcast_ref::<P::State>().map(serde_json::to_value),
+            decode: |value| {
+                serde_json::from_value::<P::State>(value)
+                    .map(|state| Box::new(state) as Box<dyn Any + Send>)
+            },
+        }
+    }
+
+    /// Serialize a state, or return None if it is not of this plugin's type
+    pub(crate) fn encode(&self, state: &dyn Any) -> Option<serde_json::Result<Value>> {
+        (self.encode)(state)
+    }
+
+    pub(crate) fn decode(&self, value: Value) -> serde_json::Result<Box<dyn Any + Send>> {
+        (self.decode)(value)
+    }
+}
+
+/// Saved plugin states, taken with [`PluginManager::snapshot`](crate::PluginManager::snapshot)
+///
+/// Serialize it to keep it across restarts, e.g. with the store plugin, and bring the
+/// states back with [`PluginManager::restore`](crate::PluginManager::restore).
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub(crate) states: Vec<SavedState>,
+}
+
+impl Snapshot {
+    /// Number of plugin states in the snapshot
+    pub fn len(&self) -> usize {
+        self.states.len()
+    }
+
+    /// Whether the snapshot holds no plugin state
+    pub fn is_empty(&self) -> bool {
+        self.states.is_empty()
+    }
+}
+
+/// The state of one plugin instance
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub(crate) struct SavedState {
+    pub plugin: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key: Option<String>,
+    pub state: Value,
+}
@@ -0,0 +1,342 @@
+use crate::{
+    CallError, Interceptor, Plugin, PluginHandle, PluginManager, PluginManagerBuilder,
+    PluginMessage, PluginOutput,
+};
+use iced::Task;
+use iced::futures::channel::mpsc;
+use iced::futures::future::{self, Either};
+use iced::futures::stream::{self, BoxStream, SelectAll, StreamExt};
+use iced_runtime::Action;
+use std::fmt::Debug;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// How long [`PluginTester`] waits for a task to make progress before failing the test
+pub const DEFAULT_TASK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Collects the outputs of all plugins
+#[derive(Clone, Default)]
+struct OutputCapture(Arc<Mutex<Vec<PluginOutput>>>);
+
+impl Interceptor for OutputCapture {
+    fn on_output(&mut self, _plugin: &'static str, output: PluginOutput) -> Option<PluginOutput> {
+        if let Ok(mut outputs) = self.0.lock() {
+            outputs.push(output.clone());
+        }
+        Some(output)
+    }
+}
+
+/// What driving the tasks of a test produced
+enum Event<T> {
+    /// A message to route through the manager
+    Message(PluginMessage),
+    /// A value produced by the task passed to [`PluginTester::perform`]
+    Value(T),
+    /// One of the tasks finished
+    Finished,
+}
+
+/// Turn a task into a stream of events that ends with [`Event::Finished`]
+fn events<T, U>(task: Task<T>, event: fn(T) -> Event<U>) -> Option<BoxStream<'static, Event<U>>>
+where
+    T: Send + 'static,
+    U: Send + 'static,
+{
+    let stream = iced_runtime::task::into_stream(task)?;
+
+    Some(
+        stream
+            .filter_map(move |action| {
+                future::ready(match action {
+                    Action::Output(value) => Some(event(value)),
+                    // Window, clipboard and widget actions need a running application
+                    _ => None,
+                })
+            })
+            .chain(stream::once(future::ready(Event::Finished)))
+            .boxed(),
+    )
+}
+
+/// Headless harness for unit-testing a plugin
+///
+/// The tester installs a plugin in a [`PluginManager`] and runs the tasks it returns
+/// to completion on a tokio runtime, routing the messages they produce back to the
+/// plugins, just like a running application would. Outputs the plugin emits are
+/// collected for assertions. Subscriptions don't run: use [`PluginTester::send`] to
+/// inject the messages they would produce.
+///
+/// The tester grants no capabilities, just like a manager built by the application.
+/// Grant the ones a test needs on a builder passed to [`PluginTester::with_builder`],
+/// so capabilities left out are denied.
+///
+/// Use it from plain `#[test]` functions, since it runs its own runtime.
+///
+/// # Example
+/// ```ignore
+/// #[test]
+/// fn stores_values() {
+///     let store = StorePlugin::new(AppName::new("com", "example", "test"));
+///     let grants = PluginManagerBuilder::new()
+///         .with_capabilities::<StorePlugin>([Capability::FsRead, Capability::FsWrite]);
+///     let mut tester = PluginTester::with_builder(grants, store);
+///
+///     tester.dispatch(StoreInput::set("ui", "theme", "dark"));
+///     assert!(matches!(tester.take_outputs()[..], [StoreOutput::Set { .. }]));
+///
+///     let output = tester.call(StoreInput::get("ui", "theme")).unwrap();
+///     assert_eq!(output.as_value::<String>().as_deref(), Some("dark"));
+/// }
+/// ```
+pub struct PluginTester<P: Plugin> {
+    manager: PluginManager,
+    handle: PluginHandle<P>,
+    outputs: OutputCapture,
+    inbox: mpsc::UnboundedReceiver<PluginMessage>,
+    runtime: tokio::runtime::Runtime,
+    timeout: Duration,
+}
+
+impl<P: Plugin + 'static> PluginTester<P> {
+    /// Install a plugin on its own and run its init task, including its
+    /// [`Plugin::init_async`] future
+    ///
+    /// # Panics
+    ///
+    /// Panics if the plugin has dependencies or declares capabilities, which need
+    /// [`PluginTester::with_builder`], or if its init task doesn't finish in time.
+    pub fn new(plugin: P) -> Self {
+        Self::with_builder(PluginManagerBuilder::new(), plugin)
+    }
+
+    /// Install a plugin next to the plugins of a builder, such as its dependencies,
+    /// and run all init tasks
+    ///
+    /// The builder grants the capabilities of all plugins, including the tested one.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the manager fails to build, or if the init tasks don't finish in time.
+    ///
+    /// # Example
+    /// ```ignore
+    /// let builder = PluginManagerBuilder::new()
+    ///     .with_plugin(StorePlugin::new(app_name.clone()))
+    ///     .with_capabilities::<StorePlugin>([Capability::FsRead, Capability::FsWrite])
+    ///     .with_capabilities::<WindowStatePlugin>([Capability::FsRead, Capability::FsWrite]);
+    /// let mut tester = PluginTester::with_builder(builder, WindowStatePlugin::new(app_name));
+    /// ```
+    pub fn with_builder(builder: PluginManagerBuilder, plugin: P) -> Self {
+        let outputs = OutputCapture::default();
+        let mut builder = builder.with_interceptor(outputs.clone());
+        let handle = builder.install(plugin);
+        let (manager, init_task) = match builder.build() {
+            Ok(built) => built,
+            Err(error) => panic!("failed to build plugins: {}", error),
+        };
+
+        let inbox = manager
+            .registry()
+            .take_inbox()
+            .expect("the inbox of a new manager is available");
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .expect("failed to create tokio runtime");
+
+        let mut tester = Self {
+            manager,
+            handle,
+            outputs,
+            inbox,
+            runtime,
+            timeout: DEFAULT_TASK_TIMEOUT,
+        };
+        tester.run(init_task);
+        tester
+    }
+
+    /// Set how long to wait for tasks to make progress, instead of [`DEFAULT_TASK_TIMEOUT`]
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Get the handle to the tested plugin
+    pub fn handle(&self) -> &PluginHandle<P> {
+        &self.handle
+    }
+
+    /// Get the manager, e.g. to look at the state of other plugins
+    pub fn manager(&self) -> &PluginManager {
+        &self.manager
+    }
+
+    pub fn manager_mut(&mut self) -> &mut PluginManager {
+        &mut self.manager
+    }
+
+    /// Get the state of the tested plugin
+    ///
+    /// # Panics
+    ///
+    /// Panics if the plugin was uninstalled.
+    pub fn state(&self) -> &P::State {
+        self.manager
+            .get_plugin_state::<P>()
+            .expect("the tested plugin is installed")
+    }
+
+    pub fn state_mut(&mut self) -> &mut P::State {
+        self.manager
+            .get_plugin_state_mut::<P>()
+            .expect("the tested plugin is installed")
+    }
+
+    /// Dispatch an input and run the resulting tasks to completion
+    pub fn dispatch(&mut self, input: P::Input) {
+        let task = self.manager.update(self.handle.input(input));
+        self.run(task);
+    }
+
+    /// Deliver an internal message, such as one the plugin's subscription would
+    /// produce, and run the resulting tasks to completion
+    pub fn send(&mut self, message: P::Message) {
+        let task = self.manager.update(self.handle.message(message));
+        self.run(task);
+    }
+
+    /// Broadcast an event to the installed plugins and run the resulting tasks to
+    /// completion
+    pub fn broadcast<E: 'static + Send + Sync + Debug>(&mut self, event: E) {
+        let task = self.manager.broadcast(event);
+        self.run(task);
+    }
+
+    /// Send an input with [`PluginHandle::call`] and return the output it causes
+    pub fn call(&mut self, input: P::Input) -> Result<P::Output, CallError> {
+        let task = self.handle.call(input);
+        self.perform(task)
+            .pop()
+            .expect("a call resolves exactly once")
+    }
+
+    /// Run a task producing plugin messages, such as one created with
+    /// [`PluginHandle::dispatch`] or [`PluginManager::shutdown`], to completion
+    ///
+    /// # Panics
+    ///
+    /// Panics if no task makes progress within the timeout.
+    pub fn run(&mut self, task: Task<PluginMessage>) {
+        let _: Vec<()> = self.drive(events(task, Event::Message));
+    }
+
+    /// Run any task to completion and return the values it produced, routing the
+    /// plugin messages sent along the way
+    ///
+    /// # Panics
+    ///
+    /// Panics if no task makes progress within the timeout.
+    pub fn perform<T: Send + 'static>(&mut self, task: Task<T>) -> Vec<T> {
+        self.drive(events(task, Event::Value))
+    }
+
+    /// Get all outputs the tested plugin emitted so far
+    pub fn outputs(&self) -> Vec<P::Output> {
+        let plugin_index = self.handle.plugin_index();
+        self.outputs
+            .0
+            .lock()
+            .map(|outputs| {
+                outputs
+                    .iter()
+                    .filter(|output| output.plugin_index() == plugin_index)
+                    .filter_map(|output| output.downcast::<P::Output>().cloned())
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Get the outputs the tested plugin emitted since the last call
+    pub fn take_outputs(&mut self) -> Vec<P::Output> {
+        let plugin_index = self.handle.plugin_index();
+        let taken: Vec<PluginOutput> = self
+            .outputs
+            .0
+            .lock()
+            .map(|mut outputs| {
+                let (taken, kept) = outputs
+                    .drain(..)
+                    .partition(|output| output.plugin_index() == plugin_index);
+                *outputs = kept;
+                taken
+            })
+            .unwrap_or_default();
+
+        taken
+            .iter()
+            .filter_map(|output| output.downcast::<P::Output>().cloned())
+            .collect()
+    }
+
+    /// Run tasks until all of them finished, routing their messages and the ones sent
+    /// through the inbox by [`PluginHandle::call`]
+    fn drive<T: Send + 'static>(&mut self, root: Option<BoxStream<'static, Event<T>>>) -> Vec<T> {
+        let Self {
+            manager,
+            inbox,
+            runtime,
+            timeout,
+            ..
+        } = self;
+
+        let mut streams = SelectAll::new();
+        let mut pending = 0;
+        let mut values = Vec::new();
+
+        if let Some(root) = root {
+            streams.push(root);
+            pending += 1;
+        }
+
+        loop {
+            let message = if pending == 0 {
+                // Nothing left to wait on, but calls may have queued messages
+                match inbox.try_recv() {
+                    Ok(message) => message,
+                    Err(_) => break,
+                }
+            } else {
+                // The timer needs the runtime, so create it inside
+                let next = runtime.block_on(async {
+                    tokio::time::timeout(*timeout, future::select(streams.next(), inbox.next()))
+                        .await
+                });
+
+                match next {
+                    Err(_) => panic!("plugin tasks made no progress within {:?}", timeout),
+                    Ok(Either::Left((Some(Event::Message(message)), _))) => message,
+                    Ok(Either::Left((Some(Event::Value(value)), _))) => {
+                        values.push(value);
+                        continue;
+                    }
+                    Ok(Either::Left((Some(Event::Finished), _))) => {
+                        pending -= 1;
+                        continue;
+                    }
+                    Ok(Either::Left((None, _))) => break,
+                    Ok(Either::Right((Some(message), _))) => message,
+                    Ok(Either::Right((None, _))) => continue,
+                }
+            };
+
+            if let Some(stream) = events(manager.update(message), Event::Message) {
+                streams.push(stream);
+                pending += 1;
+            }
+        }
+
+        values
+    }
+}
@@ -0,0 +1,67 @@
+use std::fmt::{self, Debug, Write};
+
+/// Longest variant name recorded, for `Debug` output without fields to stop at
+const MAX_VARIANT_LEN: usize = 64;
+
+/// Collects `Debug` output up to the end of the variant name
+struct VariantWriter(String);
+
+impl Write for VariantWriter {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        for c in s.chars() {
+            if matches!(c, '(' | ' ' | '{') || self.0.len() >= MAX_VARIANT_LEN {
+                // Stops the formatter before it formats the fields
+                return Err(fmt::Error);
+            }
+            self.0.push(c);
+        }
+        Ok(())
+    }
+}
+
+/// Name of the enum variant of a message, taken from its `Debug` output
+pub(crate) fn variant(message: &dyn Debug) -> String {
+    let mut writer = VariantWriter(String::new());
+    let _ = write!(writer, "{:?}", message);
+    writer.0
+}
+
+/// Record a message produced by a task of a plugin
+pub(crate) fn task_completed(plugin: &'static str, message: &dyn Debug) {
+    tracing::debug!(plugin, message = %variant(message), "task completed");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Panics when formatted, like a field that is expensive to format
+    struct Expensive;
+
+    impl Debug for Expensive {
+        fn fmt(&self, _f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            panic!("formatted a field");
+        }
+    }
+
+    #[allow(dead_code)]
+    #[derive(Debug)]
+    enum Message {
+        Tick,
+        Add(Expensive),
+        Set { value: Expensive },
+    }
+
+    #[test]
+    fn names_variants_without_formatting_fields() {
+        assert_eq!(variant(&Message::Tick), "Tick");
+        assert_eq!(variant(&Message::Add(Expensive)), "Add");
+        assert_eq!(variant(&Message::Set { value: Expensive }), "Set");
+    }
+
+    #[test]
+    fn bounds_the_name() {
+        let message = "x".repeat(1000);
+        assert_eq!(variant(&message).len(), MAX_VARIANT_LEN);
+    }
+}
@@ -0,0 +1,174 @@
+use iced::{Subscription, Task};
+use iced_plugins::{CallError, Plugin, PluginContext, PluginManagerBuilder, PluginTester};
+use std::time::Duration;
+
+/// Answers questions right away, later from a task, or never
+#[derive(Clone, Debug)]
+struct Echo;
+
+#[derive(Clone, Debug)]
+enum EchoMessage {
+    Now(u32),
+    Later(u32),
+    Reply(u32),
+    Never,
+}
+
+impl Plugin for Echo {
+    type Input = EchoMessage;
+    type Message = EchoMessage;
+    type State = ();
+    type Output = u32;
+
+    fn name(&self) -> &'static str {
+        "echo"
+    }
+
+    fn init(&self, _ctx: &mut PluginContext<'_>) -> (Self::State, Task<Self::Message>) {
+        ((), Task::none())
+    }
+
+    fn update(
+        &self,
+        _state: &mut Self::State,
+        message: Self::Message,
+        _ctx: &mut PluginContext<'_>,
+    ) -> (Task<Self::Message>, Option<Self::Output>) {
+        match message {
+            EchoMessage::Now(value) | EchoMessage::Reply(value) => (Task::none(), Some(value)),
+            EchoMessage::Later(value) => {
+                let reply = Task::future(async move {
+                    tokio::time::sleep(Duration::from_millis(10 * u64::from(value))).await;
+                    EchoMessage::Reply(value)
+                });
+                (reply, None)
+            }
+            EchoMessage::Never => (Task::none(), None),
+        }
+    }
+
+    fn subscription(&self, _state: &Self::State) -> Subscription<Self::Message> {
+        Subscription::none()
+    }
+}
+
+/// Asks the echo plugin through its context and reports the answer
+#[derive(Clone, Debug)]
+struct Asker;
+
+#[derive(Clone, Debug)]
+enum AskerMessage {
+    Ask(EchoMessage),
+    Answered(Result<u32, CallError>),
+}
+
+impl Plugin for Asker {
+    type Input = AskerMessage;
+    type Message = AskerMessage;
+    type State = ();
+    type Output = Result<u32, CallError>;
+
+    fn name(&self) -> &'static str {
+        "asker"
+    }
+
+    fn init(&self, _ctx: &mut PluginContext<'_>) -> (Self::State, Task<Self::Message>) {
+        ((), Task::none())
+    }
+
+    fn update(
+        &self,
+        _state: &mut Self::State,
+        message: Self::Message,
+        ctx: &mut PluginContext<'_>,
+    ) -> (Task<Self::Message>, Option<Self::Output>) {
+        match message {
+            AskerMessage::Ask(question) => {
+                let task = ctx.call::<Echo>(question).map(AskerMessage::Answered);
+                (task, None)
+            }
+            AskerMessage::Answered(answer) => (Task::none(), Some(answer)),
+        }
+    }
+
+    fn subscription(&self, _state: &Self::State) -> Subscription<Self::Message> {
+        Subscription::none()
+    }
+}
+
+#[test]
+fn call_resolves_with_the_output() {
+    let mut tester = PluginTester::new(Echo);
+
+    assert_eq!(tester.call(EchoMessage::Now(1)), Ok(1));
+}
+
+#[test]
+fn call_resolves_with_the_output_of_a_later_task() {
+    let mut tester = PluginTester::new(Echo);
+
+    assert_eq!(tester.call(EchoMessage::Later(2)), Ok(2));
+}
+
+#[test]
+fn concurrent_calls_get_their_own_outputs() {
+    let mut tester = PluginTester::new(Echo);
+    let handle = tester.handle().clone();
+
+    // The slowest call is sent first, so outputs arrive in reverse order
+    let calls = [3, 2, 1].map(|value| {
+        handle
+            .call(EchoMessage::Later(value))
+            .map(move |result| (value, result))
+    });
+    let mut results = tester.perform(Task::batch(calls));
+    results.sort_by_key(|(value, _)| *value);
+
+    assert_eq!(results, [(1, Ok(1)), (2, Ok(2)), (3, Ok(3))]);
+}
+
+#[test]
+fn call_times_out_without_output() {
+    let mut tester = PluginTester::new(Echo);
+    let call = tester
+        .handle()
+        .call_with_timeout(EchoMessage::Never, Duration::from_millis(20));
+
+    assert_eq!(tester.perform(call), [Err(CallError::Timeout)]);
+}
+
+#[test]
+fn call_to_disabled_plugin_is_unavailable() {
+    let mut tester = PluginTester::new(Echo);
+    let handle = tester.handle().clone();
+    tester.manager_mut().disable(&handle).unwrap();
+
+    assert_eq!(
+        tester.call(EchoMessage::Now(1)),
+        Err(CallError::Unavailable)
+    );
+}
+
+#[test]
+fn plugins_call_each_other_through_the_context() {
+    let echo = PluginManagerBuilder::new().with_plugin(Echo);
+    let mut tester = PluginTester::with_builder(echo, Asker);
+
+    let asker = tester.handle().clone();
+    tester.run(Task::batch([
+        asker.dispatch(AskerMessage::Ask(EchoMessage::Later(1))),
+        asker.dispatch(AskerMessage::Ask(EchoMessage::Now(2))),
+    ]));
+
+    // Each answer reaches the call that asked for it, in the order they arrive
+    assert_eq!(tester.take_outputs(), [Ok(2), Ok(1)]);
+}
+
+#[test]
+fn context_call_to_missing_plugin_is_unavailable() {
+    let mut tester = PluginTester::new(Asker);
+
+    tester.dispatch(AskerMessage::Ask(EchoMessage::Now(1)));
+
+    assert_eq!(tester.take_outputs(), [Err(CallError::Unavailable)]);
+}
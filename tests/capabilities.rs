@@ -0,0 +1,185 @@
+use iced::{Subscription, Task};
+use iced_plugins::{
+    BuildError, CallError, Capability, Plugin, PluginContext, PluginManagerBuilder, PluginTester,
+};
+
+/// Keeps a value, standing in for a plugin that writes files
+#[derive(Clone, Debug)]
+struct Vault;
+
+impl Plugin for Vault {
+    type Input = u32;
+    type Message = u32;
+    type State = u32;
+    type Output = u32;
+
+    fn name(&self) -> &'static str {
+        "vault"
+    }
+
+    fn capabilities(&self) -> Vec<Capability> {
+        vec![Capability::FsWrite]
+    }
+
+    fn init(&self, _ctx: &mut PluginContext<'_>) -> (Self::State, Task<Self::Message>) {
+        (0, Task::none())
+    }
+
+    fn update(
+        &self,
+        state: &mut Self::State,
+        message: Self::Message,
+        _ctx: &mut PluginContext<'_>,
+    ) -> (Task<Self::Message>, Option<Self::Output>) {
+        *state = message;
+        (Task::none(), Some(message))
+    }
+
+    fn subscription(&self, _state: &Self::State) -> Subscription<Self::Message> {
+        Subscription::none()
+    }
+}
+
+/// Writes to the vault through its context, without declaring any capability
+#[derive(Clone, Debug)]
+struct Writer;
+
+#[derive(Clone, Debug)]
+enum WriterMessage {
+    Dispatch(u32),
+    Call(u32),
+    Called(Result<u32, CallError>),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum WriterOutput {
+    Dispatched(bool),
+    Called(Result<u32, CallError>),
+}
+
+impl Plugin for Writer {
+    type Input = WriterMessage;
+    type Message = WriterMessage;
+    type State = ();
+    type Output = WriterOutput;
+
+    fn name(&self) -> &'static str {
+        "writer"
+    }
+
+    fn init(&self, _ctx: &mut PluginContext<'_>) -> (Self::State, Task<Self::Message>) {
+        ((), Task::none())
+    }
+
+    fn update(
+        &self,
+        _state: &mut Self::State,
+        message: Self::Message,
+        ctx: &mut PluginContext<'_>,
+    ) -> (Task<Self::Message>, Option<Self::Output>) {
+        match message {
+            WriterMessage::Dispatch(value) => {
+                let dispatched = ctx.dispatch::<Vault>(value);
+                (Task::none(), Some(WriterOutput::Dispatched(dispatched)))
+            }
+            WriterMessage::Call(value) => {
+                let task = ctx.call::<Vault>(value).map(WriterMessage::Called);
+                (task, None)
+            }
+            WriterMessage::Called(result) => (Task::none(), Some(WriterOutput::Called(result))),
+        }
+    }
+
+    fn subscription(&self, _state: &Self::State) -> Subscription<Self::Message> {
+        Subscription::none()
+    }
+}
+
+fn writer(grants: &[Capability]) -> PluginTester<Writer> {
+    let vault = PluginManagerBuilder::new()
+        .with_plugin(Vault)
+        .with_capabilities::<Vault>([Capability::FsWrite])
+        .with_capabilities::<Writer>(grants.iter().copied());
+    PluginTester::with_builder(vault, Writer)
+}
+
+fn vault(tester: &PluginTester<Writer>) -> u32 {
+    *tester.manager().get_plugin_state::<Vault>().unwrap()
+}
+
+#[test]
+fn drops_dispatches_without_the_capabilities_of_the_target() {
+    let mut tester = writer(&[]);
+
+    tester.dispatch(WriterMessage::Dispatch(7));
+
+    assert_eq!(tester.take_outputs(), [WriterOutput::Dispatched(false)]);
+    assert_eq!(vault(&tester), 0);
+}
+
+#[test]
+fn refuses_calls_without_the_capabilities_of_the_target() {
+    let mut tester = writer(&[]);
+
+    tester.dispatch(WriterMessage::Call(7));
+
+    assert_eq!(
+        tester.take_outputs(),
+        [WriterOutput::Called(Err(CallError::MissingCapability(
+            Capability::FsWrite
+        )))]
+    );
+    assert_eq!(vault(&tester), 0);
+}
+
+#[test]
+fn delivers_with_the_capabilities_of_the_target() {
+    let mut tester = writer(&[Capability::FsWrite]);
+
+    tester.dispatch(WriterMessage::Dispatch(7));
+    tester.dispatch(WriterMessage::Call(8));
+
+    assert_eq!(
+        tester.take_outputs(),
+        [WriterOutput::Dispatched(true), WriterOutput::Called(Ok(8))]
+    );
+    assert_eq!(vault(&tester), 8);
+}
+
+#[test]
+fn refuses_declared_capabilities_that_were_not_granted() {
+    let result = PluginManagerBuilder::new().with_plugin(Vault).build();
+
+    assert!(matches!(
+        result,
+        Err(BuildError::CapabilityNotGranted { plugin: "vault", ref capabilities })
+            if capabilities == &[Capability::FsWrite]
+    ));
+}
+
+#[test]
+fn grants_capabilities_per_instance() {
+    let granted = PluginManagerBuilder::new()
+        .with_plugin_keyed("granted", Vault)
+        .with_capabilities_keyed::<Vault>("granted", [Capability::FsWrite])
+        .build();
+    assert!(granted.is_ok());
+
+    let other = PluginManagerBuilder::new()
+        .with_plugin_keyed("other", Vault)
+        .with_capabilities_keyed::<Vault>("granted", [Capability::FsWrite])
+        .build();
+    assert!(matches!(
+        other,
+        Err(BuildError::CapabilityNotGranted {
+            plugin: "vault",
+            ..
+        })
+    ));
+}
+
+#[test]
+#[should_panic(expected = "not granted")]
+fn tester_grants_no_capabilities() {
+    PluginTester::new(Vault);
+}
@@ -0,0 +1,51 @@
+use iced_plugins::PluginInput;
+
+#[derive(Clone, Debug, PartialEq, PluginInput)]
+#[input(derive(PartialEq))]
+enum CounterMessage {
+    /// Add one to the counter
+    Increment,
+    /// Add a number to the counter
+    Add(i32, i32),
+    /// Set the counter
+    Set { value: i32, label: String },
+    /// Tick of the counter's subscription
+    #[internal]
+    #[allow(dead_code)]
+    Tick,
+}
+
+#[derive(Clone, Debug, PartialEq, PluginInput)]
+#[input(name = Commands)]
+pub enum Events {
+    Quit,
+}
+
+#[test]
+fn converts_every_kind_of_variant() {
+    assert_eq!(
+        CounterMessage::from(CounterInput::Increment),
+        CounterMessage::Increment
+    );
+    assert_eq!(
+        CounterMessage::from(CounterInput::Add(1, 2)),
+        CounterMessage::Add(1, 2)
+    );
+    assert_eq!(
+        CounterMessage::from(CounterInput::Set {
+            value: 3,
+            label: "three".to_string(),
+        }),
+        CounterMessage::Set {
+            value: 3,
+            label: "three".to_string(),
+        }
+    );
+}
+
+#[test]
+fn adds_requested_derives() {
+    assert_eq!(CounterInput::Add(1, 2), CounterInput::Add(1, 2));
+    assert_eq!(format!("{:?}", Commands::Quit), "Quit");
+    assert_eq!(Events::from(Commands::Quit), Events::Quit);
+}
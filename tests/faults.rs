@@ -0,0 +1,220 @@
+use iced::futures::future::BoxFuture;
+use iced::widget::text;
+use iced::{Element, Subscription, Task};
+use iced_plugins::{LifecycleError, Plugin, PluginContext, PluginStatus, PluginTester};
+
+/// The hook a [`Fragile`] plugin panics in
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Hook {
+    Init,
+    InitAsync,
+    LoadFuture,
+    View,
+    Shutdown,
+}
+
+#[derive(Clone, Debug)]
+struct Fragile(Hook);
+
+impl Fragile {
+    fn check(&self, hook: Hook) {
+        if self.0 == hook {
+            panic!("{:?} failed", hook);
+        }
+    }
+}
+
+impl Plugin for Fragile {
+    type Input = ();
+    type Message = ();
+    type State = &'static str;
+    type Output = ();
+
+    fn name(&self) -> &'static str {
+        "fragile"
+    }
+
+    fn init(&self, _ctx: &mut PluginContext<'_>) -> (Self::State, Task<Self::Message>) {
+        self.check(Hook::Init);
+        ("initialized", Task::none())
+    }
+
+    fn init_async(&self, _ctx: &mut PluginContext<'_>) -> Option<BoxFuture<'static, Self::State>> {
+        self.check(Hook::InitAsync);
+        let plugin = self.clone();
+        Some(Box::pin(async move {
+            plugin.check(Hook::LoadFuture);
+            "loaded"
+        }))
+    }
+
+    fn update(
+        &self,
+        _state: &mut Self::State,
+        _message: Self::Message,
+        _ctx: &mut PluginContext<'_>,
+    ) -> (Task<Self::Message>, Option<Self::Output>) {
+        (Task::none(), None)
+    }
+
+    fn subscription(&self, _state: &Self::State) -> Subscription<Self::Message> {
+        Subscription::none()
+    }
+
+    fn view<'a>(&'a self, state: &'a Self::State) -> Option<Element<'a, Self::Message>> {
+        self.check(Hook::View);
+        Some(text(*state).into())
+    }
+
+    fn shutdown(
+        &self,
+        _state: &mut Self::State,
+        _ctx: &mut PluginContext<'_>,
+    ) -> Task<Self::Message> {
+        self.check(Hook::Shutdown);
+        Task::none()
+    }
+}
+
+/// Faults itself through its context instead of panicking
+#[derive(Clone, Debug)]
+struct Quitter {
+    quit_in_init: bool,
+}
+
+impl Plugin for Quitter {
+    type Input = bool;
+    type Message = bool;
+    type State = u32;
+    type Output = u32;
+
+    fn name(&self) -> &'static str {
+        "quitter"
+    }
+
+    fn init(&self, ctx: &mut PluginContext<'_>) -> (Self::State, Task<Self::Message>) {
+        if self.quit_in_init {
+            ctx.fault("gave up in init");
+        }
+        (0, Task::none())
+    }
+
+    fn update(
+        &self,
+        state: &mut Self::State,
+        quit: Self::Message,
+        ctx: &mut PluginContext<'_>,
+    ) -> (Task<Self::Message>, Option<Self::Output>) {
+        *state += 1;
+        if quit {
+            // Dropped along with the output
+            ctx.dispatch::<Quitter>(false);
+            ctx.fault("gave up");
+            ctx.fault("second reason");
+        }
+        (Task::none(), Some(*state))
+    }
+
+    fn subscription(&self, _state: &Self::State) -> Subscription<Self::Message> {
+        Subscription::none()
+    }
+}
+
+fn status<P: Plugin + 'static>(tester: &PluginTester<P>) -> PluginStatus {
+    tester.manager().status(tester.handle())
+}
+
+fn faulted(hook: Hook) -> PluginStatus {
+    PluginStatus::Faulted {
+        reason: format!("{:?} failed", hook),
+    }
+}
+
+#[test]
+fn faults_plugins_panicking_in_init() {
+    let mut tester = PluginTester::new(Fragile(Hook::Init));
+    assert_eq!(status(&tester), faulted(Hook::Init));
+
+    let handle = tester.handle().clone();
+    assert_eq!(
+        tester.manager_mut().enable(&handle),
+        Err(LifecycleError::NotInitialized { plugin: "fragile" })
+    );
+}
+
+#[test]
+fn faults_plugins_panicking_in_init_async() {
+    let tester = PluginTester::new(Fragile(Hook::InitAsync));
+    assert_eq!(status(&tester), faulted(Hook::InitAsync));
+    assert_eq!(*tester.state(), "initialized");
+}
+
+#[test]
+fn faults_plugins_whose_load_future_panics() {
+    let mut tester = PluginTester::new(Fragile(Hook::LoadFuture));
+    assert_eq!(status(&tester), faulted(Hook::LoadFuture));
+    assert_eq!(*tester.state(), "initialized");
+
+    // Faulted plugins keep the state they have and can run again
+    let handle = tester.handle().clone();
+    assert_eq!(tester.manager_mut().enable(&handle), Ok(()));
+}
+
+#[test]
+fn faults_plugins_panicking_in_view() {
+    let tester = PluginTester::new(Fragile(Hook::View));
+    assert!(tester.manager().view_of::<Fragile>().is_none());
+    assert_eq!(status(&tester), faulted(Hook::View));
+}
+
+#[test]
+fn faults_plugins_panicking_in_shutdown() {
+    let mut tester = PluginTester::new(Fragile(Hook::Shutdown));
+    let shutdown = tester.manager_mut().shutdown();
+    tester.run(shutdown);
+    assert_eq!(status(&tester), faulted(Hook::Shutdown));
+}
+
+#[test]
+fn keeps_working_plugins_running() {
+    let tester = PluginTester::new(Fragile(Hook::Shutdown));
+    assert!(tester.manager().view_of::<Fragile>().is_some());
+    assert_eq!(status(&tester), PluginStatus::Enabled);
+    assert_eq!(*tester.state(), "loaded");
+}
+
+#[test]
+fn faults_plugins_through_their_context() {
+    let mut tester = PluginTester::new(Quitter {
+        quit_in_init: false,
+    });
+
+    tester.dispatch(false);
+    tester.dispatch(true);
+
+    assert_eq!(
+        status(&tester),
+        PluginStatus::Faulted {
+            reason: "gave up".to_string()
+        }
+    );
+    assert_eq!(tester.take_outputs(), [1]);
+    assert_eq!(*tester.state(), 2);
+}
+
+#[test]
+fn faults_plugins_through_their_context_in_init() {
+    let mut tester = PluginTester::new(Quitter { quit_in_init: true });
+    assert_eq!(
+        status(&tester),
+        PluginStatus::Faulted {
+            reason: "gave up in init".to_string()
+        }
+    );
+
+    let handle = tester.handle().clone();
+    assert_eq!(
+        tester.manager_mut().enable(&handle),
+        Err(LifecycleError::NotInitialized { plugin: "quitter" })
+    );
+}
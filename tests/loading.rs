@@ -0,0 +1,92 @@
+use iced::futures::future::BoxFuture;
+use iced::{Subscription, Task};
+use iced_plugins::{
+    Plugin, PluginContext, PluginManager, PluginManagerBuilder, ReplayError, SnapshotError,
+};
+use serde::{Deserialize, Serialize};
+
+/// Counts the messages it gets, after an `init_async` future if `slow`
+#[derive(Debug)]
+struct Counter {
+    slow: bool,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct Increment;
+
+impl Plugin for Counter {
+    type Input = Increment;
+    type Message = Increment;
+    type State = u32;
+    type Output = ();
+
+    fn name(&self) -> &'static str {
+        "counter"
+    }
+
+    fn init(&self, _ctx: &mut PluginContext<'_>) -> (Self::State, Task<Self::Message>) {
+        (0, Task::none())
+    }
+
+    fn init_async(&self, _ctx: &mut PluginContext<'_>) -> Option<BoxFuture<'static, Self::State>> {
+        self.slow
+            .then(|| Box::pin(async { 0_u32 }) as BoxFuture<'static, u32>)
+    }
+
+    fn update(
+        &self,
+        state: &mut Self::State,
+        _message: Self::Message,
+        _ctx: &mut PluginContext<'_>,
+    ) -> (Task<Self::Message>, Option<Self::Output>) {
+        *state += 1;
+        (Task::none(), None)
+    }
+
+    fn subscription(&self, _state: &Self::State) -> Subscription<Self::Message> {
+        Subscription::none()
+    }
+}
+
+/// Builds a manager without running its init task, like replays do
+fn manager(slow: bool) -> PluginManager {
+    let mut builder = PluginManagerBuilder::new()
+        .with_replay::<Counter>()
+        .with_snapshot::<Counter>();
+    builder.install(Counter { slow });
+    let (manager, _) = builder.build().expect("manager builds");
+    manager
+}
+
+#[test]
+fn refuses_to_replay_while_plugins_are_loading() {
+    let mut manager = manager(true);
+
+    let error = manager.replay(&b""[..]).unwrap_err();
+
+    assert!(matches!(
+        error,
+        ReplayError::NotReady { ref plugins } if plugins == &["counter"]
+    ));
+}
+
+#[test]
+fn refuses_to_restore_loading_plugins() {
+    let snapshot = manager(false).snapshot().expect("state serializes");
+
+    let error = manager(true).restore(&snapshot).unwrap_err();
+
+    assert_eq!(
+        error,
+        SnapshotError::NotReady {
+            plugins: vec!["counter"]
+        }
+    );
+}
+
+#[test]
+fn restores_ready_plugins() {
+    let snapshot = manager(false).snapshot().expect("state serializes");
+
+    assert_eq!(manager(false).restore(&snapshot), Ok(1));
+}
@@ -0,0 +1,126 @@
+use iced::{Subscription, Task};
+use iced_plugins::{
+    Interceptor, MAX_DISPATCH_DEPTH, Plugin, PluginContext, PluginHandle, PluginManagerBuilder,
+    PluginMessage,
+};
+use std::sync::{Arc, Mutex};
+
+/// Logs every message it handles, then dispatches one to each of its targets
+#[derive(Debug)]
+struct Relay {
+    name: &'static str,
+    targets: Vec<&'static str>,
+    log: Arc<Mutex<Vec<&'static str>>>,
+}
+
+impl Relay {
+    fn new(
+        name: &'static str,
+        targets: &[&'static str],
+        log: &Arc<Mutex<Vec<&'static str>>>,
+    ) -> Self {
+        Self {
+            name,
+            targets: targets.to_vec(),
+            log: Arc::clone(log),
+        }
+    }
+}
+
+impl Plugin for Relay {
+    type Input = ();
+    type Message = ();
+    type State = ();
+    type Output = ();
+
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn init(&self, _ctx: &mut PluginContext<'_>) -> (Self::State, Task<Self::Message>) {
+        ((), Task::none())
+    }
+
+    fn update(
+        &self,
+        _state: &mut Self::State,
+        _message: Self::Message,
+        ctx: &mut PluginContext<'_>,
+    ) -> (Task<Self::Message>, Option<Self::Output>) {
+        self.log.lock().unwrap().push(self.name);
+        for target in &self.targets {
+            ctx.dispatch_keyed::<Relay>(target, ());
+        }
+        (Task::none(), None)
+    }
+
+    fn subscription(&self, _state: &Self::State) -> Subscription<Self::Message> {
+        Subscription::none()
+    }
+}
+
+#[test]
+fn delivers_dispatched_messages_depth_first() {
+    let log = Arc::default();
+    let mut builder = PluginManagerBuilder::new()
+        .with_plugin_keyed("b", Relay::new("b", &["d"], &log))
+        .with_plugin_keyed("c", Relay::new("c", &[], &log))
+        .with_plugin_keyed("d", Relay::new("d", &[], &log));
+    let a = builder.install_keyed("a", Relay::new("a", &["b", "c"], &log));
+    let (mut manager, _) = builder.build().unwrap();
+
+    let _ = manager.update(a.input(()));
+
+    assert_eq!(*log.lock().unwrap(), ["a", "b", "d", "c"]);
+}
+
+#[test]
+fn cuts_off_dispatch_loops() {
+    let log = Arc::default();
+    let mut builder =
+        PluginManagerBuilder::new().with_plugin_keyed("pong", Relay::new("pong", &["ping"], &log));
+    let ping = builder.install_keyed("ping", Relay::new("ping", &["pong"], &log));
+    let (mut manager, _) = builder.build().unwrap();
+
+    let _ = manager.update(ping.input(()));
+
+    // The first message, then every dispatch up to the maximum depth
+    assert_eq!(log.lock().unwrap().len(), MAX_DISPATCH_DEPTH + 1);
+}
+
+/// Sends every message for one plugin to another one
+struct Redirect {
+    from: usize,
+    to: PluginHandle<Relay>,
+}
+
+impl Interceptor for Redirect {
+    fn on_message(
+        &mut self,
+        _plugin: &'static str,
+        message: PluginMessage,
+    ) -> Option<PluginMessage> {
+        if message.plugin_index() == self.from {
+            Some(self.to.input(()))
+        } else {
+            Some(message)
+        }
+    }
+}
+
+#[test]
+fn delivers_retargeted_messages_to_their_new_plugin() {
+    let log = Arc::default();
+    let mut builder = PluginManagerBuilder::new();
+    let a = builder.install_keyed("a", Relay::new("a", &[], &log));
+    let b = builder.install_keyed("b", Relay::new("b", &[], &log));
+    let redirect = Redirect {
+        from: a.input(()).plugin_index(),
+        to: b,
+    };
+    let (mut manager, _) = builder.with_interceptor(redirect).build().unwrap();
+
+    let _ = manager.update(a.input(()));
+
+    assert_eq!(*log.lock().unwrap(), ["b"]);
+}
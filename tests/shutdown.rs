@@ -0,0 +1,96 @@
+use iced::{Subscription, Task};
+use iced_plugins::{Plugin, PluginContext, PluginManagerBuilder, PluginStatus, PluginTester};
+use std::sync::{Arc, Mutex};
+
+/// Logs its label when it shuts down, and faults on request
+#[derive(Debug)]
+struct Closer {
+    label: &'static str,
+    log: Arc<Mutex<Vec<&'static str>>>,
+}
+
+impl Closer {
+    fn new(label: &'static str, log: &Arc<Mutex<Vec<&'static str>>>) -> Self {
+        Self {
+            label,
+            log: Arc::clone(log),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+struct Fault;
+
+impl Plugin for Closer {
+    type Input = Fault;
+    type Message = Fault;
+    type State = ();
+    type Output = ();
+
+    fn name(&self) -> &'static str {
+        "closer"
+    }
+
+    fn init(&self, _ctx: &mut PluginContext<'_>) -> (Self::State, Task<Self::Message>) {
+        ((), Task::none())
+    }
+
+    fn update(
+        &self,
+        _state: &mut Self::State,
+        _message: Self::Message,
+        ctx: &mut PluginContext<'_>,
+    ) -> (Task<Self::Message>, Option<Self::Output>) {
+        ctx.fault("asked to fault");
+        (Task::none(), None)
+    }
+
+    fn subscription(&self, _state: &Self::State) -> Subscription<Self::Message> {
+        Subscription::none()
+    }
+
+    fn shutdown(
+        &self,
+        _state: &mut Self::State,
+        _ctx: &mut PluginContext<'_>,
+    ) -> Task<Self::Message> {
+        self.log.lock().unwrap().push(self.label);
+        Task::none()
+    }
+}
+
+fn shutdown<P: Plugin + 'static>(tester: &mut PluginTester<P>) {
+    let task = tester.manager_mut().shutdown();
+    tester.run(task);
+}
+
+#[test]
+fn runs_each_hook_once() {
+    let log = Arc::new(Mutex::new(Vec::new()));
+    let mut tester = PluginTester::new(Closer::new("closer", &log));
+
+    shutdown(&mut tester);
+    shutdown(&mut tester);
+
+    assert_eq!(*log.lock().unwrap(), ["closer"]);
+}
+
+#[test]
+fn skips_disabled_and_faulted_plugins() {
+    let log = Arc::new(Mutex::new(Vec::new()));
+    let mut builder = PluginManagerBuilder::new();
+    let disabled = builder.install_keyed("disabled", Closer::new("disabled", &log));
+    builder.install_keyed("enabled", Closer::new("enabled", &log));
+    let mut tester = PluginTester::with_builder(builder, Closer::new("faulted", &log));
+
+    tester.manager_mut().disable(&disabled).unwrap();
+    tester.dispatch(Fault);
+    assert!(matches!(
+        tester.manager().status(tester.handle()),
+        PluginStatus::Faulted { .. }
+    ));
+
+    shutdown(&mut tester);
+
+    assert_eq!(*log.lock().unwrap(), ["enabled"]);
+}